@@ -1,9 +1,65 @@
 extern crate vulkano_shaders;
 
+use std::env;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use vulkano_shaders::ShaderType;
+
+/// The shaders compiled into the executable.
+const SHADERS: [(&'static str, ShaderType); 13] = [
+    ("src/vs.glsl", ShaderType::Vertex),
+    ("src/fullscreen_vs.glsl", ShaderType::Vertex),
+    ("src/fs.glsl", ShaderType::Fragment),
+    ("src/cs.glsl", ShaderType::Compute),
+    ("src/blit_vs.glsl", ShaderType::Vertex),
+    ("src/blit_fs.glsl", ShaderType::Fragment),
+    ("src/id_fs.glsl", ShaderType::Fragment),
+    ("src/background_fs.glsl", ShaderType::Fragment),
+    ("src/accumulate_fs.glsl", ShaderType::Fragment),
+    ("src/graph_vs.glsl", ShaderType::Vertex),
+    ("src/graph_fs.glsl", ShaderType::Fragment),
+    ("src/mesh_vs.glsl", ShaderType::Vertex),
+    ("src/mesh_fs.glsl", ShaderType::Fragment),
+];
+
+/// The version of the `shaderc` crate in `Cargo.lock`, which the compiler linked into the
+/// executable comes with.
+fn shaderc_version() -> Option<String> {
+    let lock = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("Cargo.lock");
+    let mut contents = String::new();
+
+    if File::open(lock).and_then(|mut file| file.read_to_string(&mut contents)).is_err() {
+        return None;
+    }
+
+    // Each package lists its name and then its version.
+    let mut lines = contents.lines().map(str::trim);
+
+    while let Some(line) = lines.next() {
+        if line == "name = \"shaderc\"" {
+            return lines.next()
+                .and_then(|line| line.split('"').nth(1))
+                .map(|version| version.to_string());
+        }
+    }
+
+    None
+}
+
 fn main() {
+    // Keys the cache of compiled shaders, see `shader::cache_key`.
+    println!("cargo:rustc-env=SUBMANIFOLD_SHADERC_VERSION={}",
+             shaderc_version().unwrap_or_else(|| "unknown".to_string()));
+
+    // The version is read from the lockfile, and the shaders are compiled from their sources,
+    // which aren't otherwise known to cargo.
+    println!("cargo:rerun-if-changed=Cargo.lock");
+
+    for &(path, _) in SHADERS.iter() {
+        println!("cargo:rerun-if-changed={}", path);
+    }
+
     // building the shaders used in the examples
-    vulkano_shaders::build_glsl_shaders([
-        ("src/vs.glsl", vulkano_shaders::ShaderType::Vertex),
-        ("src/fs.glsl", vulkano_shaders::ShaderType::Fragment),
-    ].iter().cloned());
+    vulkano_shaders::build_glsl_shaders(SHADERS.iter().cloned());
 }
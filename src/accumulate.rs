@@ -0,0 +1,295 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use vulkano::buffer::BufferUsage;
+use vulkano::buffer::CpuAccessibleBuffer;
+use vulkano::command_buffer::DynamicState;
+use vulkano::command_buffer::PrimaryCommandBufferBuilder;
+use vulkano::command_buffer::PrimaryCommandBufferBuilderInlineDraw;
+use vulkano::descriptor::descriptor_set::DescriptorPool;
+use vulkano::device::Device;
+use vulkano::device::Queue;
+use vulkano::format::Format;
+use vulkano::framebuffer::Framebuffer;
+use vulkano::framebuffer::Subpass;
+use vulkano::image::Dimensions;
+use vulkano::image::StorageImage;
+use vulkano::pipeline::GraphicsPipeline;
+use vulkano::pipeline::GraphicsPipelineParams;
+use vulkano::pipeline::blend::Blend;
+use vulkano::pipeline::depth_stencil::DepthStencil;
+use vulkano::pipeline::input_assembly::InputAssembly;
+use vulkano::pipeline::multisample::Multisample;
+use vulkano::pipeline::vertex::SingleBufferDefinition;
+use vulkano::pipeline::viewport::ViewportsState;
+use vulkano::pipeline::viewport::Viewport;
+use vulkano::pipeline::viewport::Scissor;
+use vulkano::sampler::Filter;
+
+use config::Config;
+use error::Error;
+use memory::MemoryUsage;
+use render_pass::CustomRenderPass;
+use upscale;
+use upscale::BlitPipeline;
+use Vertex;
+
+/// The format of the image each sample is rendered into.
+const SAMPLE_FORMAT: Format = Format::R16G16B16A16Sfloat;
+/// The format of the images the average is kept in, precise enough for thousands of samples.
+const AVERAGE_FORMAT: Format = Format::R32G32B32A32Sfloat;
+
+mod accumulate_layout {
+    pipeline_layout! {
+        set0: {
+            sample_image: CombinedImageSampler,
+            previous: CombinedImageSampler,
+            weight: UniformBuffer<::accumulate_fs::ty::Weight>
+        }
+    }
+}
+
+type AccumulatePipeline = GraphicsPipeline<SingleBufferDefinition<Vertex>,
+                                           accumulate_layout::CustomPipeline, CustomRenderPass>;
+
+/// The offset of the position sample `sample` is taken at from the centers of the pixels, in
+/// pixels, spread evenly over the pixels by the Halton sequences of bases 2 and 3.
+///
+/// The first sample is taken at the centers, so that a scene that keeps changing isn't
+/// jittered.
+pub fn jitter(sample: u32) -> [f32; 2] {
+    if sample == 0 {
+        [0.0, 0.0]
+    } else {
+        [halton(sample, 2) - 0.5, halton(sample, 3) - 0.5]
+    }
+}
+
+/// The element `index` of the Halton sequence with `base`, between 0 and 1.
+fn halton(mut index: u32, base: u32) -> f32 {
+    let mut fraction = 1.0;
+    let mut result = 0.0;
+
+    while index > 0 {
+        fraction /= base as f32;
+        result += fraction * (index % base) as f32;
+        index /= base;
+    }
+
+    result
+}
+
+/// Averages the frames of a scene that doesn't change, each one sampled at a different position
+/// within the pixels, converging to an antialiased image.
+///
+/// Each sample is rendered offscreen, then blended into the average kept in one of two images,
+/// reading the average of the previous samples from the other one. The average is then copied
+/// into the swapchain image.
+pub struct Accumulator {
+    dimensions: [u32; 2],
+    render_pass: Arc<CustomRenderPass>,
+    framebuffer: Arc<Framebuffer<CustomRenderPass>>,
+    average_render_pass: Arc<CustomRenderPass>,
+    average_framebuffers: [Arc<Framebuffer<CustomRenderPass>>; 2],
+    accumulate_pipeline: Arc<AccumulatePipeline>,
+    /// Writing into the average image of the same index, reading the other one.
+    accumulate_sets: [Arc<accumulate_layout::set0::Set>; 2],
+    weight: Arc<CpuAccessibleBuffer<::accumulate_fs::ty::Weight>>,
+    blit_pipeline: Arc<BlitPipeline>,
+    blit_sets: [Arc<upscale::blit_layout::set0::Set>; 2],
+    /// The index of the average image that was written last.
+    current: usize,
+    samples: u32,
+    max_samples: u32,
+}
+
+impl Accumulator {
+    /// Creates the images for a swapchain with images of `target_dimensions`, to be drawn into
+    /// with `target_render_pass`.
+    pub fn new(device: &Arc<Device>, queue: &Arc<Queue>, descriptor_pool: &Arc<DescriptorPool>,
+               target_render_pass: &Arc<CustomRenderPass>, target_dimensions: [u32; 2],
+               config: &Config) -> Result<Accumulator, Error> {
+        let dimensions = upscale::scene_dimensions(target_dimensions, config);
+        let image = |format| {
+            StorageImage::new(device,
+                              Dimensions::Dim2d { width: dimensions[0], height: dimensions[1] },
+                              format, Some(queue.family()))
+                .map_err(|err| Error::OutOfMemory("the accumulation image", err))
+        };
+
+        let sample = image(SAMPLE_FORMAT)?;
+        let averages = [image(AVERAGE_FORMAT)?, image(AVERAGE_FORMAT)?];
+
+        let render_pass = CustomRenderPass::new(device, &::render_pass::Formats {
+            color: (SAMPLE_FORMAT, 1)
+        }).map_err(|err| Error::RenderPass("the sample render pass", err))?;
+        let average_render_pass = CustomRenderPass::new(device, &::render_pass::Formats {
+            color: (AVERAGE_FORMAT, 1)
+        }).map_err(|err| Error::RenderPass("the average render pass", err))?;
+
+        let framebuffer = Framebuffer::new(&render_pass, [dimensions[0], dimensions[1], 1],
+                                           ::render_pass::AList {
+                                               color: &sample
+                                           })
+            .map_err(|err| Error::Framebuffer("the sample framebuffer", err))?;
+        let average_framebuffer = |image| {
+            Framebuffer::new(&average_render_pass, [dimensions[0], dimensions[1], 1],
+                             ::render_pass::AList {
+                                 color: image
+                             })
+                .map_err(|err| Error::Framebuffer("the average framebuffer", err))
+        };
+        let average_framebuffers = [average_framebuffer(&averages[0])?,
+                                    average_framebuffer(&averages[1])?];
+
+        // Not every device can filter the float images of the averages linearly.
+        let sampler = upscale::create_sampler(device, Filter::Nearest)?;
+
+        let weight = CpuAccessibleBuffer::from_data(device, &BufferUsage::all(),
+                                                    Some(queue.family()),
+                                                    ::accumulate_fs::ty::Weight { weight: 1.0 })
+            .map_err(|err| Error::OutOfMemory("the weight buffer", err))?;
+
+        let vs = ::blit_vs::Shader::load(device)
+            .map_err(|err| Error::OutOfMemory("the vertex shader module", err))?;
+        let fs = ::accumulate_fs::Shader::load(device)
+            .map_err(|err| Error::OutOfMemory("the fragment shader module", err))?;
+
+        let accumulate_layout = accumulate_layout::CustomPipeline::new(device)
+            .map_err(|err| Error::OutOfMemory("the pipeline layout", err))?;
+
+        let accumulate_set = |previous| {
+            accumulate_layout::set0::Set::new(descriptor_pool, &accumulate_layout,
+                                              &accumulate_layout::set0::Descriptors {
+                                                  sample_image: (&sampler, &sample),
+                                                  previous: (&sampler, previous),
+                                                  weight: &weight,
+                                              })
+        };
+        let accumulate_sets = [accumulate_set(&averages[1]), accumulate_set(&averages[0])];
+
+        let accumulate_pipeline = GraphicsPipeline::new(device, GraphicsPipelineParams {
+            vertex_input: SingleBufferDefinition::new(),
+            vertex_shader: vs.main_entry_point(),
+            input_assembly: InputAssembly {
+                // The same geometry as the scene is drawn with.
+                topology: config.coverage.topology(),
+                primitive_restart_enable: false,
+            },
+            tessellation: None,
+            geometry_shader: None,
+            viewport: ViewportsState::Fixed {
+                data: vec![(
+                    Viewport {
+                        origin: [0.0, 0.0],
+                        depth_range: 0.0 .. 1.0,
+                        dimensions: [dimensions[0] as f32, dimensions[1] as f32],
+                    },
+                    Scissor::irrelevant()
+                )],
+            },
+            raster: Default::default(),
+            multisample: Multisample::disabled(),
+            fragment_shader: fs.main_entry_point(),
+            depth_stencil: DepthStencil::disabled(),
+            blend: Blend::pass_through(),
+            layout: &accumulate_layout,
+            render_pass: Subpass::from(&average_render_pass, 0).ok_or_else(Error::no_subpass)?,
+        }).map_err(|err| Error::GraphicsPipeline("the accumulation pipeline", err))?;
+
+        let (blit_layout, blit_pipeline) = upscale::create_blit_pipeline(device, config,
+                                                                         target_render_pass,
+                                                                         target_dimensions,
+                                                                         dimensions)?;
+        let blit_set = |image| {
+            upscale::blit_layout::set0::Set::new(descriptor_pool, &blit_layout,
+                                                 &upscale::blit_layout::set0::Descriptors {
+                                                     source: (&sampler, image)
+                                                 })
+        };
+        let blit_sets = [blit_set(&averages[0]), blit_set(&averages[1])];
+
+        Ok(Accumulator {
+            dimensions: dimensions,
+            render_pass: render_pass,
+            framebuffer: framebuffer,
+            average_render_pass: average_render_pass,
+            average_framebuffers: average_framebuffers,
+            accumulate_pipeline: accumulate_pipeline,
+            accumulate_sets: accumulate_sets,
+            weight: weight,
+            blit_pipeline: blit_pipeline,
+            blit_sets: blit_sets,
+            current: 0,
+            samples: 0,
+            max_samples: config.accumulate.unwrap_or(1),
+        })
+    }
+
+    /// The dimensions the scene is rendered at.
+    pub fn dimensions(&self) -> [u32; 2] {
+        self.dimensions
+    }
+
+    /// Adds the sample and average images to `usage`.
+    pub fn memory_usage(&self, usage: &mut MemoryUsage) {
+        usage.image("accumulation sample image", 1, self.dimensions, SAMPLE_FORMAT);
+        usage.image("accumulation average images", 2, self.dimensions, AVERAGE_FORMAT);
+    }
+
+    /// The render pass each sample has to be drawn with.
+    pub fn render_pass(&self) -> &Arc<CustomRenderPass> {
+        &self.render_pass
+    }
+
+    /// The framebuffer each sample has to be drawn into.
+    pub fn framebuffer(&self) -> &Arc<Framebuffer<CustomRenderPass>> {
+        &self.framebuffer
+    }
+
+    /// The number of samples averaged so far.
+    pub fn samples(&self) -> u32 {
+        self.samples
+    }
+
+    /// Whether the average hasn't converged yet, so that another sample should be drawn.
+    pub fn needs_sample(&self) -> bool {
+        self.samples < self.max_samples
+    }
+
+    /// Starts over with the next sample, after the scene has changed.
+    pub fn reset(&mut self) {
+        self.samples = 0;
+    }
+
+    /// The offset of the position the next sample is taken at from the centers of the pixels,
+    /// in pixels, see `jitter`.
+    pub fn jitter(&self) -> [f32; 2] {
+        jitter(self.samples)
+    }
+
+    /// Records blending the sample drawn before with `render_pass` into the average.
+    pub fn record(&mut self, builder: PrimaryCommandBufferBuilder,
+                  vertex_buffer: &Arc<CpuAccessibleBuffer<[Vertex]>>)
+                  -> PrimaryCommandBufferBuilder {
+        self.current = 1 - self.current;
+        self.samples += 1;
+        self.weight.write(Duration::new(1, 0)).unwrap().weight = 1.0 / self.samples as f32;
+
+        builder.draw_inline(&self.average_render_pass, &self.average_framebuffers[self.current],
+                            ::render_pass::ClearValues {
+                                color: [0.0, 0.0, 0.0, 0.0]
+                            })
+               .draw(&self.accumulate_pipeline, vertex_buffer, &DynamicState::none(),
+                     &self.accumulate_sets[self.current], &())
+               .draw_end()
+    }
+
+    /// Draws the average, within a render pass on the swapchain image.
+    pub fn draw(&self, builder: PrimaryCommandBufferBuilderInlineDraw,
+                vertex_buffer: &Arc<CpuAccessibleBuffer<[Vertex]>>)
+                -> PrimaryCommandBufferBuilderInlineDraw {
+        builder.draw(&self.blit_pipeline, vertex_buffer, &DynamicState::none(),
+                     &self.blit_sets[self.current], &())
+    }
+}
@@ -0,0 +1,27 @@
+#version 450
+
+#extension GL_ARB_separate_shader_objects: enable
+#extension GL_ARB_shading_language_420pack: enable
+
+layout(set = 0, binding = 0) uniform sampler2D sample_image;
+layout(set = 0, binding = 1) uniform sampler2D previous;
+
+// The weight of the new sample in the average, 1 for the first one.
+layout(set = 0, binding = 2) uniform Weight {
+    float weight;
+} weight;
+
+layout(location = 0) in vec2 tex_coords;
+
+layout(location = 0) out vec4 f_color;
+
+void main() {
+    vec4 current = texture(sample_image, tex_coords);
+
+    // The previous average is undefined before the first sample.
+    if(weight.weight >= 1.0) {
+        f_color = current;
+    } else {
+        f_color = mix(texture(previous, tex_coords), current, weight.weight);
+    }
+}
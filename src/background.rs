@@ -0,0 +1,118 @@
+use std::sync::Arc;
+
+use vulkano::buffer::BufferUsage;
+use vulkano::buffer::CpuAccessibleBuffer;
+use vulkano::command_buffer::DynamicState;
+use vulkano::command_buffer::PrimaryCommandBufferBuilderInlineDraw;
+use vulkano::descriptor::descriptor_set::DescriptorPool;
+use vulkano::device::Device;
+use vulkano::device::Queue;
+use vulkano::framebuffer::Subpass;
+use vulkano::pipeline::GraphicsPipeline;
+use vulkano::pipeline::GraphicsPipelineParams;
+use vulkano::pipeline::blend::Blend;
+use vulkano::pipeline::depth_stencil::DepthStencil;
+use vulkano::pipeline::input_assembly::InputAssembly;
+use vulkano::pipeline::multisample::Multisample;
+use vulkano::pipeline::vertex::SingleBufferDefinition;
+use vulkano::pipeline::viewport::ViewportsState;
+use vulkano::pipeline::viewport::Viewport;
+use vulkano::pipeline::viewport::Scissor;
+
+use config::BackgroundKind;
+use config::Config;
+use error::Error;
+use render_pass::CustomRenderPass;
+use Vertex;
+
+mod background_layout {
+    pipeline_layout! {
+        set0: {
+            gradient: UniformBuffer<::background_fs::ty::Gradient>
+        }
+    }
+}
+
+type BackgroundPipeline = GraphicsPipeline<SingleBufferDefinition<Vertex>,
+                                           background_layout::CustomPipeline, CustomRenderPass>;
+
+/// A gradient drawn behind the scene, which shows through where the scene is transparent.
+pub struct Background {
+    pipeline: Arc<BackgroundPipeline>,
+    set: Arc<background_layout::set0::Set>,
+}
+
+impl Background {
+    /// Creates the background for the scene drawn with `render_pass` into images of
+    /// `dimensions`, or returns `None` if the background is a solid color.
+    pub fn new(device: &Arc<Device>, queue: &Arc<Queue>, descriptor_pool: &Arc<DescriptorPool>,
+               render_pass: &Arc<CustomRenderPass>, dimensions: [u32; 2], config: &Config)
+               -> Result<Option<Background>, Error> {
+        if config.background == BackgroundKind::Solid {
+            return Ok(None);
+        }
+
+        let vs = ::blit_vs::Shader::load(device)
+            .map_err(|err| Error::OutOfMemory("the vertex shader module", err))?;
+        let fs = ::background_fs::Shader::load(device)
+            .map_err(|err| Error::OutOfMemory("the fragment shader module", err))?;
+
+        let pipeline_layout = background_layout::CustomPipeline::new(device)
+            .map_err(|err| Error::OutOfMemory("the pipeline layout", err))?;
+
+        let gradient = CpuAccessibleBuffer::from_data(device, &BufferUsage::all(),
+                                                      Some(queue.family()),
+            ::background_fs::ty::Gradient {
+                first: config.background_colors[0],
+                second: config.background_colors[1],
+                radial: (config.background == BackgroundKind::Radial) as u32,
+            })
+            .map_err(|err| Error::OutOfMemory("the gradient buffer", err))?;
+
+        let set = background_layout::set0::Set::new(descriptor_pool, &pipeline_layout,
+                                                    &background_layout::set0::Descriptors {
+                                                        gradient: &gradient
+                                                    });
+
+        let pipeline = GraphicsPipeline::new(device, GraphicsPipelineParams {
+            vertex_input: SingleBufferDefinition::new(),
+            vertex_shader: vs.main_entry_point(),
+            input_assembly: InputAssembly {
+                // The same geometry as the scene is drawn with.
+                topology: config.coverage.topology(),
+                primitive_restart_enable: false,
+            },
+            tessellation: None,
+            geometry_shader: None,
+            viewport: ViewportsState::Fixed {
+                data: vec![(
+                    Viewport {
+                        origin: [0.0, 0.0],
+                        depth_range: 0.0 .. 1.0,
+                        dimensions: [dimensions[0] as f32, dimensions[1] as f32],
+                    },
+                    Scissor::irrelevant()
+                )],
+            },
+            raster: Default::default(),
+            multisample: Multisample::disabled(),
+            fragment_shader: fs.main_entry_point(),
+            depth_stencil: DepthStencil::disabled(),
+            blend: Blend::pass_through(),
+            layout: &pipeline_layout,
+            render_pass: Subpass::from(render_pass, 0).ok_or_else(Error::no_subpass)?,
+        }).map_err(|err| Error::GraphicsPipeline("the background pipeline", err))?;
+
+        Ok(Some(Background {
+            pipeline: pipeline,
+            set: set,
+        }))
+    }
+
+    /// Draws the background, within the render pass the scene is drawn in before the scene.
+    pub fn draw(&self, builder: PrimaryCommandBufferBuilderInlineDraw,
+                vertex_buffer: &Arc<CpuAccessibleBuffer<[Vertex]>>)
+                -> PrimaryCommandBufferBuilderInlineDraw {
+        builder.draw(&self.pipeline, vertex_buffer, &DynamicState::none(), &self.set, &())
+    }
+}
@@ -0,0 +1,28 @@
+#version 450
+
+#extension GL_ARB_separate_shader_objects: enable
+#extension GL_ARB_shading_language_420pack: enable
+
+// Vertical gradients go from `first` at the top to `second` at the bottom, radial ones from
+// `first` in the center to `second` in the corners.
+layout(set = 0, binding = 0) uniform Gradient {
+    vec4 first;
+    vec4 second;
+    uint radial;
+} gradient;
+
+layout(location = 0) in vec2 tex_coords;
+
+layout(location = 0) out vec4 f_color;
+
+void main() {
+    float t;
+
+    if(gradient.radial != 0) {
+        t = length(tex_coords - vec2(0.5)) * sqrt(2.0);
+    } else {
+        t = tex_coords.y;
+    }
+
+    f_color = mix(gradient.first, gradient.second, clamp(t, 0.0, 1.0));
+}
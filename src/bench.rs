@@ -0,0 +1,168 @@
+use std::sync::Arc;
+use std::time::Instant;
+
+use vulkano::device::Device;
+use vulkano::device::Queue;
+use vulkano::swapchain::Surface;
+
+use config::Config;
+use error::Error;
+use renderer::Renderer;
+use report::Report;
+use report::Timing;
+use shader::FragmentShader;
+use shader::VertexShader;
+use simulation::Time;
+
+/// How much slower the last quarter of the frames of a path may be than the first before the GPU
+/// is likely being throttled, as a fraction.
+const THROTTLING_SLOWDOWN: f32 = 0.1;
+
+/// The fewest frames a trend is looked for in.
+const THROTTLING_MIN_FRAMES: usize = 8;
+
+/// Renders `frames` frames of the same moment into the window with the graphics pipeline, then
+/// with the compute shader of `--compute-present`, and prints how long a frame took on each,
+/// returning it as a report too.
+///
+/// Both render the built-in scene the same way, with the reference objects, the debug tint and the
+/// heatmap, so that only the path differs. `--accumulate` and the fragment shaders of the user
+/// are rejected with it, as the compute path doesn't accumulate or run them. A frame is timed
+/// from its submission until the GPU has finished it, after `--warmup` frames that aren't.
+///
+/// Frames that get slower over the run of the same workload are flagged, as a GPU that heats up
+/// and lowers its clocks, typically on a laptop, makes the numbers misleading.
+pub fn compare_paths(device: &Arc<Device>, queue: &Arc<Queue>, present_queue: &Arc<Queue>,
+                     surface: &Arc<Surface>, config: &Config, refresh_hz: f32, frames: u32)
+                     -> Result<Report, Error> {
+    let time = Time::fixed(config.fixed_time.unwrap_or(0.0));
+    let mut timings = Vec::new();
+    let mut swapchain = None;
+
+    for &(name, compute) in &[("graphics", false), ("compute", true)] {
+        let config = Config { compute_present: compute, .. config.clone() };
+        let vs = VertexShader::load(device, &config).map_err(Error::Shader)?;
+        let fs = FragmentShader::load(device, &config).map_err(Error::Shader)?;
+
+        // The surface only holds one swapchain at a time, so the renderer of the previous path
+        // is dropped at the end of each iteration before this one is created.
+        let mut renderer = Renderer::new(device, queue, present_queue, surface, &config,
+                                         refresh_hz, vs, fs)?;
+
+        if renderer.compute_path() != compute {
+            warn!("skipping the {} path, the surface doesn't support it", name);
+            continue;
+        }
+
+        for _ in 0 .. config.warmup {
+            renderer.render(time, None)?;
+        }
+
+        renderer.wait();
+
+        let mut durations = Vec::with_capacity(frames as usize);
+
+        for _ in 0 .. frames {
+            let start = Instant::now();
+
+            renderer.render(time, None)?;
+            renderer.wait();
+
+            let elapsed = start.elapsed();
+            durations.push(elapsed.as_secs() as f32 * 1000.0 +
+                           elapsed.subsec_nanos() as f32 / 1_000_000.0);
+        }
+
+        let mean = durations.iter().sum::<f32>() / frames as f32;
+        let fastest = durations.iter().cloned().fold(::std::f32::INFINITY, f32::min);
+        let std_dev = (durations.iter().map(|duration| (duration - mean).powi(2)).sum::<f32>() /
+                       frames as f32).sqrt();
+        let throttling = throttling(&durations);
+
+        println!("{:<8} {:>8.3} ms mean {:>8.3} ms fastest {:>8.3} ms std dev over {} frames",
+                 name, mean, fastest, std_dev, frames);
+
+        if throttling {
+            warn!("the frames of the {} path got slower over the run, the GPU is likely being \
+                   throttled and the numbers are misleading", name);
+        }
+
+        timings.push(Timing {
+            path: name,
+            frames: frames,
+            mean_ms: mean,
+            fastest_ms: fastest,
+            std_dev_ms: std_dev,
+            throttling: throttling,
+        });
+        swapchain = Some((renderer.dimensions(), renderer.present_mode()));
+    }
+
+    // The graphics path is always available.
+    let (resolution, present_mode) = swapchain.expect("no path was rendered");
+
+    Ok(Report::new(device.physical_device(), resolution, present_mode, timings))
+}
+
+/// Whether the median of the last quarter of `durations` is slower than that of the first by
+/// more than `THROTTLING_SLOWDOWN`, a trend that occasional slow frames don't make.
+fn throttling(durations: &[f32]) -> bool {
+    if durations.len() < THROTTLING_MIN_FRAMES {
+        return false;
+    }
+
+    let quarter = durations.len() / 4;
+    let first = median(&durations[.. quarter]);
+    let last = median(&durations[durations.len() - quarter ..]);
+
+    last > first * (1.0 + THROTTLING_SLOWDOWN)
+}
+
+fn median(durations: &[f32]) -> f32 {
+    let mut sorted = durations.to_vec();
+
+    sorted.sort_by(|a, b| a.partial_cmp(b).expect("frame times are never NaN"));
+    sorted[sorted.len() / 2]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn median_takes_the_middle_frame() {
+        assert_eq!(median(&[3.0, 1.0, 2.0]), 2.0);
+        // Of an even number of frames, the upper of the middle two.
+        assert_eq!(median(&[4.0, 1.0, 3.0, 2.0]), 3.0);
+        assert_eq!(median(&[5.0]), 5.0);
+    }
+
+    #[test]
+    fn flat_frame_times_are_not_throttling() {
+        assert!(!throttling(&[10.0; THROTTLING_MIN_FRAMES]));
+        assert!(!throttling(&[10.0; 100]));
+
+        // A slowdown within the tolerance isn't either, nor a single slow frame at the end.
+        let slightly_slower = (0..16).map(|i| if i < 12 { 10.0 } else { 10.5 }).collect::<Vec<_>>();
+        let spike = (0..16).map(|i| if i == 15 { 50.0 } else { 10.0 }).collect::<Vec<_>>();
+
+        assert!(!throttling(&slightly_slower));
+        assert!(!throttling(&spike));
+    }
+
+    #[test]
+    fn rising_frame_times_are_throttling() {
+        let rising = (0..16).map(|i| 10.0 + i as f32).collect::<Vec<_>>();
+
+        assert!(throttling(&rising));
+    }
+
+    #[test]
+    fn short_runs_are_never_throttling() {
+        let rising = (0..THROTTLING_MIN_FRAMES - 1).map(|i| 10.0 * (i + 1) as f32)
+            .collect::<Vec<_>>();
+
+        assert!(!throttling(&rising));
+        assert!(!throttling(&[]));
+    }
+}
@@ -0,0 +1,13 @@
+#version 450
+
+#extension GL_ARB_separate_shader_objects: enable
+#extension GL_ARB_shading_language_420pack: enable
+
+layout(location = 0) in vec2 position;
+
+layout(location = 0) out vec2 tex_coords;
+
+void main() {
+    gl_Position = vec4(position.xy, 0.0, 1.0);
+    tex_coords = position * 0.5 + vec2(0.5);
+}
@@ -0,0 +1,1714 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use toml;
+
+use vulkano::pipeline::input_assembly::PrimitiveTopology;
+use vulkano::pipeline::raster::CullMode;
+use vulkano::pipeline::raster::FrontFace;
+use vulkano::sampler::Filter;
+use vulkano::swapchain::PresentMode;
+
+use view::View;
+
+/// The most bounces `--bounces` and the hotkeys allow, so that a shader's loop stays bounded.
+pub const MAX_BOUNCES: u32 = 16;
+
+pub const USAGE: &'static str = "\
+Usage: submanifold [OPTIONS]
+
+Options:
+    --config FILE        Read the initial values of the options below from the
+                         TOML file FILE, the flags given override them
+    --scene FILE         Read a TOML file like `--config` after it, and read it
+                         again whenever it or its `frag` shader changes: the
+                         camera, the light, the exposure, `frag`, `heatmap` and
+                         the `reference` options apply right away, a file that
+                         is invalid keeps the previous scene
+    --state STRING       Show the view of a string copied with Ctrl/Cmd+S: the
+                         camera, the light, the shader and the time, the flags
+                         given override it
+    --width PIXELS       The width of the rendered image [default: 1280]
+    --height PIXELS      The height of the rendered image [default: 1024]
+    --min-size WxH       The size the window can't be resized below
+    --max-size WxH       The size the window can't be resized above
+    --preview-window WxH Open a second window of WxH showing the same frames,
+                         such as a small preview next to a large output window,
+                         closing either quits and only the first takes input
+    --headless PATH      Render a single frame without opening a window and save
+                         it to PATH as a PNG
+    --montage PATH       Render the same frame as `--headless` on every device and
+                         save them in a grid labeled with the device names to
+                         PATH as a PNG, skipping the devices that fail
+    --sweep DIR          Render the same frame as `--headless` at each of the
+                         `--sweep-sizes` and save them to DIR as WxH.png,
+                         skipping the sizes that fail
+    --sweep-sizes WxH,...
+                         The sizes of `--sweep` [default: 1920x1080,1600x1200,
+                         2560x1080,1024x1024]
+    --cubemap DIR        Render the six faces of a cubemap around
+                         `--cubemap-center` as `--headless` and save them to DIR
+                         as px.png, nx.png, py.png, ny.png, pz.png and nz.png,
+                         facing +x, -x, +y, -y, +z and -z; the four sides have +z
+                         up, pz.png has -x up and nz.png +x
+    --cubemap-size N     The width and height of each face [default: 512]
+    --cubemap-center X,Y,Z
+                         Where the cubemap is captured from [default: the
+                         camera position]
+    --turntable PATH     Render `--frames` frames as `--headless` while the camera
+                         orbits the point it looks at `--revolutions` times, which
+                         loop seamlessly, and encode them into the video PATH,
+                         such as `turntable.mp4`, with `ffmpeg`. Without `ffmpeg`
+                         or an extension of PATH, they are saved as
+                         frame-0000.png and on into the directory PATH without
+                         its extension instead
+    --revolutions N      The number of orbits of `--turntable` [default: 1]
+    --frames N           The number of frames of `--turntable` [default: 120]
+    --screenshot-on-start PATH
+                         Save the first frame shown in the window to PATH as a
+                         PNG, then exit
+    --timecode           Write the frame number and the time at the bottom-left of
+                         the frames saved by `--headless` and
+                         `--screenshot-on-start`, to check the order of frames
+                         assembled into a video
+    --opaque-capture     Save the frames of `--headless`, `--screenshot-on-start`
+                         and the other captures fully opaque, instead of with the
+                         alpha the shader output for compositing them elsewhere
+    --no-clip            Render the parts of the window covered by other windows
+                         too, so that screenshots of them aren't undefined
+    --stream-port PORT   Serve the frames shown in the window as an MJPEG stream
+                         at http://HOST:PORT/, which browsers show like a video,
+                         skipping the frames a client is too slow to receive
+    --frag PATH          Compile the GLSL source at PATH as the fragment shader,
+                         which must have the same interface as `fs.glsl`
+    --spirv-frag PATH    Use the precompiled SPIR-V module at PATH as the fragment
+                         shader, it must have the same interface as `fs.glsl`
+    --frag-entry NAME    The entry point of the fragment shader module
+                         [default: main]
+    --spirv-vert PATH    Use the precompiled SPIR-V module at PATH as the vertex
+                         shader, it must have the same interface as `vs.glsl`
+    --vert-entry NAME    The entry point of the vertex shader module
+                         [default: main]
+    --hot-reload         Recompile the fragment shader whenever `fs.glsl` changes
+    --shader-dir PATH    The directory containing `fs.glsl` for hot-reload
+                         [default: the `src` directory of the build]
+    --define NAME[=VALUE]
+                         Define the macro NAME as VALUE, or as 1, when compiling
+                         the fragment shader, can be given repeatedly
+    --dump-spirv DIR     Write the SPIR-V of the vertex and fragment shaders to
+                         DIR, then exit
+    --shader-cache DIR   Keep the SPIR-V of the shaders compiled at runtime in DIR,
+                         so that they aren't compiled again until their source,
+                         the definitions or the compiler change [default:
+                         `submanifold/shaders` in `$XDG_CACHE_HOME` or
+                         `~/.cache`]
+    --no-shader-cache    Compile the shaders at runtime every time
+    --check-shader PATH  Compile the GLSL shader at PATH, or read it if it is
+                         SPIR-V (`.spv`), and check its entry point and its
+                         uniform block against `vs.glsl` without creating a
+                         device, then exit with 1 if it fails; vertex shaders
+                         are named `.vert` or `vs.glsl`, followed by `.spv` if
+                         compiled, others are fragment shaders, can be given
+                         more than once
+    --debug-tint         Tint the output faintly and show invalid colors in
+                         magenta, to tell whether the shader runs at all
+    --flip-y             Put the origin of `frag_coord()` and `iMouse` in `fs.glsl`
+                         at the bottom-left as in OpenGL and Shadertoy, instead
+                         of the top-left as in Vulkan
+    --coord-system KIND  The convention of the fragment shader the camera and the
+                         light are passed in, `z-up` (right-handed, +x forward,
+                         +y left, as `fs.glsl` and the other options), `z-up-lh`
+                         (y negated), `y-up` (right-handed as in OpenGL and
+                         three.js, z-up turned so that +z becomes +y and +y
+                         becomes -z) or `y-up-lh` (as in Unity and DirectX, y
+                         and z swapped), the built-in scene and `--mesh` expect
+                         `z-up` [default: z-up]
+    --heatmap            Show how many steps each pixel takes to trace as a
+                         heatmap instead of its color, H toggles it either way
+    --bounces N          How many times the secondary rays of shaders that trace
+                         reflections or refractions may bounce, at most 16, `]`
+                         adds one and `[` removes one [default: 2]
+    --pixel-scale N      Render at 1/N of the window resolution and scale the
+                         image up to it [default: 1]
+    --internal-res WxH   Render at exactly this resolution and scale it to fit
+                         the window, with black bars to keep the aspect ratio
+    --cam-pos X,Y,Z      Where the camera starts [default: 0,0,0]
+    --cam-look X,Y,Z     The point the camera starts looking at [default: 1,0,0]
+    --cam-up X,Y,Z       The direction that is up on the screen, the camera can't
+                         look along it [default: 0,0,1]
+    --fov DEGREES        The field of view across the diagonal of the image, less
+                         than 180 [default: 90]
+    --ortho              Render with an orthographic camera, its rays parallel
+                         from a plane through `--cam-pos`, 5 switches between it
+                         and the perspective one
+    --ortho-size SIZE    How wide the orthographic view is across the diagonal of
+                         the image, in units of the scene [default: as wide as the
+                         perspective view at `--cam-look`]
+    --sensitivity-x S    How far the camera moves per pixel the mouse moves
+                         horizontally, relative to following the cursor exactly
+                         [default: 1]
+    --sensitivity-y S    The same vertically [default: 1]
+    --invert-y           Move the camera the other way when the mouse moves up or
+                         down
+    --preset-transition SECONDS
+                         The time the camera takes to turn to a view chosen with
+                         the number keys, 1, 3 and 7 for the front, right and top
+                         ones, with Ctrl/Cmd for the opposite ones, and 0 for an
+                         isometric one, 0 snapping to it [default: 0.5]
+    --camera-damping RATE
+                         Let the camera dragged with Ctrl/Cmd held glide to a stop
+                         instead of following the mouse exactly, its speed
+                         falling by a factor of e every 1/RATE seconds, whatever
+                         the frame rate [default: off]
+    --light-dir X,Y,Z    The direction towards the light the shaders get, which
+                         moving the mouse with Alt held turns and K resets
+                         [default: -1,1,2]
+    --light-color RRGGBB The color of the light [default: ffffff]
+    --light-intensity I  The intensity of the light [default: 1]
+    --scale-filter FILTER
+                         How the image is scaled to the window, `nearest` (crisp
+                         pixels) or `linear` (smooth) [default: linear]
+    --accumulate N       While the time doesn't advance, average up to N frames
+                         sampled at jittered positions within the pixels, any
+                         key press starts over; without a window, each frame is
+                         the average of N such samples
+    --aa-compare PATH    Render the same frame as `--headless` once without
+                         anti-aliasing and once with the N samples of
+                         `--accumulate`, and save them side by side, labeled,
+                         to PATH as a PNG
+    --mrt                Render the color, normal, id and distance outputs of the
+                         fragment shader into separate images, Tab cycles the one
+                         shown and Ctrl/Cmd+F12 saves the distances as a grayscale
+                         PNG in the working directory. The id is a `uint`, and a
+                         right click prints the one under the cursor, 0 being
+                         nothing
+    --distance-range NEAR,FAR
+                         The distances saved as white and black, linearly in
+                         between [default: the nearest and farthest hit]
+    --letterbox SIZE     Ease in black bars of SIZE times the window height at
+                         the top and bottom on start, L toggles them either way
+                         [default size: 0.125]
+    --letterbox-duration SECONDS
+                         The time the bars take to ease in or out [default: 1]
+    --crosshair          Show a marker at the center of the window on start, X
+                         toggles it either way
+    --crosshair-color RRGGBB[AA]
+                         The color of the marker [default: ffffff80]
+    --crosshair-size PIXELS
+                         The length of each arm of the marker [default: 12]
+    --idle-timeout SECONDS
+                         After SECONDS without input, do what `--idle-behavior`
+                         says until the next input
+    --idle-behavior KIND What to do when idle, `orbit` (circle the camera around
+                         the point it looks at) or `letterbox` [default: orbit]
+    --motion-blur SAMPLES
+                         Average SAMPLES frames spread over the time the shutter
+                         is open into each frame of `--headless`, `--montage` and
+                         `--sweep`
+    --shutter-angle DEGREES
+                         How long the shutter is open for motion blur, from 0 to
+                         360 degrees of a frame at `--max-fps` or 60 Hz
+                         [default: 180]
+    --warmup N           The number of frames rendered and discarded before the
+                         headless capture [default: 3]
+    --state-size N       The number of floats in the state the fragment shader
+                         keeps across frames [default: 1]
+    --time-scale S       How many times faster than real time the time passed to
+                         the shaders advances [default: 1]
+    --fixed-time SECONDS Pin the time passed to the shaders to SECONDS, freezing
+                         any animation at that moment
+    --time-of-day        Pass the local time of day from the clock to the shaders,
+                         in hours from 0 to 24, for visuals that follow the day
+    --fixed-time-of-day HOURS
+                         Pass HOURS as the time of day instead of the clock, to
+                         test how a shader looks at that time
+    --smooth-dt WEIGHT   Advance the time by a moving average of the frame times
+                         instead of each one, WEIGHT being the share of the latest
+                         frame, between 0 and 1, against jitter [default: off]
+    --bpm BPM            Advance the time by the same step every frame instead of
+                         by the real time, chosen so that each beat at BPM beats
+                         per minute lands on the same frame in every run
+    --exposure E         The exposure the colors are scaled by, or the initial one
+                         with auto-exposure [default: 1]
+    --auto-exposure      Adjust the exposure over time so that the average
+                         luminance of the frame is the target gray
+    --exposure-target L  The average luminance auto-exposure aims for
+                         [default: 0.18]
+    --adaptation-speed R The rate auto-exposure adapts at, per second
+                         [default: 1]
+    --ambient-clear      Clear to the mean color of a recent frame instead of
+                         blue, where the scene is transparent or doesn't draw
+    --max-fps N          Limit the frame rate to N frames per second
+                         [default: the refresh rate of the monitor]
+    --frame-skip N       Render only every Nth of those frames, still handling the
+                         input in between, and all of them for a couple of
+                         seconds after an input [default: 1]
+    --max-frames-in-flight N
+                         How many frames the CPU may get ahead of the GPU, from
+                         1 (the lowest latency) to 3 [default: 2]
+    --low-latency        Keep a single frame in flight, present with the mailbox or
+                         relaxed FIFO mode if the surface supports one, and read
+                         the input right before rendering instead of before
+                         waiting for the next frame
+    --present-mode MODE  Present with MODE if the surface supports it, one of
+                         `fifo`, `relaxed`, `mailbox` and `immediate` [default:
+                         the first one the surface supports]
+    --compute-present    Render with a compute shader writing directly into the
+                         swapchain images, if the surface supports it
+    --bench-paths FRAMES Render FRAMES frames with the graphics pipeline and then
+                         with `--compute-present`, print how long they took and
+                         warn if they got slower over the run as when the GPU is
+                         throttled, then exit, not with `--accumulate` or
+                         a fragment shader of `--frag`, `--spirv-frag` or
+                         `--hot-reload`
+    --json-report PATH   Write the device, the resolution, the present mode and the
+                         timings of `--bench-paths` to PATH as JSON on exit
+    --queue-priority P   The priority of the graphics queue, between 0 and 1
+                         [default: 1]
+    --coverage METHOD    How the screen is covered for the fragment shader, one of
+                         `list` (two triangles), `fan` (a triangle fan) and
+                         `triangle` (a single triangle generated by the vertex
+                         shader) [default: list]
+    --mesh PATH          Draw the triangle mesh of the OBJ file at PATH over the
+                         scene, lit by the light and with the camera moved back
+                         along its direction until the mesh fits into the view
+    --reference KIND     Add a unit sphere, a ground plane checkered in unit squares
+                         or both (`sphere`, `ground` or `both`) to the scene for a
+                         sense of scale, O toggles them either way [default kind:
+                         both]
+    --reference-center X,Y,Z
+                         The center of the reference sphere [default: 4,-3,0]
+    --reference-radius R The radius of the reference sphere [default: 1]
+    --ground-height Z    The height of the ground plane [default: -1]
+    --primitive-restart  Let the largest index start a new fan in indexed geometry,
+                         which requires `--coverage fan`
+    --background KIND    What is behind the scene where it is transparent, one of
+                         `solid` (the clear color), `vertical` and `radial`
+                         gradients [default: solid]
+    --background-colors RRGGBB[AA],RRGGBB[AA]
+                         The colors at the top and bottom of a vertical gradient,
+                         or the center and corners of a radial one
+                         [default: 000000,203060]
+    --clear-color RRGGBB[AA]
+                         The color the scene is cleared to [default: 0000ff]
+    --cull-mode MODE     The faces to cull, one of `none`, `front`, `back` and
+                         `both` [default: none]
+    --front-face DIR     The winding of front faces, `ccw` or `cw` [default: ccw]
+    --depth-bias C,S     Offset the depth by the constant factor C and the slope
+                         factor S
+    --depth-clamp        Clamp the depth instead of clipping, if the device
+                         supports it
+    --no-depth-write     Test the triangles of `--mesh` against the depth buffer
+                         without writing their depth, so that they don't occlude
+                         one another, as for a transparent overlay
+    --watchdog SECONDS   Warn about frames taking longer than SECONDS, as the GPU
+                         is likely hung, 0 disables it [default: 5 in debug
+                         builds, 0 in release builds]
+    --watchdog-abort     Abort when the watchdog finds a hung frame
+    --renderdoc          Connect to RenderDoc when run under it, so that F11
+                         captures the next frame
+    --device N           Render with the device of index N rather than the one
+                         preferred, in the order the Vulkan driver lists them
+    --device-info        Print the properties and limits of the device and the
+                         capabilities of the window surface, then exit
+    --mem-info           Print the sizes of the major allocations after setup
+                         and after every resize
+    -q, --quiet          Only log errors, for scripts and pipelines
+    -v, --verbose        Log more of what is going on, given twice or as `-vv`
+                         everything down to the details of the dependencies.
+                         Without either, `RUST_LOG` can set the levels instead
+                         [default: what is going on in submanifold, and the
+                         warnings of its dependencies]
+    -h, --help           Print this message and exit";
+
+/// The geometry covering the screen that the fragment shader runs on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Coverage {
+    /// Two triangles, which works everywhere.
+    List,
+    /// A fan of the four corners, which some drivers and portability layers have trouble with.
+    Fan,
+    /// A single triangle larger than the screen, with the positions generated by the vertex
+    /// shader.
+    Triangle,
+}
+
+impl Coverage {
+    pub fn topology(&self) -> PrimitiveTopology {
+        match *self {
+            Coverage::Fan => PrimitiveTopology::TriangleFan,
+            Coverage::List | Coverage::Triangle => PrimitiveTopology::TriangleList,
+        }
+    }
+}
+
+/// What is drawn behind the scene.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackgroundKind {
+    Solid,
+    Vertical,
+    Radial,
+}
+
+/// The objects `--reference` adds to the built-in scene.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReferenceKind {
+    Sphere,
+    Ground,
+    Both,
+}
+
+impl ReferenceKind {
+    /// The flags of the objects in the `reference` uniform, as `fs.glsl` reads them.
+    pub fn flags(&self) -> u32 {
+        match *self {
+            ReferenceKind::Sphere => 1,
+            ReferenceKind::Ground => 2,
+            ReferenceKind::Both => 3,
+        }
+    }
+}
+
+/// The convention of the coordinates the camera and the light are passed to the shader in, for
+/// shaders ported from elsewhere.
+///
+/// The options and the built-in scene are always right-handed with +z up, +x forward and +y left,
+/// they are only converted in the uniform buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CoordSystem {
+    /// Right-handed with +z up, as in Blender: left unchanged.
+    ZUp,
+    /// Left-handed with +z up, as in Unreal: y is negated.
+    ZUpLeftHanded,
+    /// Right-handed with +y up, as in OpenGL and three.js: turned about x, so that +z becomes +y
+    /// and +y becomes -z.
+    YUp,
+    /// Left-handed with +y up, as in Unity and DirectX: y and z are swapped.
+    YUpLeftHanded,
+}
+
+impl CoordSystem {
+    /// The rows of the matrix turning a vector of the options into this convention.
+    pub fn matrix(&self) -> [[f32; 3]; 3] {
+        match *self {
+            CoordSystem::ZUp => [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+            CoordSystem::ZUpLeftHanded => [[1.0, 0.0, 0.0], [0.0, -1.0, 0.0], [0.0, 0.0, 1.0]],
+            CoordSystem::YUp => [[1.0, 0.0, 0.0], [0.0, 0.0, 1.0], [0.0, -1.0, 0.0]],
+            CoordSystem::YUpLeftHanded => [[1.0, 0.0, 0.0], [0.0, 0.0, 1.0], [0.0, 1.0, 0.0]],
+        }
+    }
+
+    /// `v` of the options in this convention.
+    pub fn transform(&self, v: [f32; 3]) -> [f32; 3] {
+        let m = self.matrix();
+        let row = |i: usize| m[i][0] * v[0] + m[i][1] * v[1] + m[i][2] * v[2];
+
+        [row(0), row(1), row(2)]
+    }
+}
+
+/// What happens after no input for `--idle-timeout`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdleKind {
+    /// The camera circles slowly around the point it looks at.
+    Orbit,
+    /// The letterbox bars ease in.
+    Letterbox,
+}
+
+/// Options passed to the program on the command line.
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub width: u32,
+    pub height: u32,
+    pub min_size: Option<[u32; 2]>,
+    pub max_size: Option<[u32; 2]>,
+    pub preview_window: Option<[u32; 2]>,
+    pub headless: Option<PathBuf>,
+    pub montage: Option<PathBuf>,
+    pub sweep: Option<PathBuf>,
+    pub sweep_sizes: Vec<[u32; 2]>,
+    pub cubemap: Option<PathBuf>,
+    pub cubemap_size: u32,
+    pub cubemap_center: Option<[f32; 3]>,
+    pub turntable: Option<PathBuf>,
+    pub revolutions: f32,
+    pub frames: u32,
+    pub screenshot_on_start: Option<PathBuf>,
+    pub timecode: bool,
+    pub opaque_capture: bool,
+    pub clipped: bool,
+    pub stream_port: Option<u16>,
+    pub warmup: u32,
+    pub motion_blur: Option<u32>,
+    pub shutter_angle: f32,
+    pub max_fps: Option<u32>,
+    pub frame_skip: u32,
+    pub max_frames_in_flight: usize,
+    pub low_latency: bool,
+    pub fixed_time: Option<f32>,
+    pub time_of_day: bool,
+    pub fixed_time_of_day: Option<f32>,
+    pub time_scale: f32,
+    pub bpm: Option<f32>,
+    pub smooth_dt: Option<f32>,
+    pub state_size: usize,
+    pub exposure: f32,
+    pub auto_exposure: bool,
+    pub exposure_target: f32,
+    pub adaptation_speed: f32,
+    pub ambient_clear: bool,
+    pub pixel_scale: u32,
+    pub internal_res: Option<[u32; 2]>,
+    pub scale_filter: Filter,
+    pub camera_position: [f32; 3],
+    pub camera_target: [f32; 3],
+    pub camera_up: [f32; 3],
+    pub fov: f32,
+    pub ortho: bool,
+    pub ortho_size: Option<f32>,
+    pub bounces: u32,
+    pub sensitivity: [f32; 2],
+    pub invert_y: bool,
+    pub camera_damping: Option<f32>,
+    pub preset_transition: f32,
+    pub light_direction: [f32; 3],
+    pub light_color: [f32; 3],
+    pub light_intensity: f32,
+    pub accumulate: Option<u32>,
+    pub aa_compare: Option<PathBuf>,
+    pub mrt: bool,
+    pub distance_range: Option<(f32, f32)>,
+    pub letterbox: Option<f32>,
+    pub letterbox_duration: f32,
+    pub idle_timeout: Option<f32>,
+    pub idle_behavior: IdleKind,
+    pub crosshair: bool,
+    pub crosshair_color: [f32; 4],
+    pub crosshair_size: u32,
+    pub compute_present: bool,
+    pub present_mode: Option<PresentMode>,
+    pub device: Option<usize>,
+    pub clear_color: [f32; 4],
+    pub bench_paths: Option<u32>,
+    pub json_report: Option<PathBuf>,
+    pub debug_tint: bool,
+    pub heatmap: bool,
+    pub flip_y: bool,
+    pub coord_system: CoordSystem,
+    pub scene: Option<PathBuf>,
+    pub frag: Option<PathBuf>,
+    pub spirv_frag: Option<PathBuf>,
+    pub frag_entry: String,
+    pub spirv_vert: Option<PathBuf>,
+    pub vert_entry: String,
+    pub queue_priority: f32,
+    pub coverage: Coverage,
+    pub background: BackgroundKind,
+    pub background_colors: [[f32; 4]; 2],
+    pub cull_mode: CullMode,
+    pub front_face: FrontFace,
+    pub depth_bias: Option<(f32, f32)>,
+    pub depth_clamp: bool,
+    pub depth_write: bool,
+    pub primitive_restart: bool,
+    pub mesh: Option<PathBuf>,
+    pub reference: ReferenceKind,
+    pub show_reference: bool,
+    pub reference_center: [f32; 3],
+    pub reference_radius: f32,
+    pub ground_height: f32,
+    pub watchdog: f32,
+    pub watchdog_abort: bool,
+    pub renderdoc: bool,
+    pub device_info: bool,
+    pub mem_info: bool,
+    pub hot_reload: bool,
+    pub shader_dir: PathBuf,
+    pub defines: Vec<(String, String)>,
+    pub dump_spirv: Option<PathBuf>,
+    pub shader_cache: Option<PathBuf>,
+    pub check_shaders: Vec<PathBuf>,
+    /// The simulation state of `--state` to restore, see `Simulation::restore`.
+    pub restore_state: Option<String>,
+    pub help: bool,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            width: 1280,
+            height: 1024,
+            min_size: None,
+            max_size: None,
+            preview_window: None,
+            headless: None,
+            montage: None,
+            sweep: None,
+            // Widescreen, 4:3, ultrawide and square.
+            sweep_sizes: vec![[1920, 1080], [1600, 1200], [2560, 1080], [1024, 1024]],
+            cubemap: None,
+            cubemap_size: 512,
+            cubemap_center: None,
+            turntable: None,
+            revolutions: 1.0,
+            frames: 120,
+            screenshot_on_start: None,
+            timecode: false,
+            opaque_capture: false,
+            clipped: true,
+            stream_port: None,
+            warmup: 3,
+            motion_blur: None,
+            shutter_angle: 180.0,
+            max_fps: None,
+            frame_skip: 1,
+            max_frames_in_flight: 2,
+            low_latency: false,
+            fixed_time: None,
+            time_of_day: false,
+            fixed_time_of_day: None,
+            time_scale: 1.0,
+            bpm: None,
+            smooth_dt: None,
+            state_size: 1,
+            exposure: 1.0,
+            auto_exposure: false,
+            exposure_target: 0.18,
+            adaptation_speed: 1.0,
+            ambient_clear: false,
+            pixel_scale: 1,
+            internal_res: None,
+            scale_filter: Filter::Linear,
+            camera_position: [0.0, 0.0, 0.0],
+            camera_target: [1.0, 0.0, 0.0],
+            camera_up: [0.0, 0.0, 1.0],
+            fov: 90.0,
+            ortho: false,
+            ortho_size: None,
+            bounces: 2,
+            sensitivity: [1.0, 1.0],
+            invert_y: false,
+            camera_damping: None,
+            preset_transition: 0.5,
+            light_direction: [-1.0, 1.0, 2.0],
+            light_color: [1.0, 1.0, 1.0],
+            light_intensity: 1.0,
+            accumulate: None,
+            aa_compare: None,
+            mrt: false,
+            distance_range: None,
+            letterbox: None,
+            letterbox_duration: 1.0,
+            idle_timeout: None,
+            idle_behavior: IdleKind::Orbit,
+            crosshair: false,
+            crosshair_color: [1.0, 1.0, 1.0, 0.5],
+            crosshair_size: 12,
+            compute_present: false,
+            present_mode: None,
+            device: None,
+            clear_color: [0.0, 0.0, 1.0, 1.0],
+            bench_paths: None,
+            json_report: None,
+            debug_tint: false,
+            heatmap: false,
+            flip_y: false,
+            coord_system: CoordSystem::ZUp,
+            scene: None,
+            frag: None,
+            spirv_frag: None,
+            frag_entry: "main".to_string(),
+            spirv_vert: None,
+            vert_entry: "main".to_string(),
+            queue_priority: 1.0,
+            coverage: Coverage::List,
+            background: BackgroundKind::Solid,
+            background_colors: [[0.0, 0.0, 0.0, 1.0], [0.125, 0.1875, 0.375, 1.0]],
+            cull_mode: CullMode::None,
+            front_face: FrontFace::CounterClockwise,
+            depth_bias: None,
+            depth_clamp: false,
+            depth_write: true,
+            primitive_restart: false,
+            mesh: None,
+            reference: ReferenceKind::Both,
+            show_reference: false,
+            reference_center: [4.0, -3.0, 0.0],
+            reference_radius: 1.0,
+            ground_height: -1.0,
+            watchdog: if cfg!(debug_assertions) { 5.0 } else { 0.0 },
+            watchdog_abort: false,
+            renderdoc: false,
+            device_info: false,
+            mem_info: false,
+            hot_reload: false,
+            shader_dir: PathBuf::from(concat!(env!("CARGO_MANIFEST_DIR"), "/src")),
+            defines: Vec::new(),
+            dump_spirv: None,
+            shader_cache: ::shader::default_cache_dir(),
+            check_shaders: Vec::new(),
+            restore_state: None,
+            help: false,
+        }
+    }
+}
+
+/// The filter for the logger of `-q`, `-v` or `-vv` in `args`, if any of them is given, read
+/// before the rest of the arguments so that what parsing them logs is filtered already.
+pub fn log_filter(args: &[String]) -> Option<&'static str> {
+    let verbosity = args.iter().map(|arg| match arg.as_str() {
+        "-v" | "--verbose" => 1,
+        "-vv" => 2,
+        _ => 0,
+    }).sum::<u32>();
+
+    if args.iter().any(|arg| arg == "-q" || arg == "--quiet") {
+        Some("error")
+    } else {
+        match verbosity {
+            0 => None,
+            1 => Some("info,submanifold=debug"),
+            _ => Some("trace"),
+        }
+    }
+}
+
+impl Config {
+    /// Parses the arguments, not including the name of the executable.
+    pub fn parse<I: Iterator<Item = String>>(args: I) -> Result<Config, String> {
+        let args = args.collect::<Vec<_>>();
+        let mut config = Config::default();
+
+        // The sources are found along with the flags, so that a value that happens to be
+        // `--config` isn't taken for one, and read first, wherever they are given, so that the
+        // flags override them.
+        let sources = apply_flags(&mut Config::default(), &args)?;
+
+        if let Some(ref path) = sources.config {
+            FileConfig::read(path)?.apply(&mut config)?;
+        }
+
+        // A view string is applied after the config file too, and the flags still override it.
+        if let Some(ref text) = sources.state {
+            View::decode(text)?.apply(&mut config);
+        }
+
+        // A scene is read after the config file, so that it can be used for a preset look.
+        if let Some(path) = sources.scene {
+            FileConfig::read(&path)?.apply(&mut config)?;
+            config.scene = Some(path);
+        }
+
+        apply_flags(&mut config, &args)?;
+
+        if config.width == 0 || config.height == 0 {
+            return Err("the image dimensions must not be zero".to_string());
+        }
+
+        if config.preview_window.map_or(false, |size| size[0] == 0 || size[1] == 0) {
+            return Err("the size of the preview window must not be zero".to_string());
+        }
+
+        if config.sweep_sizes.iter().any(|size| size[0] == 0 || size[1] == 0) {
+            return Err("the sizes of the sweep must not be zero".to_string());
+        }
+
+        let min_size = config.min_size.unwrap_or([1, 1]);
+        let max_size = config.max_size.unwrap_or([u32::max_value(), u32::max_value()]);
+
+        if min_size[0] == 0 || min_size[1] == 0 {
+            return Err("the minimum window size must not be zero".to_string());
+        }
+
+        if min_size[0] > max_size[0] || min_size[1] > max_size[1] {
+            return Err("the minimum window size must not exceed the maximum one".to_string());
+        }
+
+        if config.width < min_size[0] || config.width > max_size[0] ||
+           config.height < min_size[1] || config.height > max_size[1] {
+            return Err("the image dimensions must be within the minimum and maximum window \
+                        sizes".to_string());
+        }
+
+        if !(config.queue_priority >= 0.0 && config.queue_priority <= 1.0) {
+            return Err("the queue priority must be between 0 and 1".to_string());
+        }
+
+        if config.pixel_scale == 0 {
+            return Err("the pixel scale must not be zero".to_string());
+        }
+
+        if let Some(dimensions) = config.internal_res {
+            if dimensions[0] == 0 || dimensions[1] == 0 {
+                return Err("the internal resolution must not be zero".to_string());
+            }
+
+            if config.pixel_scale > 1 {
+                return Err("the internal resolution and the pixel scale can't be combined"
+                           .to_string());
+            }
+        }
+
+        if config.letterbox.map_or(false, |size: f32| !(size >= 0.0 && size <= 0.5)) {
+            return Err("the letterbox size must be between 0 and 0.5".to_string());
+        }
+
+        if !(config.letterbox_duration >= 0.0 && config.letterbox_duration.is_finite()) {
+            return Err("the letterbox duration must be a non-negative number".to_string());
+        }
+
+        if config.idle_timeout.map_or(false, |time: f32| !(time > 0.0 && time.is_finite())) {
+            return Err("the idle timeout must be a positive number".to_string());
+        }
+
+        if config.bench_paths == Some(0) {
+            return Err("the number of frames to bench must not be zero".to_string());
+        }
+
+        // The compute path doesn't accumulate, so the paths wouldn't render the same frames.
+        if config.bench_paths.is_some() && config.accumulate.is_some() {
+            return Err("`--bench-paths` can't be combined with `--accumulate`".to_string());
+        }
+
+        // Both paths render the built-in scene, which a shader of the user's would replace on
+        // the graphics path only.
+        if config.bench_paths.is_some() && (config.frag.is_some() || config.spirv_frag.is_some()
+                                            || config.hot_reload) {
+            return Err("`--bench-paths` can't be combined with `--frag`, `--spirv-frag` or \
+                        `--hot-reload`".to_string());
+        }
+
+        if config.crosshair_size == 0 {
+            return Err("the crosshair size must not be zero".to_string());
+        }
+
+        if config.fixed_time.map_or(false, |time: f32| !time.is_finite()) {
+            return Err("the fixed time must be a finite number".to_string());
+        }
+
+        if config.fixed_time_of_day.map_or(false, |hours: f32| !(hours >= 0.0 && hours < 24.0)) {
+            return Err("the fixed time of day must be from 0 to 24 hours".to_string());
+        }
+
+        if !(config.time_scale > 0.0 && config.time_scale.is_finite()) {
+            return Err("the time scale must be a positive number".to_string());
+        }
+
+        if config.bpm.map_or(false, |bpm: f32| !(bpm > 0.0 && bpm.is_finite())) {
+            return Err("the tempo must be a positive number".to_string());
+        }
+
+        if config.smooth_dt.map_or(false, |weight: f32| !(weight > 0.0 && weight <= 1.0)) {
+            return Err("the weight of the latest frame time must be between 0 and 1".to_string());
+        }
+
+        if !(config.exposure > 0.0 && config.exposure_target > 0.0) {
+            return Err("the exposure and its target must be positive".to_string());
+        }
+
+        if !(config.adaptation_speed >= 0.0) {
+            return Err("the adaptation speed must not be negative".to_string());
+        }
+
+        if !(config.watchdog >= 0.0 && config.watchdog.is_finite()) {
+            return Err("the watchdog timeout must not be negative".to_string());
+        }
+
+        if config.state_size == 0 {
+            return Err("the state size must not be zero".to_string());
+        }
+
+        if config.camera_position.iter().chain(&config.camera_target).any(|c| !c.is_finite()) {
+            return Err("the camera position and the point it looks at must be finite"
+                       .to_string());
+        }
+
+        if config.sensitivity.iter().any(|&s| !(s > 0.0 && s.is_finite())) {
+            return Err("the mouse sensitivities must be positive, use `--invert-y` to flip the \
+                        vertical one".to_string());
+        }
+
+        if config.camera_damping.map_or(false, |rate| !(rate > 0.0 && rate.is_finite())) {
+            return Err("the camera damping must be positive".to_string());
+        }
+
+        if !(config.preset_transition >= 0.0 && config.preset_transition.is_finite()) {
+            return Err("the preset transition must be a non-negative number".to_string());
+        }
+
+        if !config.camera_up.iter().all(|c| c.is_finite()) ||
+           config.camera_up.iter().all(|&c| c == 0.0) {
+            return Err("the up direction of the camera must be finite and not zero".to_string());
+        }
+
+        if config.camera_direction().is_none() {
+            return Err("the camera must look at a point other than its position, and not \
+                        along its up direction".to_string());
+        }
+
+        if !(config.fov > 0.0 && config.fov < 180.0) {
+            return Err("the field of view must be between 0 and 180 degrees".to_string());
+        }
+
+        if config.ortho_size.map_or(false, |size| !(size > 0.0 && size.is_finite())) {
+            return Err("the size of the orthographic view must be positive".to_string());
+        }
+
+        if config.bounces > MAX_BOUNCES {
+            return Err(format!("there can be at most {} bounces", MAX_BOUNCES));
+        }
+
+        if config.cubemap_size == 0 {
+            return Err("the size of the cubemap faces must not be zero".to_string());
+        }
+
+        if config.cubemap_center.map_or(false, |center| !center.iter().all(|c| c.is_finite())) {
+            return Err("the center of the cubemap must be finite".to_string());
+        }
+
+        if !config.light_direction.iter().all(|c| c.is_finite()) ||
+           config.light_direction.iter().all(|&c| c == 0.0) {
+            return Err("the direction towards the light must be finite and not zero".to_string());
+        }
+
+        if !(config.light_intensity >= 0.0 && config.light_intensity.is_finite()) {
+            return Err("the light intensity must be a non-negative number".to_string());
+        }
+
+        if config.accumulate == Some(0) {
+            return Err("the number of accumulated frames must not be zero".to_string());
+        }
+
+        if config.aa_compare.is_some() && config.accumulate.is_none() {
+            return Err("`--aa-compare` needs the samples of `--accumulate` to compare with"
+                           .to_string());
+        }
+
+        if config.motion_blur == Some(0) {
+            return Err("the number of motion blur samples must not be zero".to_string());
+        }
+
+        if config.frames == 0 {
+            return Err("the number of frames of the turntable must not be zero".to_string());
+        }
+
+        if !config.revolutions.is_finite() {
+            return Err("the number of revolutions of the turntable must be a number".to_string());
+        }
+
+        if !(config.shutter_angle >= 0.0 && config.shutter_angle <= 360.0) {
+            return Err("the shutter angle must be between 0 and 360 degrees".to_string());
+        }
+
+        if !(config.max_frames_in_flight >= 1 && config.max_frames_in_flight <= 3) {
+            return Err("the number of frames in flight must be between 1 and 3".to_string());
+        }
+
+        // Every frame the CPU gets ahead of the GPU is a frame between the input and the screen.
+        if config.low_latency {
+            config.max_frames_in_flight = 1;
+        }
+
+        if config.max_fps == Some(0) {
+            return Err("the frame rate limit must not be zero".to_string());
+        }
+
+        // Vulkan only allows restarting strips and fans, of which only the latter can be chosen.
+        if config.primitive_restart && config.coverage != Coverage::Fan {
+            return Err("primitive restart requires `--coverage fan`".to_string());
+        }
+
+        if config.frame_skip == 0 {
+            return Err("the frame skip must not be zero".to_string());
+        }
+
+        if config.frag.is_some() && config.spirv_frag.is_some() {
+            return Err("only one of `--frag` and `--spirv-frag` can be given".to_string());
+        }
+
+        if !(config.reference_radius > 0.0 && config.reference_radius.is_finite()) {
+            return Err("the radius of the reference sphere must be positive".to_string());
+        }
+
+        if !config.ground_height.is_finite() {
+            return Err("the height of the ground plane must be finite".to_string());
+        }
+
+        if config.distance_range.map_or(false, |(near, far)| !(near >= 0.0 && near < far)) {
+            return Err("the distance range must start at 0 or later and end after it"
+                           .to_string());
+        }
+
+        Ok(config)
+    }
+
+    /// The movement of the mouse by `delta` pixels scaled by the sensitivities and flipped
+    /// vertically with `--invert-y`, as it moves the camera.
+    pub fn mouse_delta(&self, delta: [f32; 2]) -> [f32; 2] {
+        let sign = if self.invert_y { -1.0 } else { 1.0 };
+
+        [delta[0] * self.sensitivity[0], delta[1] * self.sensitivity[1] * sign]
+    }
+
+    /// The unit vector from the position of the camera to the point it looks at, unless they
+    /// are the same or along the up direction, where the left and up directions are undefined.
+    pub fn camera_direction(&self) -> Option<[f32; 3]> {
+        let position = self.camera_position;
+        let target = self.camera_target;
+        let direction = [target[0] - position[0], target[1] - position[1],
+                         target[2] - position[2]];
+        let up = self.camera_up;
+        let length = direction.iter().map(|c| c * c).sum::<f32>().sqrt();
+        let up_length = up.iter().map(|c| c * c).sum::<f32>().sqrt();
+
+        // The length of the part of the direction perpendicular to up, times that of up.
+        let across = [direction[1] * up[2] - direction[2] * up[1],
+                      direction[2] * up[0] - direction[0] * up[2],
+                      direction[0] * up[1] - direction[1] * up[0]];
+        let across = across.iter().map(|c| c * c).sum::<f32>().sqrt();
+
+        if !(across > 1e-6 * length * up_length) {
+            return None;
+        }
+
+        Some([direction[0] / length, direction[1] / length, direction[2] / length])
+    }
+
+    /// How wide the view of `--ortho` is across the diagonal, by default as wide as the
+    /// perspective view is at the point the camera looks at, so that switching keeps the size of
+    /// what is there. That is at a distance of 1 if it is where the camera is.
+    pub fn ortho_size(&self) -> f32 {
+        self.ortho_size.unwrap_or_else(|| {
+            let distance = (0..3).map(|i| (self.camera_target[i] - self.camera_position[i]).powi(2))
+                .sum::<f32>().sqrt();
+            let distance = if distance > 0.0 { distance } else { 1.0 };
+
+            2.0 * distance * (self.fov.to_radians() / 2.0).tan()
+        })
+    }
+}
+
+/// The files and the view string the options are read from before the flags.
+#[derive(Debug, Default)]
+struct Sources {
+    /// `--config`.
+    config: Option<PathBuf>,
+    /// `--state`.
+    state: Option<String>,
+    /// `--scene`.
+    scene: Option<PathBuf>,
+}
+
+/// Sets the options of the flags in `args` in `config`, returning the sources they override.
+fn apply_flags(config: &mut Config, args: &[String]) -> Result<Sources, String> {
+    let mut sources = Sources::default();
+    let mut args = args.iter().cloned();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--config" => sources.config = Some(value(&arg, args.next())?),
+            "--state" => sources.state = Some(value(&arg, args.next())?),
+            "--scene" => sources.scene = Some(value(&arg, args.next())?),
+            // Read by `log_filter` before the logger is set up.
+            "-q" | "--quiet" | "-v" | "-vv" | "--verbose" => (),
+            "--width" => config.width = value(&arg, args.next())?,
+            "--height" => config.height = value(&arg, args.next())?,
+            "--min-size" => config.min_size = Some(size(&arg, args.next())?),
+            "--max-size" => config.max_size = Some(size(&arg, args.next())?),
+            "--preview-window" => config.preview_window = Some(size(&arg, args.next())?),
+            "--headless" => config.headless = Some(value(&arg, args.next())?),
+            "--montage" => config.montage = Some(value(&arg, args.next())?),
+            "--sweep" => config.sweep = Some(value(&arg, args.next())?),
+            "--sweep-sizes" => config.sweep_sizes = sizes(&arg, args.next())?,
+            "--cubemap" => config.cubemap = Some(value(&arg, args.next())?),
+            "--cubemap-size" => config.cubemap_size = value(&arg, args.next())?,
+            "--cubemap-center" => config.cubemap_center = Some(vector(&arg, args.next())?),
+            "--turntable" => config.turntable = Some(value(&arg, args.next())?),
+            "--revolutions" => config.revolutions = value(&arg, args.next())?,
+            "--frames" => config.frames = value(&arg, args.next())?,
+            "--screenshot-on-start" => {
+                config.screenshot_on_start = Some(value(&arg, args.next())?)
+            }
+            "--timecode" => config.timecode = true,
+            "--opaque-capture" => config.opaque_capture = true,
+            "--no-clip" => config.clipped = false,
+            "--stream-port" => config.stream_port = Some(value(&arg, args.next())?),
+            "--warmup" => config.warmup = value(&arg, args.next())?,
+            "--motion-blur" => config.motion_blur = Some(value(&arg, args.next())?),
+            "--shutter-angle" => config.shutter_angle = value(&arg, args.next())?,
+            "--max-fps" => config.max_fps = Some(value(&arg, args.next())?),
+            "--frame-skip" => config.frame_skip = value(&arg, args.next())?,
+            "--max-frames-in-flight" => {
+                config.max_frames_in_flight = value(&arg, args.next())?
+            }
+            "--low-latency" => config.low_latency = true,
+            "--fixed-time" => config.fixed_time = Some(value(&arg, args.next())?),
+            "--time-of-day" => config.time_of_day = true,
+            "--fixed-time-of-day" => {
+                config.fixed_time_of_day = Some(value(&arg, args.next())?)
+            }
+            "--time-scale" => config.time_scale = value(&arg, args.next())?,
+            "--bpm" => config.bpm = Some(value(&arg, args.next())?),
+            "--smooth-dt" => config.smooth_dt = Some(value(&arg, args.next())?),
+            "--state-size" => config.state_size = value(&arg, args.next())?,
+            "--exposure" => config.exposure = value(&arg, args.next())?,
+            "--auto-exposure" => config.auto_exposure = true,
+            "--exposure-target" => config.exposure_target = value(&arg, args.next())?,
+            "--adaptation-speed" => config.adaptation_speed = value(&arg, args.next())?,
+            "--ambient-clear" => config.ambient_clear = true,
+            "--pixel-scale" => config.pixel_scale = value(&arg, args.next())?,
+            "--internal-res" => config.internal_res = Some(size(&arg, args.next())?),
+            "--cam-pos" => config.camera_position = vector(&arg, args.next())?,
+            "--cam-look" => config.camera_target = vector(&arg, args.next())?,
+            "--cam-up" => config.camera_up = vector(&arg, args.next())?,
+            "--fov" => config.fov = value(&arg, args.next())?,
+            "--ortho" => config.ortho = true,
+            "--ortho-size" => config.ortho_size = Some(value(&arg, args.next())?),
+            "--bounces" => config.bounces = value(&arg, args.next())?,
+            "--sensitivity-x" => config.sensitivity[0] = value(&arg, args.next())?,
+            "--sensitivity-y" => config.sensitivity[1] = value(&arg, args.next())?,
+            "--invert-y" => config.invert_y = true,
+            "--camera-damping" => config.camera_damping = Some(value(&arg, args.next())?),
+            "--preset-transition" => {
+                config.preset_transition = value(&arg, args.next())?
+            }
+            "--light-dir" => config.light_direction = vector(&arg, args.next())?,
+            "--light-color" => config.light_color = rgb(&arg, args.next())?,
+            "--light-intensity" => config.light_intensity = value(&arg, args.next())?,
+            "--scale-filter" => config.scale_filter = filter(&arg, args.next())?,
+            "--accumulate" => config.accumulate = Some(value(&arg, args.next())?),
+            "--aa-compare" => config.aa_compare = Some(value(&arg, args.next())?),
+            "--mrt" => config.mrt = true,
+            "--distance-range" => config.distance_range = Some(pair(&arg, args.next())?),
+            "--letterbox" => config.letterbox = Some(value(&arg, args.next())?),
+            "--letterbox-duration" => {
+                config.letterbox_duration = value(&arg, args.next())?
+            }
+            "--idle-timeout" => config.idle_timeout = Some(value(&arg, args.next())?),
+            "--idle-behavior" => config.idle_behavior = idle_behavior(&arg, args.next())?,
+            "--crosshair" => config.crosshair = true,
+            "--crosshair-color" => config.crosshair_color = single_color(&arg, args.next())?,
+            "--crosshair-size" => config.crosshair_size = value(&arg, args.next())?,
+            "--compute-present" => config.compute_present = true,
+            "--present-mode" => config.present_mode = Some(present_mode(&arg, args.next())?),
+            "--device" => config.device = Some(value(&arg, args.next())?),
+            "--clear-color" => config.clear_color = single_color(&arg, args.next())?,
+            "--bench-paths" => config.bench_paths = Some(value(&arg, args.next())?),
+            "--json-report" => config.json_report = Some(value(&arg, args.next())?),
+            "--debug-tint" => config.debug_tint = true,
+            "--heatmap" => config.heatmap = true,
+            "--flip-y" => config.flip_y = true,
+            "--coord-system" => config.coord_system = coord_system(&arg, args.next())?,
+            "--frag" => config.frag = Some(value(&arg, args.next())?),
+            "--spirv-frag" => config.spirv_frag = Some(value(&arg, args.next())?),
+            "--frag-entry" => config.frag_entry = value(&arg, args.next())?,
+            "--spirv-vert" => config.spirv_vert = Some(value(&arg, args.next())?),
+            "--vert-entry" => config.vert_entry = value(&arg, args.next())?,
+            "--queue-priority" => config.queue_priority = value(&arg, args.next())?,
+            "--coverage" => config.coverage = coverage(&arg, args.next())?,
+            "--background" => config.background = background(&arg, args.next())?,
+            "--background-colors" => {
+                config.background_colors = colors(&arg, args.next())?
+            }
+            "--cull-mode" => config.cull_mode = cull_mode(&arg, args.next())?,
+            "--front-face" => config.front_face = front_face(&arg, args.next())?,
+            "--depth-bias" => config.depth_bias = Some(pair(&arg, args.next())?),
+            "--depth-clamp" => config.depth_clamp = true,
+            "--no-depth-write" => config.depth_write = false,
+            "--primitive-restart" => config.primitive_restart = true,
+            "--mesh" => config.mesh = Some(value(&arg, args.next())?),
+            "--reference" => {
+                config.reference = reference(&arg, args.next())?;
+                config.show_reference = true;
+            }
+            "--reference-center" => config.reference_center = vector(&arg, args.next())?,
+            "--reference-radius" => config.reference_radius = value(&arg, args.next())?,
+            "--ground-height" => config.ground_height = value(&arg, args.next())?,
+            "--watchdog" => config.watchdog = value(&arg, args.next())?,
+            "--watchdog-abort" => config.watchdog_abort = true,
+            "--renderdoc" => config.renderdoc = true,
+            "--device-info" => config.device_info = true,
+            "--mem-info" => config.mem_info = true,
+            "--hot-reload" => config.hot_reload = true,
+            "--shader-dir" => config.shader_dir = value(&arg, args.next())?,
+            "--define" => config.defines.push(define(&arg, args.next())?),
+            "--dump-spirv" => config.dump_spirv = Some(value(&arg, args.next())?),
+            "--shader-cache" => config.shader_cache = Some(value(&arg, args.next())?),
+            "--no-shader-cache" => config.shader_cache = None,
+            "--check-shader" => config.check_shaders.push(value(&arg, args.next())?),
+            "-h" | "--help" => config.help = true,
+            _ => return Err(format!("unknown argument `{}`", arg)),
+        }
+    }
+
+    Ok(sources)
+}
+
+/// The options that can be set in the file given with `--config`, named like their flags.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct FileConfig {
+    width: Option<u32>,
+    height: Option<u32>,
+    internal_res: Option<[u32; 2]>,
+    cam_pos: Option<[f32; 3]>,
+    cam_look: Option<[f32; 3]>,
+    cam_up: Option<[f32; 3]>,
+    fov: Option<f32>,
+    ortho: Option<bool>,
+    ortho_size: Option<f32>,
+    bounces: Option<u32>,
+    sensitivity_x: Option<f32>,
+    sensitivity_y: Option<f32>,
+    invert_y: Option<bool>,
+    camera_damping: Option<f32>,
+    preset_transition: Option<f32>,
+    light_dir: Option<[f32; 3]>,
+    light_color: Option<String>,
+    light_intensity: Option<f32>,
+    background: Option<String>,
+    background_colors: Option<[String; 2]>,
+    exposure: Option<f32>,
+    auto_exposure: Option<bool>,
+    time_scale: Option<f32>,
+    fixed_time: Option<f32>,
+    time_of_day: Option<bool>,
+    fixed_time_of_day: Option<f32>,
+    bpm: Option<f32>,
+    smooth_dt: Option<f32>,
+    max_fps: Option<u32>,
+    max_frames_in_flight: Option<usize>,
+    low_latency: Option<bool>,
+    present_mode: Option<String>,
+    device: Option<usize>,
+    clear_color: Option<String>,
+    state_size: Option<usize>,
+    /// Relative to the directory of the file.
+    frag: Option<PathBuf>,
+    heatmap: Option<bool>,
+    coord_system: Option<String>,
+    reference: Option<String>,
+    reference_center: Option<[f32; 3]>,
+    reference_radius: Option<f32>,
+    ground_height: Option<f32>,
+    /// Everything else, which is warned about rather than rejected so that files written for
+    /// newer versions still load.
+    #[serde(flatten)]
+    unknown: BTreeMap<String, toml::Value>,
+}
+
+impl FileConfig {
+    fn read(path: &Path) -> Result<FileConfig, String> {
+        let mut text = String::new();
+
+        File::open(path)
+            .and_then(|mut file| file.read_to_string(&mut text))
+            .map_err(|err| format!("failed to read `{}`: {}", path.display(), err))?;
+
+        let mut file: FileConfig = toml::from_str(&text)
+            .map_err(|err| format!("invalid config `{}`: {}", path.display(), err))?;
+
+        if let Some(dir) = path.parent() {
+            file.frag = file.frag.map(|frag| dir.join(frag));
+        }
+
+        Ok(file)
+    }
+
+    /// Sets the options of `config` the file has values for, the values are validated along
+    /// with the flags.
+    fn apply(self, config: &mut Config) -> Result<(), String> {
+        for key in self.unknown.keys() {
+            warn!("unknown option `{}` in the config file, ignoring it", key);
+        }
+
+        // The values written as strings are parsed exactly like the flags.
+        if let Some(value) = self.background {
+            config.background = background("background", Some(value))?;
+        }
+
+        if let Some(values) = self.background_colors {
+            config.background_colors = colors("background-colors", Some(values.join(",")))?;
+        }
+
+        if let Some(value) = self.light_color {
+            config.light_color = rgb("light-color", Some(value))?;
+        }
+
+        if let Some(value) = self.present_mode {
+            config.present_mode = Some(present_mode("present-mode", Some(value))?);
+        }
+
+        if let Some(value) = self.clear_color {
+            config.clear_color = single_color("clear-color", Some(value))?;
+        }
+
+        if let Some(value) = self.coord_system {
+            config.coord_system = coord_system("coord-system", Some(value))?;
+        }
+
+        if let Some(value) = self.reference {
+            config.reference = reference("reference", Some(value))?;
+            config.show_reference = true;
+        }
+
+        config.width = self.width.unwrap_or(config.width);
+        config.height = self.height.unwrap_or(config.height);
+        config.internal_res = self.internal_res.or(config.internal_res);
+        config.camera_position = self.cam_pos.unwrap_or(config.camera_position);
+        config.camera_target = self.cam_look.unwrap_or(config.camera_target);
+        config.camera_up = self.cam_up.unwrap_or(config.camera_up);
+        config.fov = self.fov.unwrap_or(config.fov);
+        config.ortho = self.ortho.unwrap_or(config.ortho);
+        config.ortho_size = self.ortho_size.or(config.ortho_size);
+        config.bounces = self.bounces.unwrap_or(config.bounces);
+        config.sensitivity[0] = self.sensitivity_x.unwrap_or(config.sensitivity[0]);
+        config.sensitivity[1] = self.sensitivity_y.unwrap_or(config.sensitivity[1]);
+        config.invert_y = self.invert_y.unwrap_or(config.invert_y);
+        config.camera_damping = self.camera_damping.or(config.camera_damping);
+        config.preset_transition = self.preset_transition.unwrap_or(config.preset_transition);
+        config.light_direction = self.light_dir.unwrap_or(config.light_direction);
+        config.light_intensity = self.light_intensity.unwrap_or(config.light_intensity);
+        config.exposure = self.exposure.unwrap_or(config.exposure);
+        config.auto_exposure = self.auto_exposure.unwrap_or(config.auto_exposure);
+        config.time_scale = self.time_scale.unwrap_or(config.time_scale);
+        config.fixed_time = self.fixed_time.or(config.fixed_time);
+        config.time_of_day = self.time_of_day.unwrap_or(config.time_of_day);
+        config.fixed_time_of_day = self.fixed_time_of_day.or(config.fixed_time_of_day);
+        config.bpm = self.bpm.or(config.bpm);
+        config.smooth_dt = self.smooth_dt.or(config.smooth_dt);
+        config.max_fps = self.max_fps.or(config.max_fps);
+        config.max_frames_in_flight =
+            self.max_frames_in_flight.unwrap_or(config.max_frames_in_flight);
+        config.low_latency = self.low_latency.unwrap_or(config.low_latency);
+        config.device = self.device.or(config.device);
+        config.state_size = self.state_size.unwrap_or(config.state_size);
+        config.frag = self.frag.or(config.frag);
+        config.heatmap = self.heatmap.unwrap_or(config.heatmap);
+        config.reference_center = self.reference_center.unwrap_or(config.reference_center);
+        config.reference_radius = self.reference_radius.unwrap_or(config.reference_radius);
+        config.ground_height = self.ground_height.unwrap_or(config.ground_height);
+
+        Ok(())
+    }
+}
+
+/// Parses the value following the flag `flag`.
+fn value<T: FromStr>(flag: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("missing value for `{}`", flag))?;
+
+    value.parse().map_err(|_| format!("invalid value `{}` for `{}`", value, flag))
+}
+
+fn coverage(flag: &str, value: Option<String>) -> Result<Coverage, String> {
+    let value = value.ok_or_else(|| format!("missing value for `{}`", flag))?;
+
+    match value.as_str() {
+        "list" => Ok(Coverage::List),
+        "fan" => Ok(Coverage::Fan),
+        "triangle" => Ok(Coverage::Triangle),
+        _ => Err(format!("invalid value `{}` for `{}`", value, flag)),
+    }
+}
+
+fn background(flag: &str, value: Option<String>) -> Result<BackgroundKind, String> {
+    let value = value.ok_or_else(|| format!("missing value for `{}`", flag))?;
+
+    match value.as_str() {
+        "solid" => Ok(BackgroundKind::Solid),
+        "vertical" => Ok(BackgroundKind::Vertical),
+        "radial" => Ok(BackgroundKind::Radial),
+        _ => Err(format!("invalid value `{}` for `{}`", value, flag)),
+    }
+}
+
+fn reference(flag: &str, value: Option<String>) -> Result<ReferenceKind, String> {
+    let value = value.ok_or_else(|| format!("missing value for `{}`", flag))?;
+
+    match value.as_str() {
+        "sphere" => Ok(ReferenceKind::Sphere),
+        "ground" => Ok(ReferenceKind::Ground),
+        "both" => Ok(ReferenceKind::Both),
+        _ => Err(format!("invalid value `{}` for `{}`", value, flag)),
+    }
+}
+
+fn coord_system(flag: &str, value: Option<String>) -> Result<CoordSystem, String> {
+    let value = value.ok_or_else(|| format!("missing value for `{}`", flag))?;
+
+    match value.as_str() {
+        "z-up" => Ok(CoordSystem::ZUp),
+        "z-up-lh" => Ok(CoordSystem::ZUpLeftHanded),
+        "y-up" => Ok(CoordSystem::YUp),
+        "y-up-lh" => Ok(CoordSystem::YUpLeftHanded),
+        _ => Err(format!("invalid value `{}` for `{}`", value, flag)),
+    }
+}
+
+fn idle_behavior(flag: &str, value: Option<String>) -> Result<IdleKind, String> {
+    let value = value.ok_or_else(|| format!("missing value for `{}`", flag))?;
+
+    match value.as_str() {
+        "orbit" => Ok(IdleKind::Orbit),
+        "letterbox" => Ok(IdleKind::Letterbox),
+        _ => Err(format!("invalid value `{}` for `{}`", value, flag)),
+    }
+}
+
+/// Parses a color written as `RRGGBB` or `RRGGBBAA` in hexadecimal.
+fn color(value: &str) -> Option<[f32; 4]> {
+    let value = value.trim().trim_start_matches('#');
+
+    if !(value.len() == 6 || value.len() == 8) || value.bytes().any(|byte| byte >= 0x80) {
+        return None;
+    }
+
+    let mut color = [1.0; 4];
+
+    for (index, channel) in color.iter_mut().enumerate().take(value.len() / 2) {
+        match u8::from_str_radix(&value[index * 2 .. index * 2 + 2], 16) {
+            Ok(byte) => *channel = byte as f32 / 255.0,
+            Err(_) => return None,
+        }
+    }
+
+    Some(color)
+}
+
+/// Parses a single color.
+fn single_color(flag: &str, value: Option<String>) -> Result<[f32; 4], String> {
+    let value = value.ok_or_else(|| format!("missing value for `{}`", flag))?;
+
+    color(&value).ok_or_else(|| format!("invalid value `{}` for `{}`", value, flag))
+}
+
+/// Parses a color without alpha, written as `RRGGBB` in hexadecimal.
+fn rgb(flag: &str, value: Option<String>) -> Result<[f32; 3], String> {
+    let value = value.ok_or_else(|| format!("missing value for `{}`", flag))?;
+
+    match color(&value) {
+        Some(color) if value.trim().trim_start_matches('#').len() == 6 => {
+            Ok([color[0], color[1], color[2]])
+        }
+        _ => Err(format!("invalid value `{}` for `{}`", value, flag)),
+    }
+}
+
+/// Parses two colors separated by a comma.
+fn colors(flag: &str, value: Option<String>) -> Result<[[f32; 4]; 2], String> {
+    let value = value.ok_or_else(|| format!("missing value for `{}`", flag))?;
+    let mut colors = value.split(',').map(color);
+
+    match (colors.next(), colors.next(), colors.next()) {
+        (Some(Some(first)), Some(Some(second)), None) => Ok([first, second]),
+        _ => Err(format!("invalid value `{}` for `{}`", value, flag)),
+    }
+}
+
+fn filter(flag: &str, value: Option<String>) -> Result<Filter, String> {
+    let value = value.ok_or_else(|| format!("missing value for `{}`", flag))?;
+
+    match value.as_str() {
+        "nearest" => Ok(Filter::Nearest),
+        "linear" => Ok(Filter::Linear),
+        _ => Err(format!("invalid value `{}` for `{}`", value, flag)),
+    }
+}
+
+fn present_mode(flag: &str, value: Option<String>) -> Result<PresentMode, String> {
+    let value = value.ok_or_else(|| format!("missing value for `{}`", flag))?;
+
+    match value.as_str() {
+        "fifo" => Ok(PresentMode::Fifo),
+        "relaxed" => Ok(PresentMode::Relaxed),
+        "mailbox" => Ok(PresentMode::Mailbox),
+        "immediate" => Ok(PresentMode::Immediate),
+        _ => Err(format!("invalid value `{}` for `{}`", value, flag)),
+    }
+}
+
+fn cull_mode(flag: &str, value: Option<String>) -> Result<CullMode, String> {
+    let value = value.ok_or_else(|| format!("missing value for `{}`", flag))?;
+
+    match value.as_str() {
+        "none" => Ok(CullMode::None),
+        "front" => Ok(CullMode::Front),
+        "back" => Ok(CullMode::Back),
+        "both" => Ok(CullMode::FrontAndBack),
+        _ => Err(format!("invalid value `{}` for `{}`", value, flag)),
+    }
+}
+
+fn front_face(flag: &str, value: Option<String>) -> Result<FrontFace, String> {
+    let value = value.ok_or_else(|| format!("missing value for `{}`", flag))?;
+
+    match value.as_str() {
+        "ccw" => Ok(FrontFace::CounterClockwise),
+        "cw" => Ok(FrontFace::Clockwise),
+        _ => Err(format!("invalid value `{}` for `{}`", value, flag)),
+    }
+}
+
+/// Parses dimensions written as `WIDTHxHEIGHT`.
+fn size(flag: &str, value: Option<String>) -> Result<[u32; 2], String> {
+    let value = value.ok_or_else(|| format!("missing value for `{}`", flag))?;
+    let mut dimensions = value.split('x').map(|dimension| dimension.trim().parse::<u32>());
+
+    match (dimensions.next(), dimensions.next(), dimensions.next()) {
+        (Some(Ok(width)), Some(Ok(height)), None) => Ok([width, height]),
+        _ => Err(format!("invalid value `{}` for `{}`", value, flag)),
+    }
+}
+
+fn sizes(flag: &str, value: Option<String>) -> Result<Vec<[u32; 2]>, String> {
+    let value = value.ok_or_else(|| format!("missing value for `{}`", flag))?;
+
+    value.split(',')
+        .map(|size| self::size(flag, Some(size.to_string())))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| format!("invalid value `{}` for `{}`", value, flag))
+}
+
+fn vector(flag: &str, value: Option<String>) -> Result<[f32; 3], String> {
+    let value = value.ok_or_else(|| format!("missing value for `{}`", flag))?;
+    let mut components = value.split(',').map(|component| component.trim().parse::<f32>());
+
+    match (components.next(), components.next(), components.next(), components.next()) {
+        (Some(Ok(x)), Some(Ok(y)), Some(Ok(z)), None) => Ok([x, y, z]),
+        _ => Err(format!("invalid value `{}` for `{}`", value, flag)),
+    }
+}
+
+/// Parses `NAME` or `NAME=VALUE`, where `NAME` must be an identifier the shader can use.
+fn define(flag: &str, value: Option<String>) -> Result<(String, String), String> {
+    let value = value.ok_or_else(|| format!("missing value for `{}`", flag))?;
+    let (name, definition) = match value.find('=') {
+        Some(index) => (&value[.. index], &value[index + 1 ..]),
+        None => (value.as_str(), "1"),
+    };
+
+    let identifier = name.chars().next().map_or(false, |c| c.is_ascii_alphabetic() || c == '_') &&
+                     name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+
+    if !identifier {
+        return Err(format!("invalid value `{}` for `{}`, expected NAME or NAME=VALUE where NAME \
+                            is made of letters, digits and underscores and doesn't start with a \
+                            digit", value, flag));
+    }
+
+    // The prefixes GLSL reserves for itself.
+    if name.starts_with("GL_") || name.contains("__") {
+        return Err(format!("invalid value `{}` for `{}`, macro names starting with `GL_` or \
+                            containing `__` are reserved", value, flag));
+    }
+
+    Ok((name.to_string(), definition.to_string()))
+}
+
+/// Parses the constant and slope factors of the depth bias, separated by a comma.
+fn pair(flag: &str, value: Option<String>) -> Result<(f32, f32), String> {
+    let value = value.ok_or_else(|| format!("missing value for `{}`", flag))?;
+    let mut numbers = value.split(',').map(|number| number.trim().parse::<f32>());
+
+    match (numbers.next(), numbers.next(), numbers.next()) {
+        (Some(Ok(first)), Some(Ok(second)), None) => Ok((first, second)),
+        _ => Err(format!("invalid value `{}` for `{}`", value, flag)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::io::Write;
+    use std::process;
+
+    use simulation::Simulation;
+
+    fn parse(args: &[&str]) -> Result<Config, String> {
+        Config::parse(args.iter().map(|arg| arg.to_string()))
+    }
+
+    fn error(args: &[&str]) -> String {
+        parse(args).expect_err("the arguments should be rejected")
+    }
+
+    /// Writes `text` to a TOML file of its own in the temporary directory.
+    fn file(name: &str, text: &str) -> String {
+        let path = env::temp_dir()
+            .join(format!("submanifold-config-{}-{}.toml", process::id(), name));
+
+        File::create(&path)
+            .and_then(|mut file| file.write_all(text.as_bytes()))
+            .expect("failed to write the config file");
+
+        path.to_string_lossy().into_owned()
+    }
+
+    /// The view string of the default configuration looking with a field of view of `fov`.
+    fn state(fov: f32) -> String {
+        let config = Config { fov: fov, ..Config::default() };
+
+        View::capture(&config, &Simulation::new(&config)).encode()
+    }
+
+    #[test]
+    fn later_sources_override_earlier_ones() {
+        let config = file("precedence-config", "fov = 40.0\nwidth = 800\nbounces = 3\n");
+        let scene = file("precedence-scene", "fov = 60.0\nbounces = 5\n");
+        let state = state(50.0);
+
+        let fov = |args: &[&str]| parse(args).unwrap().fov;
+
+        assert_eq!(fov(&["--config", &config]), 40.0);
+        assert_eq!(fov(&["--state", &state, "--config", &config]), 50.0);
+        assert_eq!(fov(&["--scene", &scene, "--state", &state, "--config", &config]), 60.0);
+        assert_eq!(fov(&["--fov", "70", "--scene", &scene, "--state", &state,
+                         "--config", &config]), 70.0);
+
+        // Options the later sources don't set are kept from the earlier ones.
+        let all = parse(&["--config", &config, "--state", &state, "--scene", &scene]).unwrap();
+
+        assert_eq!(all.width, 800);
+        assert_eq!(all.bounces, 5);
+        assert_eq!(all.scene, Some(PathBuf::from(&scene)));
+    }
+
+    #[test]
+    fn sources_are_only_found_where_flags_are() {
+        // The value of another flag isn't read as a source.
+        let config = parse(&["--json-report", "--config"]).unwrap();
+
+        assert_eq!(config.json_report, Some(PathBuf::from("--config")));
+        assert_eq!(error(&["--width", "800", "--scene"]), "missing value for `--scene`");
+    }
+
+    #[test]
+    fn file_values_are_validated_like_flags() {
+        let config = file("invalid-fov", "fov = 200.0\n");
+        let color = file("invalid-color", "clear-color = \"nope\"\n");
+
+        assert_eq!(error(&["--config", &config]),
+                   "the field of view must be between 0 and 180 degrees");
+        assert_eq!(error(&["--config", &color]), "invalid value `nope` for `clear-color`");
+    }
+
+    #[test]
+    fn rejects_values_out_of_range() {
+        assert_eq!(error(&["--width", "0"]), "the image dimensions must not be zero");
+        assert_eq!(error(&["--queue-priority", "1.5"]),
+                   "the queue priority must be between 0 and 1");
+        assert_eq!(error(&["--fov", "180"]),
+                   "the field of view must be between 0 and 180 degrees");
+        assert_eq!(error(&["--letterbox", "0.6"]),
+                   "the letterbox size must be between 0 and 0.5");
+        assert_eq!(error(&["--shutter-angle", "361"]),
+                   "the shutter angle must be between 0 and 360 degrees");
+        assert_eq!(error(&["--fixed-time-of-day", "24"]),
+                   "the fixed time of day must be from 0 to 24 hours");
+        assert_eq!(error(&["--bounces", "17"]), "there can be at most 16 bounces");
+        assert_eq!(error(&["--distance-range", "2,1"]),
+                   "the distance range must start at 0 or later and end after it");
+    }
+
+    #[test]
+    fn rejects_malformed_arguments() {
+        assert_eq!(error(&["--width"]), "missing value for `--width`");
+        assert_eq!(error(&["--width", "wide"]), "invalid value `wide` for `--width`");
+        assert_eq!(error(&["--wdith", "800"]), "unknown argument `--wdith`");
+        assert_eq!(error(&["--config"]), "missing value for `--config`");
+    }
+
+    #[test]
+    fn frames_in_flight_are_between_1_and_3() {
+        for &frames in &["1", "2", "3"] {
+            assert!(parse(&["--max-frames-in-flight", frames]).is_ok());
+        }
+
+        for &frames in &["0", "4"] {
+            assert_eq!(error(&["--max-frames-in-flight", frames]),
+                       "the number of frames in flight must be between 1 and 3");
+        }
+    }
+
+    #[test]
+    fn low_latency_overrides_the_frames_in_flight() {
+        let config = parse(&["--max-frames-in-flight", "3", "--low-latency"]).unwrap();
+
+        assert_eq!(config.max_frames_in_flight, 1);
+
+        // A value out of range is still rejected.
+        assert_eq!(error(&["--low-latency", "--max-frames-in-flight", "4"]),
+                   "the number of frames in flight must be between 1 and 3");
+    }
+
+    #[test]
+    fn parses_colors() {
+        assert_eq!(colors("--background-colors", Some("ff0000,#00ff0080".to_string())),
+                   Ok([[1.0, 0.0, 0.0, 1.0], [0.0, 1.0, 0.0, 128.0 / 255.0]]));
+
+        for value in &["ff0000", "ff0000,00ff00,0000ff", "ff0000,00ff0", "ff0000,gg0000"] {
+            assert_eq!(colors("--background-colors", Some(value.to_string())),
+                       Err(format!("invalid value `{}` for `--background-colors`", value)));
+        }
+
+        assert_eq!(colors("--background-colors", None),
+                   Err("missing value for `--background-colors`".to_string()));
+    }
+
+    #[test]
+    fn parses_sizes() {
+        assert_eq!(size("--min-size", Some("640x480".to_string())), Ok([640, 480]));
+        assert_eq!(size("--min-size", Some("640 x 480".to_string())), Ok([640, 480]));
+
+        for value in &["640", "640x480x2", "x480", "-640x480"] {
+            assert_eq!(size("--min-size", Some(value.to_string())),
+                       Err(format!("invalid value `{}` for `--min-size`", value)));
+        }
+    }
+
+    #[test]
+    fn parses_pairs() {
+        assert_eq!(pair("--depth-bias", Some("1.5, -2".to_string())), Ok((1.5, -2.0)));
+
+        for value in &["1.5", "1,2,3", "1,two"] {
+            assert_eq!(pair("--depth-bias", Some(value.to_string())),
+                       Err(format!("invalid value `{}` for `--depth-bias`", value)));
+        }
+    }
+
+    #[test]
+    fn bench_paths_rejects_the_shaders_of_the_user() {
+        let frag = file("bench-frag", "");
+
+        let shaders = vec![vec!["--frag", frag.as_str()], vec!["--spirv-frag", "scene.spv"],
+                           vec!["--hot-reload"]];
+
+        for shader in shaders {
+            let args = [vec!["--bench-paths", "10"], shader].concat();
+
+            assert!(error(&args).contains("`--bench-paths` can't be combined"));
+        }
+
+        assert!(parse(&["--bench-paths", "10"]).is_ok());
+    }
+
+    #[test]
+    fn log_filter_follows_the_verbosity() {
+        let filter = |args: &[&str]| {
+            log_filter(&args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>())
+        };
+
+        assert_eq!(filter(&["--width", "800"]), None);
+        assert_eq!(filter(&["-v"]), Some("info,submanifold=debug"));
+        assert_eq!(filter(&["--verbose", "-v"]), Some("trace"));
+        assert_eq!(filter(&["-vv"]), Some("trace"));
+        // Quiet wins over any verbosity.
+        assert_eq!(filter(&["-vv", "-q"]), Some("error"));
+    }
+}
@@ -0,0 +1,120 @@
+use std::sync::Arc;
+
+use vulkano::buffer::BufferUsage;
+use vulkano::buffer::CpuAccessibleBuffer;
+use vulkano::command_buffer::DynamicState;
+use vulkano::command_buffer::PrimaryCommandBufferBuilderInlineDraw;
+use vulkano::descriptor::pipeline_layout::EmptyPipeline;
+use vulkano::device::Device;
+use vulkano::device::Queue;
+use vulkano::framebuffer::Subpass;
+use vulkano::pipeline::GraphicsPipeline;
+use vulkano::pipeline::GraphicsPipelineParams;
+use vulkano::pipeline::blend::Blend;
+use vulkano::pipeline::depth_stencil::DepthStencil;
+use vulkano::pipeline::input_assembly::InputAssembly;
+use vulkano::pipeline::input_assembly::PrimitiveTopology;
+use vulkano::pipeline::multisample::Multisample;
+use vulkano::pipeline::vertex::SingleBufferDefinition;
+use vulkano::pipeline::viewport::ViewportsState;
+use vulkano::pipeline::viewport::Viewport;
+use vulkano::pipeline::viewport::Scissor;
+
+use config::Config;
+use error::Error;
+use render_pass::CustomRenderPass;
+
+#[derive(Debug, Clone)]
+struct CrosshairVertex {
+    position: [f32; 2],
+    color: [f32; 4],
+}
+impl_vertex!(CrosshairVertex, position, color);
+
+type CrosshairPipeline = GraphicsPipeline<SingleBufferDefinition<CrosshairVertex>,
+                                          EmptyPipeline, CustomRenderPass>;
+
+/// A marker at the center of the image, drawn over the scene to frame it.
+///
+/// The lines are laid out in pixels, so it is created again along with the swapchain to stay
+/// centered and keep its size.
+pub struct Crosshair {
+    pipeline: Arc<CrosshairPipeline>,
+    vertex_buffer: Arc<CpuAccessibleBuffer<[CrosshairVertex]>>,
+}
+
+impl Crosshair {
+    pub fn new(device: &Arc<Device>, queue: &Arc<Queue>, render_pass: &Arc<CustomRenderPass>,
+               dimensions: [u32; 2], config: &Config) -> Result<Crosshair, Error> {
+        let vs = ::graph_vs::Shader::load(device)
+            .map_err(|err| Error::OutOfMemory("the vertex shader module", err))?;
+        let fs = ::graph_fs::Shader::load(device)
+            .map_err(|err| Error::OutOfMemory("the fragment shader module", err))?;
+
+        let pipeline_layout = EmptyPipeline::new(device)
+            .map_err(|err| Error::OutOfMemory("the pipeline layout", err))?;
+
+        let pipeline = GraphicsPipeline::new(device, GraphicsPipelineParams {
+            vertex_input: SingleBufferDefinition::new(),
+            vertex_shader: vs.main_entry_point(),
+            input_assembly: InputAssembly {
+                topology: PrimitiveTopology::LineList,
+                primitive_restart_enable: false,
+            },
+            tessellation: None,
+            geometry_shader: None,
+            viewport: ViewportsState::Fixed {
+                data: vec![(
+                    Viewport {
+                        origin: [0.0, 0.0],
+                        depth_range: 0.0 .. 1.0,
+                        dimensions: [dimensions[0] as f32, dimensions[1] as f32],
+                    },
+                    Scissor::irrelevant()
+                )],
+            },
+            raster: Default::default(),
+            multisample: Multisample::disabled(),
+            fragment_shader: fs.main_entry_point(),
+            depth_stencil: DepthStencil::disabled(),
+            // A translucent color lets the scene show through.
+            blend: Blend::alpha_blending(),
+            layout: &pipeline_layout,
+            render_pass: Subpass::from(render_pass, 0).ok_or_else(Error::no_subpass)?,
+        }).map_err(|err| Error::GraphicsPipeline("the crosshair pipeline", err))?;
+
+        // A horizontal and a vertical line through the center, converted from pixels to
+        // normalized device coordinates.
+        let size = config.crosshair_size as f32;
+        let center = [dimensions[0] as f32 / 2.0, dimensions[1] as f32 / 2.0];
+        let to_ndc = |x: f32, y: f32| {
+            [x / dimensions[0] as f32 * 2.0 - 1.0, y / dimensions[1] as f32 * 2.0 - 1.0]
+        };
+        let positions = [
+            to_ndc(center[0] - size, center[1]), to_ndc(center[0] + size, center[1]),
+            to_ndc(center[0], center[1] - size), to_ndc(center[0], center[1] + size),
+        ];
+        let color = config.crosshair_color;
+
+        let vertex_buffer = CpuAccessibleBuffer::from_iter(device, &BufferUsage::all(),
+                                                           Some(queue.family()),
+                                                           positions.iter().map(|&position| {
+                                                               CrosshairVertex {
+                                                                   position: position,
+                                                                   color: color,
+                                                               }
+                                                           }))
+            .map_err(|err| Error::OutOfMemory("the vertex buffer", err))?;
+
+        Ok(Crosshair {
+            pipeline: pipeline,
+            vertex_buffer: vertex_buffer,
+        })
+    }
+
+    /// Draws the crosshair, within a render pass on the swapchain image.
+    pub fn draw(&self, builder: PrimaryCommandBufferBuilderInlineDraw)
+                -> PrimaryCommandBufferBuilderInlineDraw {
+        builder.draw(&self.pipeline, &self.vertex_buffer, &DynamicState::none(), &(), &())
+    }
+}
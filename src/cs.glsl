@@ -0,0 +1,223 @@
+#version 450
+
+#extension GL_ARB_separate_shader_objects: enable
+#extension GL_ARB_shading_language_420pack: enable
+
+// Tau is the masterconstant; Pi is inferior (Tau = 2 * Pi).
+// See http://tauday.com/tau-manifesto
+#define TAU    6.2831853071795864769252867665590057683943
+#define SQRT_2 1.4142135623730950488016887242096980785696
+
+// The same as in `fs.glsl`.
+#define HEATMAP_MAX_STEPS 8.0
+#define REFERENCE_SPHERE 1u
+#define REFERENCE_GROUND 2u
+
+layout(local_size_x = 16, local_size_y = 16, local_size_z = 1) in;
+
+layout(set = 0, binding = 0) uniform Data {
+    vec2 resolution;
+    // The offset of the sample position within the pixel, in pixels.
+    vec2 jitter;
+    // Shadertoy's `iMouse`, in the pixels of `frag_coord()`: where the cursor was last while the
+    // left button was held, and where the button was pressed, negated while it is released.
+    vec4 mouse;
+    // Where the camera is and the unit vector it looks along. Each is followed by a scalar, which
+    // keeps the block free of padding.
+    vec3 camera_position;
+    float time;
+    vec3 camera_direction;
+    float dt;
+    // The unit vector towards a directional light, its intensity and its color, for shaders that
+    // shade. The built-in scene doesn't.
+    vec3 light_direction;
+    float light_intensity;
+    vec3 light_color;
+    uint debug_tint;
+    float refresh_hz;
+    float exposure;
+    // The number of frames rendered before.
+    uint frame;
+    // Whether the cost of each pixel is shown as a heatmap instead of its color.
+    uint heatmap;
+    // Whether `frag_coord()` and `mouse` have their origin at the bottom-left, with `--flip-y`.
+    uint flip_y;
+    // The objects of `--reference` shown, 1 for the sphere and 2 for the ground plane, the height
+    // of the plane and the radius and center of the sphere.
+    uint reference;
+    float ground_height;
+    float reference_radius;
+    vec3 reference_center;
+    float real_time;
+    vec3 camera_up;
+    float fov;
+    uint bounces;
+    float time_of_day;
+    uint orthographic;
+    float ortho_size;
+} uniforms;
+
+// The swapchain image is written to directly, its format is not known in advance.
+layout(set = 0, binding = 1) uniform writeonly image2D target;
+
+vec3 get_coord_direction(in vec2 coord, in mat3 camera_direction, in float fov_rad) {
+    if(uniforms.orthographic != 0u) {
+        return camera_direction[0];
+    }
+
+    vec2 rel = coord - uniforms.resolution / 2.0;
+    float distance_from_screen_center =
+        length(uniforms.resolution) / (2.0 * tan(fov_rad / 2.0));
+    vec3 direction = camera_direction[0] * distance_from_screen_center
+                     + camera_direction[1] * -rel[0]
+                     + camera_direction[2] * -rel[1];
+    return normalize(direction);
+}
+
+vec3 get_coord_origin(in vec2 coord, in mat3 camera_direction) {
+    if(uniforms.orthographic == 0u) {
+        return uniforms.camera_position;
+    }
+
+    vec2 rel = coord - uniforms.resolution / 2.0;
+    float units_per_pixel = uniforms.ortho_size / length(uniforms.resolution);
+
+    return uniforms.camera_position + (camera_direction[1] * -rel[0] +
+                                       camera_direction[2] * -rel[1]) * units_per_pixel;
+}
+
+// The number of steps taken to trace the pixel and the distance to what it hit, as in `fs.glsl`.
+uint steps = 0u;
+float hit_distance = 0.0;
+
+vec3 heatmap_ramp(in float t) {
+    t = clamp(t, 0.0, 1.0);
+
+    return clamp(vec3(3.0 * t - 1.0, 3.0 * t - 2.0, sin(t * TAU / 2.0) + max(3.0 * t - 2.0, 0.0)),
+                 0.0, 1.0);
+}
+
+vec4 intersect_sphere(in vec3 ray_origin, in vec3 ray_direction,
+                      in vec3 sphere_center, in float radius) {
+    steps += 1u;
+
+    vec3 rel = ray_origin - sphere_center;
+    float a = dot(ray_direction, ray_direction);
+    float b = 2.0 * dot(ray_direction, rel);
+    float c = dot(rel, rel) - radius * radius;
+
+    // Discriminant of a quadratic function
+    float d = b * b - 4.0 * a * c;
+
+    if(d < 0.0) {
+        return vec4(0.0);
+    }
+
+    float sqrt_d = sqrt(d);
+    float dist;
+    float t = (-b - sqrt_d) / (2.0 * a);
+
+    if(t > 0.0) {
+        dist = t;
+    } else {
+        steps += 1u;
+        t = (-b + sqrt_d) / (2.0 * a);
+
+        if(t > 0.0) {
+            dist = t;
+        } else {
+            return vec4(0.0);
+        }
+    }
+
+    hit_distance = dist * length(ray_direction);
+
+    vec3 intersection = ray_origin + ray_direction * dist;
+    vec3 normal = intersection - sphere_center;
+
+    return vec4(normal, 1.0);
+}
+
+vec4 intersect_ground(in vec3 ray_origin, in vec3 ray_direction, in float height) {
+    steps += 1u;
+
+    float dist = (height - ray_origin.z) / ray_direction.z;
+
+    if(!(dist > 0.0) || isinf(dist)) {
+        return vec4(0.0);
+    }
+
+    hit_distance = dist * length(ray_direction);
+
+    vec3 intersection = ray_origin + ray_direction * dist;
+    float checker = mod(floor(intersection.x) + floor(intersection.y), 2.0);
+
+    return vec4(0.0, 0.0, sign(ray_origin.z - height) * (0.5 + 0.5 * checker), 1.0);
+}
+
+void nearest(inout vec4 hit, inout float dist, in vec4 candidate) {
+    if(candidate.w > 0.0 && (hit.w == 0.0 || hit_distance < dist)) {
+        hit = candidate;
+        dist = hit_distance;
+    }
+
+    hit_distance = hit.w > 0.0 ? dist : 0.0;
+}
+
+// The scene of `fs.glsl`, with the objects of `--reference`.
+vec4 trace(in vec3 ray_origin, in vec3 ray_direction) {
+    vec4 hit = intersect_sphere(ray_origin, ray_direction, vec3(3.0, 0.0, 0.0), 1.0);
+    float dist = hit_distance;
+
+    if((uniforms.reference & REFERENCE_SPHERE) != 0u) {
+        nearest(hit, dist, intersect_sphere(ray_origin, ray_direction, uniforms.reference_center,
+                                            uniforms.reference_radius));
+    }
+
+    if((uniforms.reference & REFERENCE_GROUND) != 0u) {
+        nearest(hit, dist, intersect_ground(ray_origin, ray_direction, uniforms.ground_height));
+    }
+
+    return hit;
+}
+
+vec4 apply_debug_tint(in vec4 color) {
+    if(any(isnan(color)) || any(isinf(color))) {
+        return vec4(1.0, 0.0, 1.0, 1.0);
+    }
+
+    return vec4(mix(color.rgb, vec3(0.0, 1.0, 0.0), 0.1), 1.0);
+}
+
+void main() {
+    // The dispatch is rounded up to whole work groups.
+    if(any(greaterThanEqual(gl_GlobalInvocationID.xy, uvec2(imageSize(target))))) {
+        return;
+    }
+
+    // Sample at the pixel center, as `gl_FragCoord` does, offset by the jitter of `--accumulate`.
+    vec2 coord = vec2(gl_GlobalInvocationID.xy) + vec2(0.5) + uniforms.jitter;
+
+    // The same camera as in `fs.glsl`, so that both paths render the same image.
+    vec3 forward = uniforms.camera_direction;
+    vec3 left = normalize(cross(uniforms.camera_up, forward));
+    mat3 camera_direction = mat3(forward, left, cross(forward, left));
+    vec3 camera_location = get_coord_origin(coord, camera_direction);
+    float fov_rad = uniforms.fov;
+    vec3 coord_direction = get_coord_direction(coord, camera_direction,
+                                               fov_rad);
+    vec4 coord_color = trace(camera_location, coord_direction);
+    vec4 color = mix(vec4(fract(coord_direction * 32.0), 1.0), coord_color, 0.90);
+
+    color.rgb *= uniforms.exposure;
+
+    if(uniforms.debug_tint != 0) {
+        color = apply_debug_tint(color);
+    }
+
+    if(uniforms.heatmap != 0) {
+        color = vec4(heatmap_ramp(float(steps) / HEATMAP_MAX_STEPS), 1.0);
+    }
+
+    imageStore(target, ivec2(gl_GlobalInvocationID.xy), color);
+}
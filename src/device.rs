@@ -0,0 +1,174 @@
+use std::env;
+use std::sync::Arc;
+
+use vulkano::instance::Instance;
+use vulkano::instance::PhysicalDevice;
+use vulkano::instance::PhysicalDeviceType;
+
+use config::Config;
+use queue::QueueFamilies;
+use queue::QueueSelector;
+
+/// The environment variable that makes a software implementation preferred.
+pub const SOFTWARE_VAR: &'static str = "SUBMANIFOLD_SOFTWARE";
+
+/// Whether a software implementation such as lavapipe or SwiftShader should be preferred, for
+/// machines without a GPU.
+fn software_requested() -> bool {
+    env::var(SOFTWARE_VAR).map(|value| value == "1").unwrap_or(false)
+}
+
+/// A rough estimate of the device memory each pixel of the scene takes: the offscreen color
+/// image, the depth image, the render targets of `--mrt`, the accumulation images and the buffer
+/// frames are read back through.
+const BYTES_PER_PIXEL: u64 = 64;
+
+/// How much `physical` is preferred over the other devices, the highest wins.
+fn score(physical: &PhysicalDevice, software: bool) -> u32 {
+    match physical.ty() {
+        PhysicalDeviceType::Cpu if software => 1,
+        _ => 0,
+    }
+}
+
+/// Chooses the physical device to render with, the one of `--device` if given.
+///
+/// Without other preferences, that is the first device, which should work most of the time.
+pub fn select_physical_device<'a>(instance: &'a Arc<Instance>, config: &Config)
+                                  -> Result<PhysicalDevice<'a>, String> {
+    if let Some(index) = config.device {
+        return PhysicalDevice::from_index(instance, index).ok_or_else(|| {
+            format!("there is no device {}, there are {}", index,
+                    PhysicalDevice::enumerate(instance).count())
+        });
+    }
+
+    let software = software_requested();
+    let mut best: Option<(PhysicalDevice, u32)> = None;
+
+    // Ties keep the earlier device.
+    for physical in PhysicalDevice::enumerate(instance) {
+        let score = score(&physical, software);
+
+        if best.as_ref().map_or(true, |&(_, best_score)| score > best_score) {
+            best = Some((physical, score));
+        }
+    }
+
+    let physical = best.map(|(physical, _)| physical).ok_or("no device available")?;
+
+    if software && physical.ty() != PhysicalDeviceType::Cpu {
+        warn!("{} is set but there is no software device, falling back to {}", SOFTWARE_VAR,
+              physical.name());
+    }
+
+    Ok(physical)
+}
+
+/// Chooses the device and the queue families to render to the window with, `preferred` if it can
+/// present to the window.
+///
+/// On hybrid graphics, the preferred device may not be the one the display is connected to.
+/// Rendering on it would then need a copy to a swapchain on the other device, which isn't
+/// supported, so the device that presents renders as well, the next best one if there are
+/// several.
+pub fn select_presenting_device<'a>(instance: &'a Arc<Instance>, preferred: PhysicalDevice<'a>,
+                                    queue_selector: &mut QueueSelector)
+                                    -> Result<(PhysicalDevice<'a>, QueueFamilies<'a>), String> {
+    let error = match queue_selector.select(&preferred) {
+        Ok(families) => return Ok((preferred, families)),
+        Err(error) => error,
+    };
+
+    let software = software_requested();
+    let mut best: Option<(PhysicalDevice, QueueFamilies, u32)> = None;
+
+    for physical in PhysicalDevice::enumerate(instance) {
+        if physical.index() == preferred.index() {
+            continue;
+        }
+
+        let families = match queue_selector.select(&physical) {
+            Ok(families) => families,
+            Err(_) => continue,
+        };
+        let score = score(&physical, software);
+
+        if best.as_ref().map_or(true, |&(_, _, best_score)| score > best_score) {
+            best = Some((physical, families, score));
+        }
+    }
+
+    match best {
+        Some((physical, families, _)) => {
+            warn!("`{}` can't present to the window, which is likely connected to another GPU \
+                   (hybrid graphics); rendering on `{}`, which can, instead", preferred.name(),
+                  physical.name());
+            Ok((physical, families))
+        }
+        None => Err(format!("{}, and no other device can present to it either", error)),
+    }
+}
+
+/// `config` with its offscreen resolution clamped to what `physical` can allocate, warning if it
+/// had to be.
+///
+/// The internal resolution, or the frame rendered without a window, is kept within the largest
+/// 2D image of the device and within half of its largest device-local memory heap, keeping its
+/// aspect ratio. The depth and other images of the scene are created at the same resolution, so
+/// they fit as well.
+pub fn clamp_resolution(physical: &PhysicalDevice, config: &Config) -> Config {
+    let max_dimension = physical.limits().max_image_dimension_2d();
+    let heap = physical.memory_heaps()
+        .filter(|heap| heap.is_device_local())
+        .map(|heap| heap.size() as u64)
+        .max()
+        .unwrap_or(0);
+    let max_pixels = heap / 2 / BYTES_PER_PIXEL;
+    let mut config = config.clone();
+
+    let clamp = |dimensions: [u32; 2], what: &str| {
+        let fitted = fit(dimensions, max_dimension, max_pixels);
+
+        if fitted != dimensions {
+            warn!("the {} of {}x{} is too large for `{}` (at most {} pixels across and {} MiB of \
+                   device memory), rendering at {}x{} instead", what, dimensions[0],
+                  dimensions[1], physical.name(), max_dimension, heap / (1024 * 1024), fitted[0],
+                  fitted[1]);
+        }
+
+        fitted
+    };
+
+    match config.internal_res {
+        Some(dimensions) => config.internal_res = Some(clamp(dimensions, "internal resolution")),
+        None => {
+            let dimensions = clamp([config.width, config.height], "resolution");
+
+            config.width = dimensions[0];
+            config.height = dimensions[1];
+        }
+    }
+
+    config
+}
+
+/// `dimensions` scaled down, if needed, to at most `max_dimension` across and `max_pixels` in
+/// total, keeping the aspect ratio.
+///
+/// Without a known memory budget, only the dimension is limited.
+fn fit(dimensions: [u32; 2], max_dimension: u32, max_pixels: u64) -> [u32; 2] {
+    let width = dimensions[0] as f64;
+    let height = dimensions[1] as f64;
+    let mut scale = (max_dimension as f64 / width).min(max_dimension as f64 / height).min(1.0);
+
+    if max_pixels > 0 {
+        scale = scale.min((max_pixels as f64 / (width * height)).sqrt());
+    }
+
+    if scale >= 1.0 {
+        return dimensions;
+    }
+
+    [((width * scale) as u32).max(1), ((height * scale) as u32).max(1)]
+}
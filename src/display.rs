@@ -0,0 +1,140 @@
+use std::sync::Arc;
+
+use vulkano::instance::Instance;
+use vulkano::swapchain::Surface;
+
+use winit::Window;
+
+/// The refresh rate assumed when the one of the monitor can't be queried, in Hz.
+pub const DEFAULT_REFRESH_HZ: f32 = 60.0;
+
+/// The refresh rate of the monitor `window` is shown on, in Hz.
+pub fn refresh_rate(window: &Window) -> f32 {
+    match query_refresh_rate(window) {
+        Some(refresh_hz) => refresh_hz,
+        None => {
+            warn!("the refresh rate of the monitor can't be queried, assuming {} Hz",
+                  DEFAULT_REFRESH_HZ);
+            DEFAULT_REFRESH_HZ
+        }
+    }
+}
+
+/// Notices when the scale factor of `window` changes, as it does when the window is moved
+/// between monitors with different scaling.
+///
+/// The version of winit in use has no event for it, so the factor is compared whenever the window
+/// moves.
+#[derive(Debug, Clone, Copy)]
+pub struct ScaleFactor {
+    factor: f32,
+}
+
+impl ScaleFactor {
+    pub fn new(window: &Window) -> ScaleFactor {
+        ScaleFactor { factor: window.hidpi_factor() }
+    }
+
+    /// Whether the scale factor of `window` has changed since the previous call.
+    pub fn changed(&mut self, window: &Window) -> bool {
+        let factor = window.hidpi_factor();
+
+        if factor == self.factor {
+            return false;
+        }
+
+        info!("the scale factor changed from {} to {}", self.factor, factor);
+        self.factor = factor;
+
+        true
+    }
+}
+
+/// Creates a new surface for `window`, to replace one that was lost, for example when the
+/// compositor restarted or after switching virtual terminals.
+///
+/// This does what `build_vk_surface` does for a new window, which can't be asked for again.
+#[cfg(all(unix, not(target_os = "android"), not(target_os = "macos")))]
+pub fn create_surface(instance: &Arc<Instance>, window: &Window) -> Result<Arc<Surface>, String> {
+    use winit::os::unix::WindowExt;
+
+    let surface = unsafe {
+        match (window.get_wayland_display(), window.get_wayland_surface()) {
+            (Some(display), Some(surface)) => {
+                Surface::from_wayland(instance, display as *const _, surface as *const _)
+            }
+            _ => {
+                let window_id = window.get_xlib_window()
+                    .ok_or("the window has neither a Wayland surface nor an X11 window")?;
+
+                if instance.loaded_extensions().khr_xlib_surface {
+                    let display = window.get_xlib_display().ok_or("the X11 display is gone")?;
+
+                    Surface::from_xlib(instance, display, window_id as _)
+                } else {
+                    let connection = window.get_xcb_connection()
+                        .ok_or("the XCB connection is gone")?;
+
+                    Surface::from_xcb(instance, connection, window_id as _)
+                }
+            }
+        }
+    };
+
+    surface.map_err(|err| format!("failed to create the surface: {}", err))
+}
+
+#[cfg(not(all(unix, not(target_os = "android"), not(target_os = "macos"))))]
+pub fn create_surface(_instance: &Arc<Instance>, _window: &Window)
+                      -> Result<Arc<Surface>, String> {
+    Err("lost surfaces can only be recreated on Linux and the BSDs".to_string())
+}
+
+/// Asks XRandR for the rate of the current mode of the screen of `window`.
+///
+/// The version of winit in use doesn't expose the video modes of the monitors, so this goes to
+/// the X server directly. RandR 1.1 knows a single mode per screen, which is the one of the
+/// primary monitor when there are several.
+#[cfg(all(unix, not(target_os = "android"), not(target_os = "macos")))]
+fn query_refresh_rate(window: &Window) -> Option<f32> {
+    use std::os::raw::c_short;
+    use std::os::raw::c_ulong;
+    use std::os::raw::c_void;
+
+    use winit::os::unix::WindowExt;
+
+    #[link(name = "Xrandr")]
+    extern "C" {
+        fn XRRGetScreenInfo(display: *mut c_void, window: c_ulong) -> *mut c_void;
+        fn XRRConfigCurrentRate(config: *mut c_void) -> c_short;
+        fn XRRFreeScreenConfigInfo(config: *mut c_void);
+    }
+
+    // Wayland has no such query, the rate of an output is only sent to its listeners.
+    let display = window.get_xlib_display()?;
+    let window_id = window.get_xlib_window()?;
+
+    let rate = unsafe {
+        let config = XRRGetScreenInfo(display as *mut c_void, window_id as c_ulong);
+
+        if config.is_null() {
+            return None;
+        }
+
+        let rate = XRRConfigCurrentRate(config);
+        XRRFreeScreenConfigInfo(config);
+
+        rate
+    };
+
+    if rate > 0 {
+        Some(rate as f32)
+    } else {
+        None
+    }
+}
+
+#[cfg(not(all(unix, not(target_os = "android"), not(target_os = "macos"))))]
+fn query_refresh_rate(_window: &Window) -> Option<f32> {
+    None
+}
@@ -0,0 +1,76 @@
+use std::io;
+
+use thiserror::Error;
+use vulkano::OomError;
+use vulkano::framebuffer::FramebufferCreationError;
+use vulkano::framebuffer::RenderPassCreationError;
+use vulkano::pipeline::GraphicsPipelineCreationError;
+use vulkano::sampler::SamplerCreationError;
+
+/// What can go wrong while setting up or capturing, by the step that failed.
+///
+/// The helpers of each step already explain the failure in their messages, which are kept as
+/// they are.
+#[derive(Debug, Error)]
+pub enum Error {
+    /// The command line or the config file is invalid.
+    #[error("{0}")]
+    Config(String),
+    #[error("{0}")]
+    Instance(String),
+    /// No device could be chosen or created.
+    #[error("{0}")]
+    Device(String),
+    #[error("{0}")]
+    Window(String),
+    #[error("{0}")]
+    Swapchain(String),
+    /// A render pass, pipeline or one of their layouts couldn't be created.
+    #[error("{0}")]
+    Pipeline(String),
+    /// A shader couldn't be compiled, read or loaded, or doesn't match the uniform buffer.
+    #[error("{0}")]
+    Shader(String),
+    /// A frame couldn't be rendered or saved, headless or as a screenshot.
+    #[error("{0}")]
+    Capture(String),
+    /// The mesh of `--mesh` couldn't be loaded or uploaded.
+    #[error("{0}")]
+    Mesh(String),
+    /// The report of `--json-report` couldn't be written.
+    #[error("{0}")]
+    Report(String),
+    /// A buffer, an image, a shader module or a pipeline layout, named by the first field,
+    /// couldn't be allocated.
+    #[error("failed to create {0}: {1}")]
+    OutOfMemory(&'static str, #[source] OomError),
+    #[error("failed to create {0}: {1}")]
+    RenderPass(&'static str, #[source] RenderPassCreationError),
+    #[error("failed to create {0}: {1}")]
+    Framebuffer(&'static str, #[source] FramebufferCreationError),
+    /// The pipeline named by the first field was rejected, for example because a reloaded
+    /// shader doesn't fit its layout.
+    #[error("failed to create {0}: {1}")]
+    GraphicsPipeline(&'static str, #[source] GraphicsPipelineCreationError),
+    #[error("failed to create the sampler: {0}")]
+    Sampler(#[source] SamplerCreationError),
+    /// A file couldn't be read or written, the first field says which and what for.
+    #[error("{0}: {1}")]
+    Io(String, #[source] io::Error),
+}
+
+impl Error {
+    /// The code the process exits with, 2 for invalid options as is usual and 1 otherwise.
+    pub fn exit_code(&self) -> i32 {
+        match *self {
+            Error::Config(_) => 2,
+            _ => 1,
+        }
+    }
+
+    /// The render pass a pipeline is created for lacks its first subpass, which every render
+    /// pass here has.
+    pub fn no_subpass() -> Error {
+        Error::Pipeline("the render pass has no subpass".to_string())
+    }
+}
@@ -0,0 +1,228 @@
+use std::mem;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
+
+use vulkano::buffer::BufferUsage;
+use vulkano::buffer::CpuAccessibleBuffer;
+use vulkano::command_buffer::DynamicState;
+use vulkano::command_buffer::PrimaryCommandBufferBuilder;
+use vulkano::descriptor::descriptor_set::DescriptorPool;
+use vulkano::device::Device;
+use vulkano::device::Queue;
+use vulkano::format::Format;
+use vulkano::framebuffer::Framebuffer;
+use vulkano::image::Dimensions;
+use vulkano::image::StorageImage;
+
+use simulation::Time;
+use config::Config;
+use error::Error;
+use memory::MemoryUsage;
+use render_pass::CustomRenderPass;
+use shader::FragmentShader;
+use shader::VertexShader;
+use state::State;
+use Pipeline;
+use Vertex;
+
+/// The width and height of the image the luminance is measured in.
+const SIZE: u32 = 16;
+
+/// A float format, so that the measurement isn't clamped to the range of the swapchain.
+const FORMAT: Format = Format::R32G32B32A32Sfloat;
+
+/// The range the exposure is kept in, so that a black or blinding frame doesn't throw it off.
+const MIN_EXPOSURE: f32 = 1.0 / 64.0;
+const MAX_EXPOSURE: f32 = 64.0;
+
+/// What is measured of a frame, at an exposure of 1.
+#[derive(Debug, Clone, Copy)]
+pub struct Measurement {
+    /// The log-average luminance.
+    pub luminance: f32,
+    /// The mean of the linear colors.
+    pub color: [f32; 3],
+}
+
+/// Measures the average luminance and color of the scene, by rendering it again into a tiny
+/// offscreen image and reading that back.
+///
+/// The image is read two frames after it was rendered, by which time the GPU is normally done
+/// with it, so that measuring doesn't stall the frame.
+pub struct Metering {
+    uniform_buffer: Arc<CpuAccessibleBuffer<::vs::ty::Data>>,
+    /// The descriptor sets of both frame parities, see `State::scratch_sets`.
+    sets: [Arc<::pipeline_layout::set0::Set>; 2],
+    render_pass: Arc<CustomRenderPass>,
+    framebuffer: Arc<Framebuffer<CustomRenderPass>>,
+    image: Arc<StorageImage<Format>>,
+    pipeline: Arc<Pipeline>,
+    // Alternating between frames, each one is read right before it is written to again.
+    readback: [Arc<CpuAccessibleBuffer<[[f32; 4]]>>; 2],
+    written: [bool; 2],
+    current: usize,
+}
+
+impl Metering {
+    pub fn new(device: &Arc<Device>, queue: &Arc<Queue>, config: &Config, vs: &VertexShader,
+               fs: &FragmentShader, pipeline_layout: &Arc<::pipeline_layout::CustomPipeline>,
+               descriptor_pool: &Arc<DescriptorPool>, state: &State)
+               -> Result<Metering, Error> {
+        let image = StorageImage::new(device, Dimensions::Dim2d { width: SIZE, height: SIZE },
+                                      FORMAT, Some(queue.family()))
+            .map_err(|err| Error::OutOfMemory("the metering image", err))?;
+
+        let render_pass = CustomRenderPass::new(device, &::render_pass::Formats {
+            color: (FORMAT, 1)
+        }).map_err(|err| Error::RenderPass("the metering render pass", err))?;
+
+        let framebuffer = Framebuffer::new(&render_pass, [SIZE, SIZE, 1], ::render_pass::AList {
+            color: &image
+        }).map_err(|err| Error::Framebuffer("the metering framebuffer", err))?;
+
+        let uniform_buffer = ::create_uniform_buffer(device, queue, config, [SIZE, SIZE], 0.0,
+                                                     Time::fixed(0.0), 1.0)?;
+
+        // The scene is measured with the state the frame reads, and the state written while
+        // measuring is thrown away, so that the scene isn't advanced twice per frame.
+        let scratch = state.scratch(device, queue)?;
+        let sets = state.scratch_sets(descriptor_pool, pipeline_layout, &uniform_buffer,
+                                      &scratch);
+
+        let pipeline = ::create_pipeline(device, config, vs, fs, pipeline_layout, &render_pass,
+                                         [SIZE, SIZE])?;
+
+        let readback = || {
+            CpuAccessibleBuffer::array(device, (SIZE * SIZE) as usize, &BufferUsage::all(),
+                                       Some(queue.family()))
+                .map_err(|err| Error::OutOfMemory("the metering readback buffer", err))
+        };
+
+        Ok(Metering {
+            uniform_buffer: uniform_buffer,
+            sets: sets,
+            render_pass: render_pass,
+            framebuffer: framebuffer,
+            image: image,
+            pipeline: pipeline,
+            readback: [readback()?, readback()?],
+            written: [false, false],
+            current: 0,
+        })
+    }
+
+    /// Recreates the pipeline, after the shaders of the scene have changed.
+    pub fn set_shaders(&mut self, device: &Arc<Device>, config: &Config, vs: &VertexShader,
+                       fs: &FragmentShader,
+                       pipeline_layout: &Arc<::pipeline_layout::CustomPipeline>)
+                       -> Result<(), Error> {
+        self.pipeline = ::create_pipeline(device, config, vs, fs, pipeline_layout,
+                                          &self.render_pass, [SIZE, SIZE])?;
+
+        Ok(())
+    }
+
+    /// Adds the metering image and the buffers it is read back into to `usage`.
+    pub fn memory_usage(&self, usage: &mut MemoryUsage) {
+        usage.image("metering image", 1, [SIZE, SIZE], FORMAT);
+        usage.buffer("metering readback buffers", 2,
+                     (SIZE * SIZE) as usize * mem::size_of::<[f32; 4]>());
+    }
+
+    /// Moves on to the next frame at `time`, returning the measurement of the frame two frames
+    /// before if it has been measured.
+    ///
+    /// The exposure of the measured frame is always 1, so that the result doesn't depend on the
+    /// exposure it is used to adjust.
+    pub fn next_frame(&mut self, config: &Config, refresh_hz: f32, time: Time)
+                      -> Option<Measurement> {
+        self.current = 1 - self.current;
+
+        *self.uniform_buffer.write(Duration::new(1, 0)).unwrap() =
+            ::simulation::uniforms(config, [SIZE, SIZE], refresh_hz, time, 1.0, 0, [0.0, 0.0]);
+
+        if !self.written[self.current] {
+            return None;
+        }
+
+        let pixels = self.readback[self.current].read(Duration::new(1, 0)).ok()?;
+
+        // The log-average, so that a few very bright pixels don't dominate.
+        let sum = pixels.iter().map(|pixel| {
+            let luminance = 0.2126 * pixel[0] + 0.7152 * pixel[1] + 0.0722 * pixel[2];
+
+            luminance.max(1e-4).ln()
+        }).sum::<f32>();
+        let count = pixels.len() as f32;
+        let mut color = [0.0; 3];
+
+        for pixel in pixels.iter() {
+            for channel in 0 .. 3 {
+                color[channel] += pixel[channel] / count;
+            }
+        }
+
+        Some(Measurement {
+            luminance: (sum / count).exp(),
+            color: color,
+        })
+    }
+
+    /// Records the measurement of the current frame, whose state parity is `parity`, see
+    /// `State::current`.
+    pub fn record(&mut self, builder: PrimaryCommandBufferBuilder,
+                  vertex_buffer: &Arc<CpuAccessibleBuffer<[Vertex]>>, parity: usize)
+                  -> PrimaryCommandBufferBuilder {
+        self.written[self.current] = true;
+
+        builder.draw_inline(&self.render_pass, &self.framebuffer, ::render_pass::ClearValues {
+                   color: [0.0, 0.0, 0.0, 1.0]
+               })
+               .draw(&self.pipeline, vertex_buffer, &DynamicState::none(),
+                     &self.sets[parity], &())
+               .draw_end()
+               .copy_color_image_to_buffer(&self.readback[self.current], &self.image, 0, 0 .. 1,
+                                           [0, 0, 0], [SIZE, SIZE, 1])
+    }
+}
+
+/// Drives the exposure towards the one that makes the average luminance the target gray.
+///
+/// It adapts in real time, even if the time passed to the shaders is fixed.
+pub struct AutoExposure {
+    exposure: f32,
+    target: f32,
+    speed: f32,
+    last_adapted: Instant,
+}
+
+impl AutoExposure {
+    pub fn new(config: &Config) -> AutoExposure {
+        AutoExposure {
+            exposure: config.exposure,
+            target: config.exposure_target,
+            speed: config.adaptation_speed,
+            last_adapted: Instant::now(),
+        }
+    }
+
+    pub fn exposure(&self) -> f32 {
+        self.exposure
+    }
+
+    /// Adapts to the average luminance `luminance` measured at an exposure of 1, over the time
+    /// since the previous adaptation.
+    pub fn adapt(&mut self, luminance: f32) {
+        let now = Instant::now();
+        let elapsed = now - self.last_adapted;
+        let dt = elapsed.as_secs() as f32 + elapsed.subsec_nanos() as f32 / 1_000_000_000.0;
+
+        self.last_adapted = now;
+
+        let goal = (self.target / luminance).max(MIN_EXPOSURE).min(MAX_EXPOSURE);
+
+        // Exponential smoothing, which behaves the same regardless of the frame rate.
+        self.exposure += (goal - self.exposure) * (1.0 - (-self.speed * dt).exp());
+    }
+}
@@ -8,12 +8,113 @@
 #define TAU    6.2831853071795864769252867665590057683943
 #define SQRT_2 1.4142135623730950488016887242096980785696
 
+// The number of steps shown as the hottest color of the heatmap.
+#define HEATMAP_MAX_STEPS 8.0
+
+// The flags of `uniforms.reference`.
+#define REFERENCE_SPHERE 1u
+#define REFERENCE_GROUND 2u
+
+// The ids of the objects of the built-in scene, written to the `id` render target with `--mrt`
+// and reported when one is right-clicked. 0 is nothing, such as the sky.
+#define OBJECT_NONE             0u
+#define OBJECT_SPHERE           1u
+#define OBJECT_REFERENCE_SPHERE 2u
+#define OBJECT_GROUND           3u
+
+layout(set = 0, binding = 0) uniform Data {
+    vec2 resolution;
+    // The offset of the sample position within the pixel, in pixels.
+    vec2 jitter;
+    // Shadertoy's `iMouse`, in the pixels of `frag_coord()`: where the cursor was last while the
+    // left button was held, and where the button was pressed, negated while it is released.
+    vec4 mouse;
+    // Where the camera is and the unit vector it looks along. Each is followed by a scalar, which
+    // keeps the block free of padding.
+    vec3 camera_position;
+    float time;
+    vec3 camera_direction;
+    float dt;
+    // The unit vector towards a directional light, its intensity and its color, for shaders that
+    // shade. The built-in scene doesn't.
+    vec3 light_direction;
+    float light_intensity;
+    vec3 light_color;
+    uint debug_tint;
+    float refresh_hz;
+    float exposure;
+    // The number of frames rendered before.
+    uint frame;
+    // Whether the cost of each pixel is shown as a heatmap instead of its color.
+    uint heatmap;
+    // Whether `frag_coord()` and `mouse` have their origin at the bottom-left, with `--flip-y`.
+    uint flip_y;
+    // The objects of `--reference` shown, 1 for the sphere and 2 for the ground plane, the height
+    // of the plane and the radius and center of the sphere.
+    uint reference;
+    float ground_height;
+    float reference_radius;
+    vec3 reference_center;
+    float real_time;
+    vec3 camera_up;
+    float fov;
+    uint bounces;
+    float time_of_day;
+    uint orthographic;
+    float ortho_size;
+} uniforms;
+
+// State kept across frames, read from the previous frame and written for the next one. Its
+// length is set with `--state-size`.
+layout(set = 0, binding = 1) readonly buffer PreviousState {
+    float data[];
+} previous_state;
+
+layout(set = 0, binding = 2) writeonly buffer State {
+    float data[];
+} state;
+
 layout(location = 0) in vec2 resolution;
 
 layout(location = 0) out vec4 f_color;
+// Only written to attachments with `--mrt`, for debugging. Without it, the pipeline discards them.
+layout(location = 1) out vec4 f_normal;
+// The id is an integer, so that it reads back exactly as written.
+layout(location = 2) out uint f_id;
+layout(location = 3) out vec4 f_distance;
+
+// The position of the fragment in pixels, with the origin at the top-left as in Vulkan, or at the
+// bottom-left as in OpenGL and Shadertoy with `--flip-y`. The built-in scene below uses
+// `gl_FragCoord` and doesn't flip.
+vec2 frag_coord() {
+    vec2 coord = gl_FragCoord.xy;
+
+    if(uniforms.flip_y != 0u) {
+        coord.y = uniforms.resolution.y - coord.y;
+    }
+
+    return coord;
+}
+
+// Shadertoy's uniforms, so that its shaders can be pasted in with few edits: its
+// `mainImage(out vec4 fragColor, in vec2 fragCoord)` is called from `main` as
+// `mainImage(f_color, frag_coord())`, with `--flip-y` for its bottom-left origin. `iResolution`
+// is in pixels with a pixel aspect ratio of 1, `iTime` and `iTimeDelta` are the time of the
+// simulation in seconds, which pausing and `--time-scale` affect, and `iFrame` counts the frames
+// rendered before. Unlike on Shadertoy, the alpha of `fragColor` is kept, and over a gradient
+// `--background` blended with, so it is best set to 1.
+#define iResolution vec3(uniforms.resolution, 1.0)
+#define iTime uniforms.time
+#define iTimeDelta uniforms.dt
+#define iFrame int(uniforms.frame)
+#define iMouse uniforms.mouse
 
 vec3 get_coord_direction(in mat3 camera_direction, in float fov_rad) {
-    vec2 rel = gl_FragCoord.xy - resolution / 2.0;
+    if(uniforms.orthographic != 0u) {
+        return camera_direction[0];
+    }
+
+    vec2 rel = gl_FragCoord.xy + uniforms.jitter - resolution / 2.0;
     float distance_from_screen_center =
         length(resolution) / (2.0 * tan(fov_rad / 2.0));
     vec3 direction = camera_direction[0] * distance_from_screen_center
@@ -22,8 +123,43 @@ vec3 get_coord_direction(in mat3 camera_direction, in float fov_rad) {
     return normalize(direction);
 }
 
+// Where the ray of the pixel starts: at the camera, or with `--ortho` at the point of the pixel
+// on the plane through the camera facing along `camera_direction[0]`.
+vec3 get_coord_origin(in mat3 camera_direction) {
+    if(uniforms.orthographic == 0u) {
+        return uniforms.camera_position;
+    }
+
+    vec2 rel = gl_FragCoord.xy + uniforms.jitter - resolution / 2.0;
+    float units_per_pixel = uniforms.ortho_size / length(resolution);
+
+    return uniforms.camera_position + (camera_direction[1] * -rel[0] +
+                                       camera_direction[2] * -rel[1]) * units_per_pixel;
+}
+
+// The number of steps taken to trace the pixel, shown with the heatmap. A raymarching loop
+// counts its iterations into it, the built-in scene counts each root it evaluates.
+uint steps = 0u;
+
+// The distance along the ray to what the pixel hit, or 0 where nothing was, saved in grayscale
+// with `--mrt`.
+float hit_distance = 0.0;
+
+// The id of the object the pixel hit, one of the `OBJECT_` ones.
+uint hit_id = OBJECT_NONE;
+
+// Maps 0..1 from black through blue and magenta to white.
+vec3 heatmap_ramp(in float t) {
+    t = clamp(t, 0.0, 1.0);
+
+    return clamp(vec3(3.0 * t - 1.0, 3.0 * t - 2.0, sin(t * TAU / 2.0) + max(3.0 * t - 2.0, 0.0)),
+                 0.0, 1.0);
+}
+
 vec4 intersect_sphere(in vec3 ray_origin, in vec3 ray_direction,
                       in vec3 sphere_center, in float radius) {
+    steps += 1u;
+
     vec3 rel = ray_origin - sphere_center;
     float a = dot(ray_direction, ray_direction);
     float b = 2.0 * dot(ray_direction, rel);
@@ -43,6 +179,7 @@ vec4 intersect_sphere(in vec3 ray_origin, in vec3 ray_direction,
     if(t > 0.0) {
         dist = t;
     } else {
+        steps += 1u;
         t = (-b + sqrt_d) / (2.0 * a);
 
         if(t > 0.0) {
@@ -52,27 +189,108 @@ vec4 intersect_sphere(in vec3 ray_origin, in vec3 ray_direction,
         }
     }
 
+    hit_distance = dist * length(ray_direction);
+
     vec3 intersection = ray_origin + ray_direction * dist;
     vec3 normal = intersection - sphere_center;
 
     return vec4(normal, 1.0);
 }
 
+// The ground plane at `height` as seen from above or below, its normal darkened in every other
+// unit square so that it shows the scale.
+vec4 intersect_ground(in vec3 ray_origin, in vec3 ray_direction, in float height) {
+    steps += 1u;
+
+    float dist = (height - ray_origin.z) / ray_direction.z;
+
+    if(!(dist > 0.0) || isinf(dist)) {
+        return vec4(0.0);
+    }
+
+    hit_distance = dist * length(ray_direction);
+
+    vec3 intersection = ray_origin + ray_direction * dist;
+    float checker = mod(floor(intersection.x) + floor(intersection.y), 2.0);
+
+    return vec4(0.0, 0.0, sign(ray_origin.z - height) * (0.5 + 0.5 * checker), 1.0);
+}
+
+// Keeps the nearer of `hit` of the object `id` at `dist` and the last intersection, which is of
+// `candidate_id`, along with `hit_distance`.
+void nearest(inout vec4 hit, inout float dist, inout uint id, in vec4 candidate,
+             in uint candidate_id) {
+    if(candidate.w > 0.0 && (hit.w == 0.0 || hit_distance < dist)) {
+        hit = candidate;
+        dist = hit_distance;
+        id = candidate_id;
+    }
+
+    hit_distance = hit.w > 0.0 ? dist : 0.0;
+}
+
 vec4 trace(in vec3 ray_origin, in vec3 ray_direction) {
-    return intersect_sphere(ray_origin, ray_direction, vec3(3.0, 0.0, 0.0), 1.0);
+    vec4 hit = intersect_sphere(ray_origin, ray_direction, vec3(3.0, 0.0, 0.0), 1.0);
+    float dist = hit_distance;
+    uint id = hit.w > 0.0 ? OBJECT_SPHERE : OBJECT_NONE;
+
+    if((uniforms.reference & REFERENCE_SPHERE) != 0u) {
+        nearest(hit, dist, id,
+                intersect_sphere(ray_origin, ray_direction, uniforms.reference_center,
+                                 uniforms.reference_radius),
+                OBJECT_REFERENCE_SPHERE);
+    }
+
+    if((uniforms.reference & REFERENCE_GROUND) != 0u) {
+        nearest(hit, dist, id,
+                intersect_ground(ray_origin, ray_direction, uniforms.ground_height),
+                OBJECT_GROUND);
+    }
+
+    hit_id = id;
+    return hit;
+}
+
+// Makes a live but broken shader distinguishable from a pipeline that doesn't draw at all:
+// invalid output turns magenta and everything else, including black, gets a faint green tint.
+vec4 apply_debug_tint(in vec4 color) {
+    if(any(isnan(color)) || any(isinf(color))) {
+        return vec4(1.0, 0.0, 1.0, 1.0);
+    }
+
+    return vec4(mix(color.rgb, vec3(0.0, 1.0, 0.0), 0.1), 1.0);
 }
 
 void main() {
-    vec3 camera_location = vec3(0.0);
-    mat3 camera_direction = mat3(
-                                vec3(1.0, 0.0, 0.0),
-                                vec3(0.0, 1.0, 0.0),
-                                vec3(0.0, 0.0, 1.0)
-                            );
-    float fov_rad = radians(90.0);
+    // The columns are the forward, left and up directions of the camera.
+    vec3 forward = uniforms.camera_direction;
+    vec3 left = normalize(cross(uniforms.camera_up, forward));
+    mat3 camera_direction = mat3(forward, left, cross(forward, left));
+    vec3 camera_location = get_coord_origin(camera_direction);
+    float fov_rad = uniforms.fov;
     vec2 coord_normalized = 2.0 * gl_FragCoord.xy / resolution.xy - vec2(1.0);
     vec3 coord_direction = get_coord_direction(camera_direction,
                                                fov_rad);
     vec4 coord_color = trace(camera_location, coord_direction);
+
+    // The normal is mapped from -1..1 to 0..1 so that it can be looked at.
+    if(coord_color.w > 0.0) {
+        f_normal = vec4(normalize(coord_color.xyz) * 0.5 + 0.5, 1.0);
+    } else {
+        f_normal = vec4(0.0, 0.0, 0.0, 1.0);
+    }
+
+    f_id = hit_id;
+    f_distance = vec4(hit_distance, 0.0, 0.0, 1.0);
+
     f_color = mix(vec4(fract(coord_direction * 32.0), 1.0), coord_color, 0.90);
+    f_color.rgb *= uniforms.exposure;
+
+    if(uniforms.debug_tint != 0) {
+        f_color = apply_debug_tint(f_color);
+    }
+
+    if(uniforms.heatmap != 0) {
+        f_color = vec4(heatmap_ramp(float(steps) / HEATMAP_MAX_STEPS), 1.0);
+    }
 }
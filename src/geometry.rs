@@ -0,0 +1,58 @@
+use std::sync::Arc;
+
+use vulkano::buffer::Buffer;
+use vulkano::buffer::BufferUsage;
+use vulkano::buffer::CpuAccessibleBuffer;
+use vulkano::device::Device;
+use vulkano::device::Queue;
+
+use memory::MemoryUsage;
+use Vertex;
+
+/// The index that starts a new strip or fan with `--primitive-restart`, instead of referring to
+/// a vertex.
+pub const RESTART_INDEX: u32 = 0xFFFF_FFFF;
+
+/// Indexed geometry of vertices of the type `V`, which the scene is drawn with instead of the
+/// vertices covering the screen, or a mesh with `mesh::MeshVertex`.
+///
+/// The other passes, which copy and blend whole images, still draw the vertices covering the
+/// screen.
+pub struct Geometry<V: 'static = Vertex> {
+    vertices: Arc<CpuAccessibleBuffer<[V]>>,
+    indices: Arc<CpuAccessibleBuffer<[u32]>>,
+}
+
+impl<V> Geometry<V> where V: Send + Sync + 'static {
+    /// Uploads `vertices` and the `indices` into them, in the topology of the pipeline that draws
+    /// them.
+    pub fn new(device: &Arc<Device>, queue: &Arc<Queue>, vertices: Vec<V>, indices: Vec<u32>)
+               -> Result<Geometry<V>, String> {
+        if let Some(&index) = indices.iter()
+            .find(|&&index| index != RESTART_INDEX && index as usize >= vertices.len()) {
+            return Err(format!("the index {} is out of the {} vertices", index, vertices.len()));
+        }
+
+        let vertices = CpuAccessibleBuffer::from_iter(device, &BufferUsage::all(),
+                                                      Some(queue.family()), vertices.into_iter())
+            .map_err(|err| format!("failed to create the vertex buffer: {}", err))?;
+        let indices = CpuAccessibleBuffer::from_iter(device, &BufferUsage::all(),
+                                                     Some(queue.family()), indices.into_iter())
+            .map_err(|err| format!("failed to create the index buffer: {}", err))?;
+
+        Ok(Geometry { vertices: vertices, indices: indices })
+    }
+
+    pub fn vertices(&self) -> &Arc<CpuAccessibleBuffer<[V]>> {
+        &self.vertices
+    }
+
+    pub fn indices(&self) -> &Arc<CpuAccessibleBuffer<[u32]>> {
+        &self.indices
+    }
+
+    pub fn memory_usage(&self, usage: &mut MemoryUsage) {
+        usage.buffer("geometry vertex buffer", 1, self.vertices.size());
+        usage.buffer("geometry index buffer", 1, self.indices.size());
+    }
+}
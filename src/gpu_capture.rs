@@ -0,0 +1,42 @@
+use renderdoc::RenderDoc;
+use renderdoc::V100;
+
+/// Captures single frames through the RenderDoc in-application API, so that a frame can be
+/// captured from a key without setting up RenderDoc to launch the program.
+pub struct GpuCapture {
+    renderdoc: Option<RenderDoc<V100>>,
+}
+
+impl GpuCapture {
+    /// Connects to RenderDoc if `enabled` and its library is loaded into the process, warning if
+    /// it isn't.
+    ///
+    /// RenderDoc only hooks into the instances created after it is loaded, so this must come
+    /// first.
+    pub fn new(enabled: bool) -> GpuCapture {
+        let renderdoc = if enabled {
+            match RenderDoc::new() {
+                Ok(renderdoc) => Some(renderdoc),
+                Err(err) => {
+                    warn!("RenderDoc is not available, frames can't be captured: {}", err);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        GpuCapture { renderdoc: renderdoc }
+    }
+
+    /// Makes RenderDoc capture the next frame, if it is connected.
+    pub fn trigger(&mut self) {
+        match self.renderdoc {
+            Some(ref mut renderdoc) => {
+                renderdoc.trigger_capture();
+                info!("capturing the next frame with RenderDoc");
+            }
+            None => warn!("frames can only be captured with `--renderdoc` under RenderDoc"),
+        }
+    }
+}
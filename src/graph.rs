@@ -0,0 +1,182 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Duration;
+
+use vulkano::buffer::BufferUsage;
+use vulkano::buffer::CpuAccessibleBuffer;
+use vulkano::command_buffer::DynamicState;
+use vulkano::command_buffer::PrimaryCommandBufferBuilderInlineDraw;
+use vulkano::descriptor::pipeline_layout::EmptyPipeline;
+use vulkano::device::Device;
+use vulkano::device::Queue;
+use vulkano::framebuffer::Subpass;
+use vulkano::pipeline::GraphicsPipeline;
+use vulkano::pipeline::GraphicsPipelineParams;
+use vulkano::pipeline::blend::Blend;
+use vulkano::pipeline::depth_stencil::DepthStencil;
+use vulkano::pipeline::input_assembly::InputAssembly;
+use vulkano::pipeline::input_assembly::PrimitiveTopology;
+use vulkano::pipeline::multisample::Multisample;
+use vulkano::pipeline::vertex::SingleBufferDefinition;
+use vulkano::pipeline::viewport::ViewportsState;
+use vulkano::pipeline::viewport::Viewport;
+use vulkano::pipeline::viewport::Scissor;
+
+use error::Error;
+use render_pass::CustomRenderPass;
+
+/// The number of frames shown in the graph.
+pub const FRAMES: usize = 120;
+
+/// The frame time at the top of the graph, in milliseconds.
+const RANGE_MS: f32 = 33.3;
+/// The frame time of the reference line, in milliseconds.
+const REFERENCE_MS: f32 = 16.6;
+
+/// The corners of the graph, in normalized device coordinates.
+const LEFT: f32 = -0.95;
+const RIGHT: f32 = -0.35;
+const BOTTOM: f32 = 0.95;
+const TOP: f32 = 0.65;
+
+const GRAPH_COLOR: [f32; 4] = [0.0, 1.0, 0.0, 1.0];
+const REFERENCE_COLOR: [f32; 4] = [1.0, 1.0, 0.0, 1.0];
+
+/// All lines of the graph, two vertices each: the reference line and a line between each of the
+/// consecutive frames.
+const VERTICES: usize = 2 + (FRAMES - 1) * 2;
+
+#[derive(Debug, Clone)]
+struct GraphVertex {
+    position: [f32; 2],
+    color: [f32; 4],
+}
+impl_vertex!(GraphVertex, position, color);
+
+type GraphPipeline = GraphicsPipeline<SingleBufferDefinition<GraphVertex>, EmptyPipeline,
+                                      CustomRenderPass>;
+
+/// The durations of the most recent frames.
+pub struct FrameTimes {
+    times: VecDeque<f32>,
+}
+
+impl FrameTimes {
+    pub fn new() -> FrameTimes {
+        FrameTimes { times: VecDeque::with_capacity(FRAMES) }
+    }
+
+    pub fn push(&mut self, duration: Duration) {
+        if self.times.len() == FRAMES {
+            self.times.pop_front();
+        }
+
+        self.times.push_back(duration.as_secs() as f32 * 1000.0 +
+                             duration.subsec_nanos() as f32 / 1_000_000.0);
+    }
+
+    /// The durations in milliseconds, from the oldest to the most recent frame.
+    pub fn iter<'a>(&'a self) -> impl Iterator<Item = f32> + 'a {
+        self.times.iter().cloned()
+    }
+}
+
+/// Maps a frame time to the vertical position in the graph.
+fn height(time_ms: f32) -> f32 {
+    BOTTOM + (TOP - BOTTOM) * (time_ms / RANGE_MS).min(1.0)
+}
+
+/// A line graph of the recent frame times, drawn over the scene.
+pub struct FrameGraph {
+    pipeline: Arc<GraphPipeline>,
+    // One per swapchain image, so that the buffer of a frame being drawn is never written to.
+    vertex_buffers: Vec<Arc<CpuAccessibleBuffer<[GraphVertex]>>>,
+}
+
+impl FrameGraph {
+    pub fn new(device: &Arc<Device>, queue: &Arc<Queue>, render_pass: &Arc<CustomRenderPass>,
+               dimensions: [u32; 2], image_count: usize) -> Result<FrameGraph, Error> {
+        let vs = ::graph_vs::Shader::load(device)
+            .map_err(|err| Error::OutOfMemory("the vertex shader module", err))?;
+        let fs = ::graph_fs::Shader::load(device)
+            .map_err(|err| Error::OutOfMemory("the fragment shader module", err))?;
+
+        let pipeline_layout = EmptyPipeline::new(device)
+            .map_err(|err| Error::OutOfMemory("the pipeline layout", err))?;
+
+        let pipeline = GraphicsPipeline::new(device, GraphicsPipelineParams {
+            vertex_input: SingleBufferDefinition::new(),
+            vertex_shader: vs.main_entry_point(),
+            input_assembly: InputAssembly {
+                topology: PrimitiveTopology::LineList,
+                primitive_restart_enable: false,
+            },
+            tessellation: None,
+            geometry_shader: None,
+            viewport: ViewportsState::Fixed {
+                data: vec![(
+                    Viewport {
+                        origin: [0.0, 0.0],
+                        depth_range: 0.0 .. 1.0,
+                        dimensions: [dimensions[0] as f32, dimensions[1] as f32],
+                    },
+                    Scissor::irrelevant()
+                )],
+            },
+            raster: Default::default(),
+            multisample: Multisample::disabled(),
+            fragment_shader: fs.main_entry_point(),
+            depth_stencil: DepthStencil::disabled(),
+            blend: Blend::pass_through(),
+            layout: &pipeline_layout,
+            render_pass: Subpass::from(render_pass, 0).ok_or_else(Error::no_subpass)?,
+        }).map_err(|err| Error::GraphicsPipeline("the frame graph pipeline", err))?;
+
+        let vertex_buffers = (0 .. image_count).map(|_| {
+            CpuAccessibleBuffer::array(device, VERTICES, &BufferUsage::all(),
+                                       Some(queue.family()))
+                .map_err(|err| Error::OutOfMemory("the vertex buffer", err))
+        }).collect::<Result<_, _>>()?;
+
+        Ok(FrameGraph {
+            pipeline: pipeline,
+            vertex_buffers: vertex_buffers,
+        })
+    }
+
+    /// Draws the graph of `frame_times` into the swapchain image `image_num`.
+    pub fn draw(&self, builder: PrimaryCommandBufferBuilderInlineDraw, image_num: usize,
+                frame_times: &FrameTimes) -> PrimaryCommandBufferBuilderInlineDraw {
+        let vertex_buffer = &self.vertex_buffers[image_num];
+
+        {
+            let mut vertices = vertex_buffer.write(Duration::new(1, 0)).unwrap();
+            let step = (RIGHT - LEFT) / (FRAMES - 1) as f32;
+            let points = frame_times.iter().enumerate().map(|(index, time)| {
+                [LEFT + step * index as f32, height(time)]
+            }).collect::<Vec<_>>();
+
+            vertices[0] = GraphVertex {
+                position: [LEFT, height(REFERENCE_MS)],
+                color: REFERENCE_COLOR,
+            };
+            vertices[1] = GraphVertex {
+                position: [RIGHT, height(REFERENCE_MS)],
+                color: REFERENCE_COLOR,
+            };
+
+            // Lines of frames that haven't been recorded yet collapse into a point.
+            for index in 0 .. FRAMES - 1 {
+                let (start, end) = match (points.get(index), points.get(index + 1)) {
+                    (Some(&start), Some(&end)) => (start, end),
+                    _ => ([LEFT, BOTTOM], [LEFT, BOTTOM]),
+                };
+
+                vertices[2 + index * 2] = GraphVertex { position: start, color: GRAPH_COLOR };
+                vertices[3 + index * 2] = GraphVertex { position: end, color: GRAPH_COLOR };
+            }
+        }
+
+        builder.draw(&self.pipeline, vertex_buffer, &DynamicState::none(), &(), &())
+    }
+}
@@ -0,0 +1,553 @@
+use std::f32::consts::PI;
+use std::fs;
+use std::io;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Child;
+use std::process::Command;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+
+use image;
+
+use vulkano::buffer::BufferUsage;
+use vulkano::buffer::CpuAccessibleBuffer;
+use vulkano::command_buffer;
+use vulkano::command_buffer::DynamicState;
+use vulkano::command_buffer::PrimaryCommandBuffer;
+use vulkano::command_buffer::PrimaryCommandBufferBuilder;
+use vulkano::device::Device;
+use vulkano::device::DeviceExtensions;
+use vulkano::device::Queue;
+use vulkano::format::Format;
+use vulkano::framebuffer::Framebuffer;
+use vulkano::image::Dimensions;
+use vulkano::image::StorageImage;
+use vulkano::instance::Instance;
+use vulkano::instance::PhysicalDevice;
+
+use accumulate;
+use background::Background;
+use simulation;
+use simulation::Time;
+use config::Config;
+use device;
+use error::Error;
+use display::DEFAULT_REFRESH_HZ;
+use idle;
+use shader::FragmentShader;
+use shader::VertexShader;
+use state::State;
+use text::Canvas;
+
+/// The format of the offscreen image, its texels have the same layout as the PNG pixels.
+const FORMAT: Format = Format::R8G8B8A8Unorm;
+
+/// Renders a single frame into an offscreen image of the configured dimensions, without creating
+/// a window, and saves it to `path` as a PNG.
+pub fn render(instance: &Arc<Instance>, config: &Config, path: &Path) -> Result<(), Error> {
+    let physical = device::select_physical_device(instance, config).map_err(Error::Device)?;
+
+    info!("using device: {} (type: {:?})", physical.name(), physical.ty());
+
+    let (dimensions, bytes) = render_frame(physical, config)?;
+    let bytes = with_timecode(config, 0, dimensions, with_alpha(config, bytes));
+
+    image::save_buffer(path, &bytes, dimensions[0], dimensions[1], image::RGBA(8))
+        .map_err(|err| Error::Capture(format!("failed to save `{}`: {}", path.display(), err)))
+}
+
+/// Renders the same frame at each of `config.sweep_sizes`, as with `--headless`, and saves them
+/// to `dir` as PNGs named after their sizes, to see how a shader copes with other aspect ratios.
+///
+/// Sizes that fail, such as those too large to allocate, are left out with a warning, only if
+/// none succeeds is it an error.
+pub fn sweep(instance: &Arc<Instance>, config: &Config, dir: &Path) -> Result<(), Error> {
+    let physical = device::select_physical_device(instance, config).map_err(Error::Device)?;
+
+    info!("using device: {} (type: {:?})", physical.name(), physical.ty());
+
+    fs::create_dir_all(dir)
+        .map_err(|err| Error::Io(format!("failed to create `{}`", dir.display()), err))?;
+
+    let mut failed = Vec::new();
+
+    for &size in &config.sweep_sizes {
+        let config = Config {
+            width: size[0],
+            height: size[1],
+            internal_res: None,
+            .. config.clone()
+        };
+        // The file is named after the size the frame was rendered at, which is smaller than
+        // requested, with a warning, if the device couldn't allocate it.
+        let result = render_frame(physical, &config).and_then(|(dimensions, bytes)| {
+            let path = dir.join(format!("{}x{}.png", dimensions[0], dimensions[1]));
+            let bytes = with_timecode(&config, 0, dimensions, with_alpha(&config, bytes));
+
+            image::save_buffer(&path, &bytes, dimensions[0], dimensions[1], image::RGBA(8))
+                .map_err(|err| {
+                    Error::Capture(format!("failed to save `{}`: {}", path.display(), err))
+                })
+                .map(|()| path)
+        });
+
+        match result {
+            Ok(path) => {
+                info!("saved the frame for {}x{} to `{}`", size[0], size[1], path.display())
+            }
+            Err(err) => {
+                warn!("skipping {}x{}: {}", size[0], size[1], err);
+                failed.push(format!("{}x{}", size[0], size[1]));
+            }
+        }
+    }
+
+    if failed.len() == config.sweep_sizes.len() {
+        return Err(Error::Capture("the frame couldn't be rendered at any size".to_string()));
+    }
+
+    if !failed.is_empty() {
+        warn!("the frame couldn't be rendered at {}", failed.join(", "));
+    }
+
+    Ok(())
+}
+
+/// The faces of a cubemap, by the names they are saved as, the directions they face and the
+/// directions that are up in them.
+const CUBE_FACES: [(&'static str, [f32; 3], [f32; 3]); 6] = [
+    ("px", [1.0, 0.0, 0.0], [0.0, 0.0, 1.0]),
+    ("nx", [-1.0, 0.0, 0.0], [0.0, 0.0, 1.0]),
+    ("py", [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]),
+    ("ny", [0.0, -1.0, 0.0], [0.0, 0.0, 1.0]),
+    ("pz", [0.0, 0.0, 1.0], [-1.0, 0.0, 0.0]),
+    ("nz", [0.0, 0.0, -1.0], [1.0, 0.0, 0.0]),
+];
+
+/// Renders the six faces of a cubemap around `config.cubemap_center` as `render` does, and saves
+/// them to `dir` as PNGs named after the faces.
+///
+/// Each face is a square seen 90 degrees across its width and height, so that the faces meet
+/// without gaps or overlaps. The field of view is across the diagonal, which makes it
+/// `2 * atan(sqrt(2))`.
+pub fn cubemap(instance: &Arc<Instance>, config: &Config, dir: &Path) -> Result<(), Error> {
+    let physical = device::select_physical_device(instance, config).map_err(Error::Device)?;
+
+    info!("using device: {} (type: {:?})", physical.name(), physical.ty());
+
+    fs::create_dir_all(dir)
+        .map_err(|err| Error::Io(format!("failed to create `{}`", dir.display()), err))?;
+
+    let center = config.cubemap_center.unwrap_or(config.camera_position);
+    let config = Config {
+        width: config.cubemap_size,
+        height: config.cubemap_size,
+        internal_res: None,
+        camera_position: center,
+        fov: (2.0 * 2.0f32.sqrt().atan()).to_degrees(),
+        .. config.clone()
+    };
+    // The faces only differ in the direction of the camera.
+    let mut offscreen = Offscreen::new(physical, &config)?;
+    let dimensions = offscreen.dimensions();
+
+    for &(name, forward, up) in &CUBE_FACES {
+        let config = Config {
+            camera_target: [center[0] + forward[0], center[1] + forward[1],
+                            center[2] + forward[2]],
+            camera_up: up,
+            .. config.clone()
+        };
+        let path = dir.join(format!("{}.png", name));
+        let bytes = offscreen.render(&config)?;
+        let bytes = with_timecode(&config, 0, dimensions, with_alpha(&config, bytes));
+
+        image::save_buffer(&path, &bytes, dimensions[0], dimensions[1], image::RGBA(8))
+            .map_err(|err| {
+                Error::Capture(format!("failed to save `{}`: {}", path.display(), err))
+            })?;
+    }
+
+    info!("saved the faces of the cubemap to `{}`", dir.display());
+
+    Ok(())
+}
+
+/// Where the frames of `turntable` go.
+enum Frames {
+    /// Piped into ffmpeg, which encodes them into a video.
+    Video(Child),
+    /// Saved as numbered PNGs.
+    Directory(PathBuf),
+}
+
+impl Frames {
+    /// The directory the frames are saved to instead of the video `path`, or `path` itself if it
+    /// has no extension, created if it doesn't exist.
+    fn directory(path: &Path) -> Result<Frames, Error> {
+        let dir = path.with_extension("");
+
+        fs::create_dir_all(&dir)
+            .map_err(|err| Error::Io(format!("failed to create `{}`", dir.display()), err))?;
+
+        Ok(Frames::Directory(dir))
+    }
+}
+
+/// Starts ffmpeg encoding the RGBA frames of `dimensions` written to its input, row by row, into
+/// the video `path` played at `frame_rate`, in the format its extension stands for.
+fn encode_video(path: &Path, dimensions: [u32; 2], frame_rate: f32) -> io::Result<Child> {
+    Command::new("ffmpeg")
+        .args(&["-loglevel", "error", "-y", "-f", "rawvideo", "-pixel_format", "rgba"])
+        .arg("-video_size").arg(format!("{}x{}", dimensions[0], dimensions[1]))
+        .arg("-framerate").arg(frame_rate.to_string())
+        .args(&["-i", "-"])
+        // Most players only play 4:2:0 chroma subsampling, which needs even dimensions.
+        .args(&["-vf", "pad=ceil(iw/2)*2:ceil(ih/2)*2", "-pix_fmt", "yuv420p"])
+        .arg(path)
+        .stdin(Stdio::piped())
+        .spawn()
+}
+
+/// Renders `config.frames` frames as `render` does while the camera orbits the point it looks at
+/// `config.revolutions` times, and encodes them into the video `path` with ffmpeg.
+///
+/// If `path` has no extension, or ffmpeg can't be run, the frames are saved as numbered PNGs into
+/// the directory `path` without its extension instead.
+///
+/// The camera turns evenly from one frame to the next, the last one a step short of where the
+/// first one is, so that the sequence loops without a repeated frame. Time advances by a frame
+/// at `--max-fps` or 60 Hz each frame, from the fixed time.
+pub fn turntable(instance: &Arc<Instance>, config: &Config, path: &Path) -> Result<(), Error> {
+    let physical = device::select_physical_device(instance, config).map_err(Error::Device)?;
+
+    info!("using device: {} (type: {:?})", physical.name(), physical.ty());
+
+    let start = config.fixed_time.unwrap_or(0.0);
+    // The frames only differ in the camera and the time.
+    let mut offscreen = Offscreen::new(physical, config)?;
+    let dimensions = offscreen.dimensions();
+    let mut frames = match path.extension() {
+        Some(_) => match encode_video(path, dimensions, frame_rate(config)) {
+            Ok(child) => Frames::Video(child),
+            Err(err) => {
+                warn!("failed to run ffmpeg to encode `{}`, saving the frames instead: {}",
+                      path.display(), err);
+                Frames::directory(path)?
+            }
+        },
+        None => Frames::directory(path)?,
+    };
+
+    for frame in 0 .. config.frames {
+        let turn = frame as f32 / config.frames as f32;
+        let config = Config {
+            camera_position: idle::orbit(config.camera_position, config.camera_target,
+                                         2.0 * PI * config.revolutions * turn),
+            fixed_time: Some(start + frame as f32 / frame_rate(config)),
+            .. config.clone()
+        };
+        let bytes = offscreen.render(&config)?;
+        let bytes = with_timecode(&config, frame, dimensions, with_alpha(&config, bytes));
+
+        match frames {
+            Frames::Video(ref mut child) => {
+                child.stdin.as_mut().unwrap().write_all(&bytes).map_err(|err| {
+                    Error::Io("failed to pipe a frame into ffmpeg".to_string(), err)
+                })?;
+            }
+            Frames::Directory(ref dir) => {
+                let path = dir.join(format!("frame-{:04}.png", frame));
+
+                image::save_buffer(&path, &bytes, dimensions[0], dimensions[1], image::RGBA(8))
+                    .map_err(|err| {
+                        Error::Capture(format!("failed to save `{}`: {}", path.display(), err))
+                    })?;
+            }
+        }
+    }
+
+    match frames {
+        Frames::Video(mut child) => {
+            // Closing the input ends the video.
+            drop(child.stdin.take());
+
+            let status = child.wait()
+                .map_err(|err| Error::Io("failed to wait for ffmpeg".to_string(), err))?;
+
+            if !status.success() {
+                return Err(Error::Capture(format!("ffmpeg failed to encode `{}`: {}",
+                                                  path.display(), status)));
+            }
+
+            info!("saved the {} frames of the turntable to `{}`", config.frames, path.display());
+        }
+        Frames::Directory(dir) => {
+            info!("saved the {} frames of the turntable to `{}`", config.frames, dir.display());
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes the timecode into the `bytes` of the frame number `frame` of `dimensions` rendered
+/// with `config`, if it asks for it.
+fn with_timecode(config: &Config, frame: u32, dimensions: [u32; 2], bytes: Vec<u8>) -> Vec<u8> {
+    if !config.timecode {
+        return bytes;
+    }
+
+    let mut canvas = Canvas::from_pixels(dimensions[0], dimensions[1], bytes);
+
+    // The frame is rendered at the fixed time.
+    canvas.write_timecode(frame, config.fixed_time.unwrap_or(0.0));
+    canvas.into_pixels()
+}
+
+/// `pixels` in RGBA as they are saved: with the alpha the shader output, which the readback and
+/// the PNG keep for compositing the frame elsewhere, or opaque with `--opaque-capture`.
+pub fn with_alpha(config: &Config, mut pixels: Vec<u8>) -> Vec<u8> {
+    if config.opaque_capture {
+        for pixel in pixels.chunks_mut(4) {
+            pixel[3] = 255;
+        }
+    }
+
+    pixels
+}
+
+/// The frame rate the frames rendered without a window are meant to be played back at.
+fn frame_rate(config: &Config) -> f32 {
+    config.max_fps.map_or(DEFAULT_REFRESH_HZ, |max_fps| max_fps as f32)
+}
+
+/// The dimensions of the frames rendered without a window.
+pub fn dimensions(config: &Config) -> [u32; 2] {
+    config.internal_res.unwrap_or([config.width, config.height])
+}
+
+/// Renders a single frame of `dimensions(config)` on `physical`, motion blurred with
+/// `--motion-blur` and anti-aliased with the jittered samples of `--accumulate`, returning its
+/// dimensions and its RGBA pixels row by row.
+///
+/// The dimensions are smaller than configured if the device can't allocate the frame.
+pub fn render_frame(physical: PhysicalDevice, config: &Config)
+                    -> Result<([u32; 2], Vec<u8>), Error> {
+    let mut offscreen = Offscreen::new(physical, config)?;
+    let pixels = offscreen.render(config)?;
+
+    Ok((offscreen.dimensions(), pixels))
+}
+
+/// The device, the offscreen image and the pipeline frames of `dimensions(config)` are rendered
+/// with, created once for all the frames of that size, such as those of a turntable or the faces
+/// of a cubemap.
+pub struct Offscreen {
+    queue: Arc<Queue>,
+    dimensions: [u32; 2],
+    uniform_buffer: Arc<CpuAccessibleBuffer<::vs::ty::Data>>,
+    /// The state the frames carry forward, as in the window, so that the frame of a turntable
+    /// or the samples of motion blur follow on from the ones before.
+    state: State,
+    /// The command buffers of both frame parities, see `State::sets`.
+    command_buffers: [Arc<PrimaryCommandBuffer>; 2],
+    pixels: Arc<CpuAccessibleBuffer<[[u8; 4]]>>,
+    warmed_up: bool,
+}
+
+impl Offscreen {
+    /// Creates a device on `physical` and everything rendering with it needs, with the
+    /// resolution clamped to what it can allocate.
+    pub fn new(physical: PhysicalDevice, config: &Config) -> Result<Offscreen, Error> {
+        let config = &device::clamp_resolution(&physical, config);
+
+        ::check_rasterization(config, &physical).map_err(Error::Device)?;
+
+        // Without a surface to draw to, any graphical queue will do.
+        let queue = physical.queue_families()
+            .find(|q| q.supports_graphics())
+            .ok_or_else(|| Error::Device("couldn't find a graphical queue family".to_string()))?;
+
+        let (device, mut queues) = Device::new(&physical, physical.supported_features(),
+                                               &DeviceExtensions::none(),
+                                               [(queue, config.queue_priority)].iter().cloned())
+            .map_err(|err| Error::Device(format!("failed to create device: {}", err)))?;
+        let queue = queues.next().unwrap();
+
+        let dimensions = dimensions(config);
+        let target = StorageImage::new(&device,
+                                       Dimensions::Dim2d {
+                                           width: dimensions[0],
+                                           height: dimensions[1],
+                                       },
+                                       FORMAT, Some(queue.family()))
+            .map_err(|err| Error::OutOfMemory("the offscreen image", err))?;
+
+        // There is no monitor the image is shown on, and the captured frame should always be the
+        // same one.
+        let uniform_buffer = ::create_uniform_buffer(&device, &queue, config, dimensions,
+                                                     DEFAULT_REFRESH_HZ,
+                                                     Time::fixed(config.fixed_time.unwrap_or(0.0)),
+                                                     config.exposure)?;
+        let vertex_buffer = ::create_vertex_buffer(&device, &queue, config.coverage)?;
+
+        let vs = VertexShader::load(&device, config).map_err(Error::Shader)?;
+        let fs = FragmentShader::load(&device, config).map_err(Error::Shader)?;
+
+        let render_pass = ::render_pass::CustomRenderPass::new(&device, &::render_pass::Formats {
+            color: (FORMAT, 1)
+        }).map_err(|err| Error::Pipeline(format!("failed to create the render pass: {}", err)))?;
+
+        let pipeline_layout = ::pipeline_layout::CustomPipeline::new(&device).map_err(|err| {
+            Error::Pipeline(format!("failed to create the pipeline layout: {}", err))
+        })?;
+
+        let descriptor_pool = ::vulkano::descriptor::descriptor_set::DescriptorPool::new(&device);
+
+        let state = State::new(&device, &queue, config.state_size)?;
+        let sets = state.sets(&descriptor_pool, &pipeline_layout, &uniform_buffer);
+
+        let pipeline = ::create_pipeline(&device, config, &vs, &fs, &pipeline_layout,
+                                         &render_pass, dimensions)?;
+
+        let background = Background::new(&device, &queue, &descriptor_pool, &render_pass,
+                                         dimensions, config)?;
+
+        let framebuffer = Framebuffer::new(&render_pass, [dimensions[0], dimensions[1], 1],
+                                           ::render_pass::AList {
+                                               color: &target
+                                           })
+            .map_err(|err| Error::Framebuffer("the offscreen framebuffer", err))?;
+
+        // The rendered image is copied into a buffer the CPU can read it from.
+        let pixels = CpuAccessibleBuffer::<[[u8; 4]]>::array(&device,
+                                                              (dimensions[0] * dimensions[1])
+                                                                  as usize,
+                                                              &BufferUsage::all(),
+                                                              Some(queue.family()))
+            .map_err(|err| Error::OutOfMemory("the buffer of the rendered image", err))?;
+
+        // The closure borrows what it records, so it goes before they are moved.
+        let command_buffers = {
+            let record = |set: &Arc<::pipeline_layout::set0::Set>| {
+                let builder = PrimaryCommandBufferBuilder::new(&device, queue.family())
+                    .draw_inline(&render_pass, &framebuffer, ::render_pass::ClearValues {
+                        color: config.clear_color
+                    });
+                let builder = match background {
+                    Some(ref background) => background.draw(builder, &vertex_buffer),
+                    None => builder,
+                };
+
+                builder.draw(&pipeline, &vertex_buffer, &DynamicState::none(), set, &())
+                    .draw_end()
+                    .copy_color_image_to_buffer(&pixels, &target, 0, 0 .. 1, [0, 0, 0],
+                                                [dimensions[0], dimensions[1], 1])
+                    .build()
+            };
+
+            [record(&sets[0]), record(&sets[1])]
+        };
+
+        Ok(Offscreen {
+            queue: queue,
+            dimensions: dimensions,
+            uniform_buffer: uniform_buffer,
+            state: state,
+            command_buffers: command_buffers,
+            pixels: pixels,
+            warmed_up: false,
+        })
+    }
+
+    /// The dimensions of the rendered frames.
+    pub fn dimensions(&self) -> [u32; 2] {
+        self.dimensions
+    }
+
+    /// Renders a frame as `render_frame` does, with the camera, the time and the other uniforms
+    /// of `config`. Its resolution and the options the pipeline was created with stay those
+    /// `new` was given.
+    pub fn render(&mut self, config: &Config) -> Result<Vec<u8>, Error> {
+        let dimensions = self.dimensions;
+
+        // The first frames are discarded, so that the captured one isn't affected by one-time
+        // costs such as lazy pipeline compilation. They lead up to it at `--max-fps`, so that the
+        // time advances through them as it would in the window. The costs are only paid once,
+        // so later frames aren't preceded by them.
+        if config.warmup > 0 && !self.warmed_up {
+            let start = config.fixed_time.unwrap_or(0.0);
+            let dt = 1.0 / frame_rate(config);
+
+            for frame in 0 .. config.warmup {
+                let time = start - (config.warmup - frame) as f32 * dt;
+
+                self.write_uniforms(config, Time { time: time, dt: dt, real_time: time }, frame,
+                                    [0.0, 0.0])?;
+                self.submit()?;
+            }
+
+            self.warmed_up = true;
+        }
+
+        self.write_uniforms(config, Time::fixed(config.fixed_time.unwrap_or(0.0)), 0,
+                            [0.0, 0.0])?;
+
+        // With motion blur, the frame is the average of sub-frames spread over the time the
+        // shutter is open, as a fraction of a frame at `--max-fps`. Each sub-frame is the
+        // average of the samples of `--accumulate`, jittered within the pixels as in the window.
+        let sub_frames = config.motion_blur.unwrap_or(1);
+        let jittered = config.accumulate.unwrap_or(1);
+        let samples = sub_frames * jittered;
+        let shutter = config.shutter_angle / 360.0 / frame_rate(config);
+        let mut sum = vec![0.0f32; (dimensions[0] * dimensions[1] * 4) as usize];
+
+        for sample in 0 .. samples {
+            if samples > 1 {
+                let offset = shutter * (sample / jittered) as f32 / sub_frames as f32;
+                let time = Time::fixed(config.fixed_time.unwrap_or(0.0) + offset);
+
+                self.write_uniforms(config, time, sample, accumulate::jitter(sample % jittered))?;
+            }
+
+            self.submit()?;
+
+            let pixels = self.pixels.read(Duration::new(5, 0)).map_err(|_| {
+                Error::Capture("timed out while reading the rendered image".to_string())
+            })?;
+
+            for (sum, &byte) in sum.iter_mut().zip(pixels.iter().flat_map(|pixel| pixel.iter())) {
+                *sum += byte as f32;
+            }
+        }
+
+        Ok(sum.iter().map(|sum| (sum / samples as f32).round() as u8).collect())
+    }
+
+    fn write_uniforms(&self, config: &Config, time: Time, frame: u32, jitter: [f32; 2])
+                      -> Result<(), Error> {
+        *self.uniform_buffer.write(Duration::new(1, 0)).map_err(|_| {
+            Error::Capture("timed out while writing the uniform buffer".to_string())
+        })? = simulation::uniforms(config, self.dimensions, DEFAULT_REFRESH_HZ, time,
+                                   config.exposure, frame, jitter);
+
+        Ok(())
+    }
+
+    /// Draws the frame, blocking until the GPU is done with it, and makes the state it wrote the
+    /// previous one of the next frame.
+    fn submit(&mut self) -> Result<(), Error> {
+        // Destroying the submission blocks until the GPU is done with it.
+        command_buffer::submit(&self.command_buffers[self.state.current()], &self.queue)
+            .map_err(|err| {
+                Error::Capture(format!("failed to submit the command buffer: {}", err))
+            })?;
+
+        self.state.swap();
+
+        Ok(())
+    }
+}
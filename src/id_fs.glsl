@@ -0,0 +1,29 @@
+#version 450
+
+#extension GL_ARB_separate_shader_objects: enable
+#extension GL_ARB_shading_language_420pack: enable
+
+#define TAU 6.2831853071795864769252867665590057683943
+
+// The ids of the `id` render target, which can't be filtered and are fetched as they are.
+layout(set = 0, binding = 0) uniform usampler2D source;
+
+layout(location = 0) in vec2 tex_coords;
+
+layout(location = 0) out vec4 f_color;
+
+// Shows every id but 0, which is nothing and stays black, in a hue of its own, consecutive ids
+// being far apart on the color wheel.
+void main() {
+    uint id = texture(source, tex_coords).r;
+
+    if(id == 0u) {
+        f_color = vec4(0.0, 0.0, 0.0, 1.0);
+        return;
+    }
+
+    float hue = fract(float(id) * 0.6180339887);
+    vec3 rgb = 0.5 + 0.5 * cos(TAU * (hue + vec3(0.0, 2.0, 1.0) / 3.0));
+
+    f_color = vec4(rgb, 1.0);
+}
@@ -0,0 +1,67 @@
+use std::f32::consts::PI;
+
+use config::Config;
+use config::IdleKind;
+
+/// How fast the camera orbits while idle, in radians per second: a full turn per minute.
+const ORBIT_SPEED: f32 = 2.0 * PI / 60.0;
+
+/// Tracks the time since the last input, to make the scene move on its own after a while, as on
+/// an unattended display.
+pub struct Idle {
+    timeout: Option<f32>,
+    behavior: IdleKind,
+    /// The real time since the last input, in seconds.
+    since_input: f32,
+    /// How far the camera has orbited since it became idle, in radians.
+    angle: f32,
+}
+
+impl Idle {
+    pub fn new(config: &Config) -> Idle {
+        Idle {
+            timeout: config.idle_timeout,
+            behavior: config.idle_behavior,
+            since_input: 0.0,
+            angle: 0.0,
+        }
+    }
+
+    pub fn behavior(&self) -> IdleKind {
+        self.behavior
+    }
+
+    /// Whether there has been no input for the timeout.
+    pub fn is_idle(&self) -> bool {
+        self.timeout.map_or(false, |timeout| self.since_input >= timeout)
+    }
+
+    /// Moves on by `dt` seconds of real time.
+    pub fn advance(&mut self, dt: f32) {
+        self.since_input += dt;
+
+        if self.is_idle() && self.behavior == IdleKind::Orbit {
+            self.angle = (self.angle + ORBIT_SPEED * dt) % (2.0 * PI);
+        }
+    }
+
+    /// Starts over after an input, undoing what being idle did.
+    pub fn input(&mut self) {
+        self.since_input = 0.0;
+        self.angle = 0.0;
+    }
+
+    /// The camera at `position`, orbited around `target` it looks at about the vertical axis.
+    pub fn camera_position(&self, position: [f32; 3], target: [f32; 3]) -> [f32; 3] {
+        orbit(position, target, self.angle)
+    }
+}
+
+/// `position` turned by `angle` radians around `target` about the vertical axis.
+pub fn orbit(position: [f32; 3], target: [f32; 3], angle: f32) -> [f32; 3] {
+    let (sin, cos) = angle.sin_cos();
+    let x = position[0] - target[0];
+    let y = position[1] - target[1];
+
+    [target[0] + x * cos - y * sin, target[1] + x * sin + y * cos, position[2]]
+}
@@ -0,0 +1,80 @@
+use std::sync::Arc;
+
+use vulkano::instance::PhysicalDevice;
+use vulkano::swapchain::Surface;
+
+/// Formats a Vulkan version number as `major.minor.patch`.
+fn version(version: u32) -> String {
+    format!("{}.{}.{}", version >> 22, (version >> 12) & 0x3ff, version & 0xfff)
+}
+
+/// Lists the sample counts included in a `VkSampleCountFlags` mask.
+fn sample_counts(mask: u32) -> String {
+    (0 .. 7).map(|bit| 1u32 << bit)
+            .filter(|count| mask & count != 0)
+            .map(|count| count.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+}
+
+/// Prints the properties and limits of `physical`, and the capabilities of `surface` on it.
+pub fn print_device_info(physical: &PhysicalDevice, surface: &Arc<Surface>) {
+    let limits = physical.limits();
+
+    println!("Device: {}", physical.name());
+    println!("    Type: {:?}", physical.ty());
+    println!("    Vendor ID: {:#06x}", physical.pci_vendor_id());
+    println!("    Device ID: {:#06x}", physical.pci_device_id());
+    println!("    API version: {}", version(physical.api_version()));
+    println!("    Driver version: {}", version(physical.driver_version()));
+
+    println!("Limits:");
+    println!("    Max image dimension 2D: {}", limits.max_image_dimension_2d());
+    println!("    Max push constants size: {} bytes", limits.max_push_constants_size());
+    println!("    Max uniform buffer range: {} bytes", limits.max_uniform_buffer_range());
+    println!("    Max compute work group size: {:?}", limits.max_compute_work_group_size());
+    println!("    Timestamp period: {} ns", limits.timestamp_period());
+    println!("    Framebuffer color sample counts: {}",
+             sample_counts(limits.framebuffer_color_sample_counts()));
+    println!("    Framebuffer depth sample counts: {}",
+             sample_counts(limits.framebuffer_depth_sample_counts()));
+
+    println!("Memory heaps:");
+
+    for heap in physical.memory_heaps() {
+        println!("    #{}: {} MiB{}", heap.id(), heap.size() / (1024 * 1024),
+                 if heap.is_device_local() { ", device local" } else { "" });
+    }
+
+    println!("Queue families:");
+
+    for family in physical.queue_families() {
+        println!("    #{}: {} queue(s), graphics: {}, compute: {}, transfers: {}, present: {}",
+                 family.id(), family.queues_count(), family.supports_graphics(),
+                 family.supports_compute(), family.supports_transfers(),
+                 surface.is_supported(&family).unwrap_or(false));
+    }
+
+    let caps = match surface.get_capabilities(physical) {
+        Ok(caps) => caps,
+        Err(err) => {
+            println!("Surface capabilities: unavailable ({})", err);
+            return;
+        }
+    };
+
+    println!("Surface capabilities:");
+    println!("    Image count: {} to {}", caps.min_image_count,
+             caps.max_image_count.map(|count| count.to_string())
+                                 .unwrap_or("unlimited".to_string()));
+    println!("    Current extent: {:?}", caps.current_extent);
+    println!("    Extent: {:?} to {:?}", caps.min_image_extent, caps.max_image_extent);
+    println!("    Usage: {:?}", caps.supported_usage_flags);
+    println!("    Present modes: {:?}", caps.present_modes.iter().collect::<Vec<_>>());
+    println!("    Composite alpha: {:?}", caps.supported_composite_alpha.iter().collect::<Vec<_>>());
+    println!("    Formats:");
+
+    for &(format, color_space) in &caps.supported_formats {
+        println!("        {:?} ({:?})", format, color_space);
+    }
+}
@@ -0,0 +1,276 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use winit::ElementState;
+use winit::MouseButton;
+use winit::VirtualKeyCode;
+
+use preset::Preset;
+
+/// Something the user can trigger from the keyboard, or the mouse for `Pick`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    Quit,
+    ShowBindings,
+    ToggleFrameGraph,
+    ResetState,
+    TogglePause,
+    SpeedUp,
+    SlowDown,
+    ToggleLetterbox,
+    CopyState,
+    PasteState,
+    CycleAttachment,
+    ToggleCrosshair,
+    ToggleHeatmap,
+    RecreateAll,
+    ResetPivot,
+    FreezeCamera,
+    ResetLight,
+    SaveDistances,
+    ToggleReference,
+    CaptureFrame,
+    MoreBounces,
+    FewerBounces,
+    CopyView,
+    Pick,
+    ToggleOrtho,
+    Preset(Preset),
+}
+
+impl Action {
+    pub fn description(&self) -> &'static str {
+        match *self {
+            Action::Quit => "Quit",
+            Action::ShowBindings => "Show or hide the key bindings",
+            Action::ToggleFrameGraph => "Toggle the frame time graph",
+            Action::ResetState => "Clear the state kept by the shader",
+            Action::TogglePause => "Pause or resume time, the camera still moves",
+            Action::SpeedUp => "Double the speed of time",
+            Action::SlowDown => "Halve the speed of time",
+            Action::ToggleLetterbox => "Ease the letterbox bars in or out",
+            Action::CopyState => "Copy the time and its speed to the clipboard",
+            Action::PasteState => "Restore the time and its speed from the clipboard",
+            Action::CycleAttachment => "Show the next render target",
+            Action::ToggleCrosshair => "Show or hide the marker at the center",
+            Action::ToggleHeatmap => "Show the cost of each pixel or its color",
+            Action::RecreateAll => "Recreate the swapchain and the pipelines, for debugging",
+            Action::ResetPivot => "Move the camera back after dragging it or choosing a view",
+            Action::FreezeCamera => "Freeze or release the camera, the time still passes",
+            Action::ResetLight => "Turn the light back after turning it with Alt held",
+            Action::SaveDistances => "Save the distances of `--mrt` as a grayscale PNG",
+            Action::ToggleReference => "Show or hide the reference sphere and ground plane",
+            Action::CaptureFrame => "Capture the next frame with RenderDoc, with `--renderdoc`",
+            Action::MoreBounces => "Let the secondary rays of the shader bounce once more",
+            Action::FewerBounces => "Let the secondary rays of the shader bounce once less",
+            Action::CopyView => "Copy the view to the clipboard as a string for `--state`",
+            Action::Pick => "Print the id of the object under the cursor, with `--mrt`",
+            Action::ToggleOrtho => "Switch between the perspective and orthographic cameras",
+            Action::Preset(preset) => preset.description(),
+        }
+    }
+}
+
+/// The modifier keys currently held down.
+///
+/// Window events don't carry the state of the modifiers, so it is tracked from the key events.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Modifiers {
+    pub ctrl: bool,
+    pub alt: bool,
+    pub logo: bool,
+}
+
+impl Modifiers {
+    pub fn update(&mut self, state: ElementState, key: VirtualKeyCode) {
+        let pressed = state == ElementState::Pressed;
+
+        match key {
+            VirtualKeyCode::LControl | VirtualKeyCode::RControl => self.ctrl = pressed,
+            VirtualKeyCode::LAlt | VirtualKeyCode::RAlt => self.alt = pressed,
+            VirtualKeyCode::LWin | VirtualKeyCode::RWin => self.logo = pressed,
+            _ => (),
+        }
+    }
+
+    /// Whether the platform's command modifier is held, Cmd on macOS and Ctrl elsewhere.
+    pub fn command(&self) -> bool {
+        if cfg!(target_os = "macos") {
+            self.logo
+        } else {
+            self.ctrl
+        }
+    }
+}
+
+/// Follows the mouse the way Shadertoy's `iMouse` does, in pixels of the window with the origin at
+/// the top-left.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Mouse {
+    cursor: [f32; 2],
+    /// Where the cursor was last while the left button was held.
+    position: [f32; 2],
+    /// Where the left button was last pressed, if it has been.
+    click: Option<[f32; 2]>,
+    pressed: bool,
+}
+
+impl Mouse {
+    pub fn moved(&mut self, x: i32, y: i32) {
+        self.cursor = [x as f32, y as f32];
+
+        if self.pressed {
+            self.position = self.cursor;
+        }
+    }
+
+    /// Where the cursor is, whether or not the button is held.
+    pub fn cursor(&self) -> [f32; 2] {
+        self.cursor
+    }
+
+    pub fn update(&mut self, state: ElementState, button: MouseButton) {
+        if button != MouseButton::Left {
+            return;
+        }
+
+        self.pressed = state == ElementState::Pressed;
+
+        if self.pressed {
+            self.position = self.cursor;
+            self.click = Some(self.cursor);
+        }
+    }
+
+    /// The position while the button was held and the one it was pressed at, both mapped with
+    /// `map`, with the latter negated while the button is released. All zero before the first
+    /// press.
+    pub fn shadertoy<F: Fn([f32; 2]) -> [f32; 2]>(&self, map: F) -> [f32; 4] {
+        let click = match self.click {
+            Some(click) => map(click),
+            None => return [0.0; 4],
+        };
+        let position = map(self.position);
+        let sign = if self.pressed { 1.0 } else { -1.0 };
+
+        [position[0], position[1], click[0] * sign, click[1] * sign]
+    }
+}
+
+/// A key, optionally combined with the command modifier.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Shortcut {
+    pub key: VirtualKeyCode,
+    pub command: bool,
+}
+
+impl Shortcut {
+    pub fn key(key: VirtualKeyCode) -> Shortcut {
+        Shortcut { key: key, command: false }
+    }
+
+    pub fn command(key: VirtualKeyCode) -> Shortcut {
+        Shortcut { key: key, command: true }
+    }
+}
+
+impl fmt::Display for Shortcut {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.command {
+            write!(f, "{}+", if cfg!(target_os = "macos") { "Cmd" } else { "Ctrl" })?;
+        }
+
+        write!(f, "{:?}", self.key)
+    }
+}
+
+/// The table of all key bindings, so that every hotkey is handled in a single place.
+pub struct Keybindings {
+    bindings: HashMap<Shortcut, Action>,
+}
+
+impl Default for Keybindings {
+    fn default() -> Keybindings {
+        let mut keybindings = Keybindings { bindings: HashMap::new() };
+
+        keybindings.bind(Shortcut::key(VirtualKeyCode::Escape), Action::Quit);
+        keybindings.bind(Shortcut::command(VirtualKeyCode::Q), Action::Quit);
+        keybindings.bind(Shortcut::key(VirtualKeyCode::F1), Action::ShowBindings);
+        keybindings.bind(Shortcut::key(VirtualKeyCode::G), Action::ToggleFrameGraph);
+        keybindings.bind(Shortcut::key(VirtualKeyCode::R), Action::ResetState);
+        keybindings.bind(Shortcut::key(VirtualKeyCode::Space), Action::TogglePause);
+        // Plus is on the same key as equals on most layouts.
+        keybindings.bind(Shortcut::key(VirtualKeyCode::Equals), Action::SpeedUp);
+        keybindings.bind(Shortcut::key(VirtualKeyCode::Add), Action::SpeedUp);
+        keybindings.bind(Shortcut::key(VirtualKeyCode::Minus), Action::SlowDown);
+        keybindings.bind(Shortcut::key(VirtualKeyCode::Subtract), Action::SlowDown);
+        keybindings.bind(Shortcut::key(VirtualKeyCode::L), Action::ToggleLetterbox);
+        keybindings.bind(Shortcut::command(VirtualKeyCode::C), Action::CopyState);
+        keybindings.bind(Shortcut::command(VirtualKeyCode::V), Action::PasteState);
+        keybindings.bind(Shortcut::key(VirtualKeyCode::Tab), Action::CycleAttachment);
+        keybindings.bind(Shortcut::key(VirtualKeyCode::X), Action::ToggleCrosshair);
+        keybindings.bind(Shortcut::key(VirtualKeyCode::H), Action::ToggleHeatmap);
+        keybindings.bind(Shortcut::command(VirtualKeyCode::R), Action::RecreateAll);
+        keybindings.bind(Shortcut::key(VirtualKeyCode::P), Action::ResetPivot);
+        keybindings.bind(Shortcut::key(VirtualKeyCode::F), Action::FreezeCamera);
+        keybindings.bind(Shortcut::key(VirtualKeyCode::K), Action::ResetLight);
+        keybindings.bind(Shortcut::command(VirtualKeyCode::F12), Action::SaveDistances);
+        keybindings.bind(Shortcut::key(VirtualKeyCode::O), Action::ToggleReference);
+        keybindings.bind(Shortcut::key(VirtualKeyCode::F11), Action::CaptureFrame);
+        keybindings.bind(Shortcut::key(VirtualKeyCode::RBracket), Action::MoreBounces);
+        keybindings.bind(Shortcut::key(VirtualKeyCode::LBracket), Action::FewerBounces);
+        keybindings.bind(Shortcut::command(VirtualKeyCode::S), Action::CopyView);
+        // As in Blender, on the number row too for keyboards without a numpad.
+        keybindings.bind(Shortcut::key(VirtualKeyCode::Numpad5), Action::ToggleOrtho);
+        keybindings.bind(Shortcut::key(VirtualKeyCode::Key5), Action::ToggleOrtho);
+
+        let presets = [
+            (VirtualKeyCode::Numpad1, VirtualKeyCode::Key1, Preset::Front, Some(Preset::Back)),
+            (VirtualKeyCode::Numpad3, VirtualKeyCode::Key3, Preset::Right, Some(Preset::Left)),
+            (VirtualKeyCode::Numpad7, VirtualKeyCode::Key7, Preset::Top, Some(Preset::Bottom)),
+            (VirtualKeyCode::Numpad0, VirtualKeyCode::Key0, Preset::Isometric, None),
+        ];
+
+        for &(numpad, key, preset, opposite) in presets.iter() {
+            for &key in [numpad, key].iter() {
+                keybindings.bind(Shortcut::key(key), Action::Preset(preset));
+
+                if let Some(opposite) = opposite {
+                    keybindings.bind(Shortcut::command(key), Action::Preset(opposite));
+                }
+            }
+        }
+
+        keybindings
+    }
+}
+
+impl Keybindings {
+    pub fn bind(&mut self, shortcut: Shortcut, action: Action) {
+        self.bindings.insert(shortcut, action);
+    }
+
+    pub fn action(&self, key: VirtualKeyCode, modifiers: &Modifiers) -> Option<Action> {
+        self.bindings.get(&Shortcut { key: key, command: modifiers.command() }).cloned()
+    }
+}
+
+impl fmt::Display for Keybindings {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut bindings = self.bindings.iter()
+            .map(|(shortcut, action)| (shortcut.to_string(), action.description()))
+            .collect::<Vec<_>>();
+
+        bindings.sort();
+
+        write!(f, "Key bindings:")?;
+
+        for (key, description) in bindings {
+            write!(f, "\n    {:<12} {}", key, description)?;
+        }
+
+        write!(f, "\n    {:<12} {}", "Right click", Action::Pick.description())?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,165 @@
+use std::borrow::Cow;
+use std::env;
+use std::sync::Arc;
+
+use vulkano::instance;
+use vulkano::instance::ApplicationInfo;
+use vulkano::instance::Instance;
+use vulkano::instance::InstanceCreationError;
+use vulkano::instance::InstanceExtensions;
+use vulkano::instance::LoadingError;
+use vulkano::instance::Version;
+use vulkano::instance::debug::DebugCallback;
+
+/// The name the renderer identifies itself with as the engine, next to the application.
+const ENGINE_NAME: &'static str = "submanifold renderer";
+
+/// The environment variable that enables the validation layer.
+pub const VALIDATION_VAR: &'static str = "SUBMANIFOLD_VALIDATION";
+
+/// The names of the validation layer in the current and in older Vulkan SDKs.
+const VALIDATION_LAYERS: [&'static str; 2] = ["VK_LAYER_KHRONOS_validation",
+                                              "VK_LAYER_LUNARG_standard_validation"];
+
+/// The validation layer to enable, if `SUBMANIFOLD_VALIDATION=1` asks for it and one is
+/// installed.
+fn validation_layer() -> Option<&'static str> {
+    if !env::var(VALIDATION_VAR).map(|value| value == "1").unwrap_or(false) {
+        return None;
+    }
+
+    let available = match instance::layers_list() {
+        Ok(layers) => layers.map(|layer| layer.name().to_string()).collect::<Vec<_>>(),
+        Err(err) => {
+            warn!("failed to list the layers, not enabling validation: {}", err);
+            return None;
+        }
+    };
+    let layer = VALIDATION_LAYERS.iter().cloned()
+        .find(|&name| available.iter().any(|layer| layer == name));
+
+    if layer.is_none() {
+        warn!("{}=1 is set, but no validation layer is installed", VALIDATION_VAR);
+    }
+
+    layer
+}
+
+/// The name and version of the crate, so that it is identifiable in profilers and overlays.
+fn application_info() -> ApplicationInfo<'static> {
+    let version = Version {
+        major: env!("CARGO_PKG_VERSION_MAJOR").parse().unwrap_or(0),
+        minor: env!("CARGO_PKG_VERSION_MINOR").parse().unwrap_or(0),
+        patch: env!("CARGO_PKG_VERSION_PATCH").parse().unwrap_or(0),
+    };
+
+    ApplicationInfo {
+        application_name: Some(Cow::Borrowed(env!("CARGO_PKG_NAME"))),
+        application_version: Some(version),
+        engine_name: Some(Cow::Borrowed(ENGINE_NAME)),
+        engine_version: Some(version),
+    }
+}
+
+/// Creates the instance with `extensions` enabled, explaining the likely cause and what to do
+/// about it if that fails.
+///
+/// With `SUBMANIFOLD_VALIDATION=1`, the validation layer is enabled as well, see
+/// `log_validation`.
+pub fn create(extensions: &InstanceExtensions) -> Result<Arc<Instance>, String> {
+    let layer = validation_layer();
+    let extensions = &InstanceExtensions {
+        ext_debug_report: layer.is_some() || extensions.ext_debug_report,
+        .. extensions.clone()
+    };
+
+    Instance::new(Some(&application_info()), extensions, layer.as_ref()).map_err(|err| {
+        let cause = match err {
+            InstanceCreationError::LoadingError(LoadingError::LibraryLoadFailure(_)) => {
+                format!("the Vulkan loader could not be found. {}", install_guidance())
+            }
+            InstanceCreationError::LoadingError(_) |
+            InstanceCreationError::IncompatibleDriver |
+            InstanceCreationError::InitializationFailed => {
+                format!("no Vulkan driver could be loaded. {}", driver_guidance())
+            }
+            InstanceCreationError::ExtensionNotPresent => {
+                format!("the instance extensions {} are not supported. {}",
+                        missing_extensions(extensions).join(", "), driver_guidance())
+            }
+            err => format!("{}", err),
+        };
+
+        format!("failed to create the Vulkan instance: {}", cause)
+    })
+}
+
+/// Logs the errors and warnings of the validation layer, if `create` enabled it, for as long as
+/// the returned callback is kept.
+///
+/// That is how the layout transitions of the images the renderer copies from, which vulkano
+/// records, are checked, by taking a screenshot or streaming with the layer enabled.
+pub fn log_validation(instance: &Arc<Instance>) -> Option<DebugCallback> {
+    if !instance.loaded_extensions().ext_debug_report {
+        return None;
+    }
+
+    DebugCallback::errors_and_warnings(instance, |message| {
+        if message.ty.error {
+            error!("{}: {}", message.layer_prefix, message.description);
+        } else {
+            warn!("{}: {}", message.layer_prefix, message.description);
+        }
+    }).map_err(|err| warn!("failed to receive the messages of the validation layer: {}", err))
+      .ok()
+}
+
+/// The names of the extensions in `required` that aren't supported, as far as the loader can
+/// tell.
+fn missing_extensions(required: &InstanceExtensions) -> Vec<&'static str> {
+    let supported = match InstanceExtensions::supported_by_core_raw() {
+        Ok(supported) => supported,
+        Err(_) => return vec!["(unknown, the supported ones can't be listed)"],
+    };
+
+    let extensions = [
+        ("VK_KHR_surface", required.khr_surface, supported.khr_surface),
+        ("VK_KHR_xlib_surface", required.khr_xlib_surface, supported.khr_xlib_surface),
+        ("VK_KHR_xcb_surface", required.khr_xcb_surface, supported.khr_xcb_surface),
+        ("VK_KHR_wayland_surface", required.khr_wayland_surface, supported.khr_wayland_surface),
+        ("VK_KHR_mir_surface", required.khr_mir_surface, supported.khr_mir_surface),
+        ("VK_KHR_android_surface", required.khr_android_surface, supported.khr_android_surface),
+        ("VK_KHR_win32_surface", required.khr_win32_surface, supported.khr_win32_surface),
+    ];
+
+    extensions.iter()
+        .filter(|&&(_, required, supported)| required && !supported)
+        .map(|&(name, _, _)| name)
+        .collect()
+}
+
+/// What to install when there is no Vulkan at all.
+fn install_guidance() -> &'static str {
+    if cfg!(target_os = "windows") {
+        "Install the Vulkan runtime, which comes with up-to-date graphics drivers, or the \
+         LunarG Vulkan SDK."
+    } else if cfg!(target_os = "macos") {
+        "Install MoltenVK, for example with the LunarG Vulkan SDK."
+    } else {
+        "Install the Vulkan loader (`libvulkan1` or `vulkan-loader` in most distributions) and a \
+         driver such as Mesa's, or the LunarG Vulkan SDK."
+    }
+}
+
+/// What to do when Vulkan is installed, but the driver doesn't load or lacks something.
+fn driver_guidance() -> &'static str {
+    if cfg!(target_os = "windows") {
+        "Update the graphics drivers from the vendor of the GPU."
+    } else if cfg!(target_os = "macos") {
+        "Make sure the MoltenVK driver is installed and `VK_ICD_FILENAMES` points to it, if set."
+    } else {
+        "Install or update a Vulkan driver (`mesa-vulkan-drivers` or the proprietary driver of the \
+         GPU) and check that `VK_ICD_FILENAMES`, if set, points to it. Without a GPU, lavapipe \
+         can be used with SUBMANIFOLD_SOFTWARE=1."
+    }
+}
@@ -0,0 +1,187 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use vulkano::buffer::BufferUsage;
+use vulkano::buffer::CpuAccessibleBuffer;
+use vulkano::command_buffer::DynamicState;
+use vulkano::command_buffer::PrimaryCommandBufferBuilderInlineDraw;
+use vulkano::descriptor::pipeline_layout::EmptyPipeline;
+use vulkano::device::Device;
+use vulkano::device::Queue;
+use vulkano::framebuffer::Subpass;
+use vulkano::pipeline::GraphicsPipeline;
+use vulkano::pipeline::GraphicsPipelineParams;
+use vulkano::pipeline::blend::Blend;
+use vulkano::pipeline::depth_stencil::DepthStencil;
+use vulkano::pipeline::input_assembly::InputAssembly;
+use vulkano::pipeline::input_assembly::PrimitiveTopology;
+use vulkano::pipeline::multisample::Multisample;
+use vulkano::pipeline::vertex::SingleBufferDefinition;
+use vulkano::pipeline::viewport::ViewportsState;
+use vulkano::pipeline::viewport::Viewport;
+use vulkano::pipeline::viewport::Scissor;
+
+use config::Config;
+use error::Error;
+use render_pass::CustomRenderPass;
+
+/// The height of each bar as a fraction of the height of the image, if it isn't configured.
+///
+/// Bars of this size crop 16:9 to roughly the 2.39:1 of anamorphic widescreen.
+pub const DEFAULT_SIZE: f32 = 0.125;
+
+const COLOR: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
+
+/// Two bars of two triangles each.
+const VERTICES: usize = 12;
+
+#[derive(Debug, Clone)]
+struct BarVertex {
+    position: [f32; 2],
+    color: [f32; 4],
+}
+impl_vertex!(BarVertex, position, color);
+
+type BarPipeline = GraphicsPipeline<SingleBufferDefinition<BarVertex>, EmptyPipeline,
+                                    CustomRenderPass>;
+
+/// The animation of black bars easing in at the top and bottom of the image and out again.
+pub struct Letterbox {
+    size: f32,
+    /// The time the bars take to ease in or out, in seconds.
+    duration: f32,
+    shown: bool,
+    /// How far the bars have eased in, from 0 to 1.
+    progress: f32,
+}
+
+impl Letterbox {
+    /// With a configured size, the bars ease in from the first frame.
+    pub fn new(config: &Config) -> Letterbox {
+        Letterbox {
+            size: config.letterbox.unwrap_or(DEFAULT_SIZE),
+            duration: config.letterbox_duration,
+            shown: config.letterbox.is_some(),
+            progress: 0.0,
+        }
+    }
+
+    /// Eases the bars in if they are out or easing out, and out otherwise.
+    pub fn toggle(&mut self) {
+        self.shown = !self.shown;
+    }
+
+    /// Whether the bars are in or easing in.
+    pub fn shown(&self) -> bool {
+        self.shown
+    }
+
+    /// Eases the bars in if `shown`, and out otherwise.
+    pub fn show(&mut self, shown: bool) {
+        self.shown = shown;
+    }
+
+    /// Moves the animation on by `dt` seconds.
+    pub fn advance(&mut self, dt: f32) {
+        let step = if self.duration > 0.0 { dt / self.duration } else { 1.0 };
+
+        self.progress = if self.shown {
+            (self.progress + step).min(1.0)
+        } else {
+            (self.progress - step).max(0.0)
+        };
+    }
+
+    /// The current height of each bar as a fraction of the height of the image.
+    pub fn height(&self) -> f32 {
+        let t = self.progress;
+
+        // Smoothstep, so that the bars start and stop moving gently.
+        self.size * t * t * (3.0 - 2.0 * t)
+    }
+}
+
+/// The letterbox bars, drawn over the scene.
+pub struct LetterboxBars {
+    pipeline: Arc<BarPipeline>,
+    // One per swapchain image, so that the buffer of a frame being drawn is never written to.
+    vertex_buffers: Vec<Arc<CpuAccessibleBuffer<[BarVertex]>>>,
+}
+
+impl LetterboxBars {
+    pub fn new(device: &Arc<Device>, queue: &Arc<Queue>, render_pass: &Arc<CustomRenderPass>,
+               dimensions: [u32; 2], image_count: usize) -> Result<LetterboxBars, Error> {
+        let vs = ::graph_vs::Shader::load(device)
+            .map_err(|err| Error::OutOfMemory("the vertex shader module", err))?;
+        let fs = ::graph_fs::Shader::load(device)
+            .map_err(|err| Error::OutOfMemory("the fragment shader module", err))?;
+
+        let pipeline_layout = EmptyPipeline::new(device)
+            .map_err(|err| Error::OutOfMemory("the pipeline layout", err))?;
+
+        let pipeline = GraphicsPipeline::new(device, GraphicsPipelineParams {
+            vertex_input: SingleBufferDefinition::new(),
+            vertex_shader: vs.main_entry_point(),
+            input_assembly: InputAssembly {
+                topology: PrimitiveTopology::TriangleList,
+                primitive_restart_enable: false,
+            },
+            tessellation: None,
+            geometry_shader: None,
+            viewport: ViewportsState::Fixed {
+                data: vec![(
+                    Viewport {
+                        origin: [0.0, 0.0],
+                        depth_range: 0.0 .. 1.0,
+                        dimensions: [dimensions[0] as f32, dimensions[1] as f32],
+                    },
+                    Scissor::irrelevant()
+                )],
+            },
+            raster: Default::default(),
+            multisample: Multisample::disabled(),
+            fragment_shader: fs.main_entry_point(),
+            depth_stencil: DepthStencil::disabled(),
+            blend: Blend::pass_through(),
+            layout: &pipeline_layout,
+            render_pass: Subpass::from(render_pass, 0).ok_or_else(Error::no_subpass)?,
+        }).map_err(|err| Error::GraphicsPipeline("the letterbox pipeline", err))?;
+
+        let vertex_buffers = (0 .. image_count).map(|_| {
+            CpuAccessibleBuffer::array(device, VERTICES, &BufferUsage::all(),
+                                       Some(queue.family()))
+                .map_err(|err| Error::OutOfMemory("the vertex buffer", err))
+        }).collect::<Result<_, _>>()?;
+
+        Ok(LetterboxBars {
+            pipeline: pipeline,
+            vertex_buffers: vertex_buffers,
+        })
+    }
+
+    /// Draws bars of `height`, as a fraction of the height of the image, into the swapchain image
+    /// `image_num`.
+    pub fn draw(&self, builder: PrimaryCommandBufferBuilderInlineDraw, image_num: usize,
+                height: f32) -> PrimaryCommandBufferBuilderInlineDraw {
+        let vertex_buffer = &self.vertex_buffers[image_num];
+
+        {
+            let mut vertices = vertex_buffer.write(Duration::new(1, 0)).unwrap();
+            let height = height * 2.0;
+
+            // The top bar, then the bottom one, in normalized device coordinates.
+            let bars = [(-1.0, -1.0 + height), (1.0 - height, 1.0)];
+
+            for (bar, &(top, bottom)) in bars.iter().enumerate() {
+                let corners = [[-1.0, top], [1.0, top], [-1.0, bottom],
+                               [-1.0, bottom], [1.0, top], [1.0, bottom]];
+
+                for (index, &position) in corners.iter().enumerate() {
+                    vertices[bar * 6 + index] = BarVertex { position: position, color: COLOR };
+                }
+            }
+        }
+
+        builder.draw(&self.pipeline, vertex_buffer, &DynamicState::none(), &(), &())
+    }
+}
@@ -0,0 +1,67 @@
+use std::f32::consts::PI;
+
+use config::Config;
+
+/// How far the light turns per pixel the cursor moves, in radians: half a turn across 720
+/// pixels.
+const SENSITIVITY: f32 = PI / 720.0;
+
+/// How close to straight up or down the light can be turned, in radians, so that turning it
+/// horizontally stays defined.
+const POLE_MARGIN: f32 = 0.01;
+
+/// The direction towards the light, which can be turned by moving the mouse with Alt held, to
+/// try out lighting without restarting.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Light {
+    /// How far the light has been turned about the vertical axis and towards it from where
+    /// `--light-dir` put it, in radians.
+    turn: [f32; 2],
+    cursor: [f32; 2],
+}
+
+impl Light {
+    /// Moves the cursor to `x`, `y` in pixels, turning the light with it if `steering`.
+    ///
+    /// Moving right turns the light to the right of the camera looking along +X, moving up
+    /// raises it.
+    pub fn moved(&mut self, config: &Config, x: i32, y: i32, steering: bool) {
+        let cursor = [x as f32, y as f32];
+        let delta = [cursor[0] - self.cursor[0], cursor[1] - self.cursor[1]];
+
+        self.cursor = cursor;
+
+        if !steering {
+            return;
+        }
+
+        // The turn towards the vertical is clamped as it is applied, so that moving back undoes
+        // it right away instead of first catching up with what went past the pole.
+        let (_, elevation) = angles(config.light_direction);
+        let limit = PI / 2.0 - POLE_MARGIN;
+
+        self.turn[0] -= delta[0] * SENSITIVITY;
+        self.turn[1] = (elevation + self.turn[1] - delta[1] * SENSITIVITY).max(-limit).min(limit) -
+                       elevation;
+    }
+
+    /// Puts the light back where the configuration has it.
+    pub fn reset(&mut self) {
+        self.turn = [0.0; 2];
+    }
+
+    /// The unit vector towards the light.
+    pub fn direction(&self, config: &Config) -> [f32; 3] {
+        let (azimuth, elevation) = angles(config.light_direction);
+        let (azimuth, elevation) = (azimuth + self.turn[0], elevation + self.turn[1]);
+
+        [elevation.cos() * azimuth.cos(), elevation.cos() * azimuth.sin(), elevation.sin()]
+    }
+}
+
+/// The angle of `direction` about the vertical from +X and above the horizontal, in radians.
+fn angles(direction: [f32; 3]) -> (f32, f32) {
+    let horizontal = (direction[0] * direction[0] + direction[1] * direction[1]).sqrt();
+
+    (direction[1].atan2(direction[0]), direction[2].atan2(horizontal))
+}
@@ -1,51 +1,463 @@
 #[macro_use]
+extern crate log;
+extern crate base64;
+extern crate chrono;
+#[macro_use]
 extern crate vulkano;
 extern crate winit;  // A library for handling windows
 extern crate vulkano_win;  // A library that links `vulkano` and `winit`
+extern crate image;
+extern crate shaderc;
+extern crate env_logger;
+extern crate clipboard;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+extern crate thiserror;
+extern crate tobj;
+extern crate toml;
+extern crate renderdoc;
 
 use vulkano_win::VkSurfaceBuild;
 
 use vulkano::buffer::BufferUsage;
 use vulkano::buffer::CpuAccessibleBuffer;
-use vulkano::command_buffer;
-use vulkano::command_buffer::DynamicState;
-use vulkano::command_buffer::PrimaryCommandBufferBuilder;
-use vulkano::command_buffer::Submission;
 use vulkano::device::Device;
-use vulkano::framebuffer::Framebuffer;
+use vulkano::device::Queue;
+use vulkano::framebuffer::RenderPass;
+use vulkano::framebuffer::RenderPassDesc;
 use vulkano::framebuffer::Subpass;
-use vulkano::instance::Instance;
 use vulkano::pipeline::GraphicsPipeline;
 use vulkano::pipeline::GraphicsPipelineParams;
 use vulkano::pipeline::blend::Blend;
 use vulkano::pipeline::depth_stencil::DepthStencil;
 use vulkano::pipeline::input_assembly::InputAssembly;
 use vulkano::pipeline::input_assembly::PrimitiveTopology;
+use vulkano::instance::PhysicalDevice;
 use vulkano::pipeline::multisample::Multisample;
-use vulkano::pipeline::vertex::SingleBufferDefinition;
+use vulkano::pipeline::raster::DepthBias;
+use vulkano::pipeline::raster::DepthBiasControl;
+use vulkano::pipeline::raster::Rasterization;
 use vulkano::pipeline::viewport::ViewportsState;
 use vulkano::pipeline::viewport::Viewport;
 use vulkano::pipeline::viewport::Scissor;
-use vulkano::swapchain::SurfaceTransform;
-use vulkano::swapchain::Swapchain;
 
+use std::env;
+use std::path::PathBuf;
+use std::process;
 use std::sync::Arc;
+use std::thread;
 use std::time::Duration;
+use std::time::Instant;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use config::BackgroundKind;
+use config::Config;
+use config::Coverage;
+use config::IdleKind;
+use display::ScaleFactor;
+use error::Error;
+use gpu_capture::GpuCapture;
+use graph::FrameTimes;
+use idle::Idle;
+use input::Action;
+use input::Keybindings;
+use input::Modifiers;
+use input::Mouse;
+use letterbox::Letterbox;
+use light::Light;
+use mesh::Mesh;
+use pivot::Pivot;
+use preset::Presets;
+use queue::QueueSelector;
+use reload::FileWatcher;
+use renderer::Renderer;
+use report::Report;
+use shader::FragmentShader;
+use shader::VertexShader;
+use shaderc::ShaderKind;
+use simulation::FrameSkip;
+use simulation::Simulation;
+use simulation::Time;
+use stream::Stream;
+use vertex_input::SceneInput;
+use view::View;
+use watchdog::Watchdog;
+
+mod accumulate;
+mod background;
+mod bench;
+mod config;
+mod crosshair;
+mod device;
+mod display;
+mod error;
+mod exposure;
+mod geometry;
+mod gpu_capture;
+mod graph;
+mod headless;
+mod idle;
+mod info;
+mod input;
+mod instance;
+mod letterbox;
+mod light;
+mod memory;
+mod mesh;
+mod mirror;
+mod montage;
+mod mrt;
+mod overlay;
+mod pivot;
+mod preset;
+mod queue;
+mod reload;
+mod renderer;
+mod report;
+mod shader;
+mod share;
+mod simulation;
+mod state;
+mod stream;
+mod text;
+mod upscale;
+mod vector;
+mod vertex_input;
+mod view;
+mod watchdog;
 
 mod vs { include!{concat!(env!("OUT_DIR"), "/shaders/src/vs.glsl")} }
+mod fullscreen_vs { include!{concat!(env!("OUT_DIR"), "/shaders/src/fullscreen_vs.glsl")} }
 mod fs { include!{concat!(env!("OUT_DIR"), "/shaders/src/fs.glsl")} }
+mod cs { include!{concat!(env!("OUT_DIR"), "/shaders/src/cs.glsl")} }
+mod blit_vs { include!{concat!(env!("OUT_DIR"), "/shaders/src/blit_vs.glsl")} }
+mod blit_fs { include!{concat!(env!("OUT_DIR"), "/shaders/src/blit_fs.glsl")} }
+mod id_fs { include!{concat!(env!("OUT_DIR"), "/shaders/src/id_fs.glsl")} }
+mod background_fs { include!{concat!(env!("OUT_DIR"), "/shaders/src/background_fs.glsl")} }
+mod accumulate_fs { include!{concat!(env!("OUT_DIR"), "/shaders/src/accumulate_fs.glsl")} }
+mod graph_vs { include!{concat!(env!("OUT_DIR"), "/shaders/src/graph_vs.glsl")} }
+mod graph_fs { include!{concat!(env!("OUT_DIR"), "/shaders/src/graph_fs.glsl")} }
+mod mesh_vs { include!{concat!(env!("OUT_DIR"), "/shaders/src/mesh_vs.glsl")} }
+mod mesh_fs { include!{concat!(env!("OUT_DIR"), "/shaders/src/mesh_fs.glsl")} }
 
 mod pipeline_layout {
     pipeline_layout! {
         set0: {
-            uniforms: UniformBuffer<::vs::ty::Data>
+            uniforms: UniformBuffer<::vs::ty::Data>,
+            previous_state: StorageBuffer<[f32]>,
+            state: StorageBuffer<[f32]>
+        }
+    }
+}
+
+mod compute_layout {
+    pipeline_layout! {
+        set0: {
+            uniforms: UniformBuffer<::vs::ty::Data>,
+            target: StorageImage
+        }
+    }
+}
+
+// The next step is to create a *render pass*, which is an object that describes where the
+// output of the graphics pipeline will go. It describes the layout of the images
+// where the colors, depth and/or stencil information will be written.
+mod render_pass {
+    use vulkano::format::Format;
+
+    // Calling this macro creates multiple structs based on the macro's parameters:
+    //
+    // - `CustomRenderPass` is the main struct that represents the render pass.
+    // - `Formats` can be used to indicate the list of the formats of the attachments.
+    // - `AList` can be used to indicate the actual list of images that are attached.
+    //
+    // Render passes can also have multiple subpasses, the only restriction being that all
+    // the passes will use the same framebuffer dimensions. Here we only have one pass, so
+    // we use the appropriate macro.
+    single_pass_renderpass!{
+        attachments: {
+            // `color` is a custom name we give to the first and only attachment.
+            color: {
+                // `load: Clear` means that we ask the GPU to clear the content of this
+                // attachment at the start of the drawing.
+                load: Clear,
+                // `store: Store` means that we ask the GPU to store the output of the draw
+                // in the actual image. We could also ask it to discard the result.
+                store: Store,
+                // `format: <ty>` indicates the type of the format of the image. This has to
+                // be one of the types of the `vulkano::format` module (or alternatively one
+                // of your structs that implements the `FormatDesc` trait). Here we use the
+                // generic `vulkano::format::Format` enum because we don't know the format in
+                // advance.
+                format: Format,
+            }
+        },
+        pass: {
+            // We use the attachment named `color` as the one and only color attachment.
+            color: [color],
+            // No depth-stencil attachment is indicated with empty brackets.
+            depth_stencil: {}
         }
     }
 }
 
-const RESOLUTION: [u32; 2] = [1280, 1024];
+#[derive(Debug, Clone)]
+struct Vertex {
+    position: [f32; 2],
+}
+impl_vertex!(Vertex, position);
+
+type Pipeline = GraphicsPipeline<SceneInput, pipeline_layout::CustomPipeline,
+                                 render_pass::CustomRenderPass>;
+
+/// Creates the uniform buffer for rendering into images of the given dimensions.
+fn create_uniform_buffer(device: &Arc<Device>, queue: &Arc<Queue>, config: &Config,
+                         dimensions: [u32; 2], refresh_hz: f32, time: Time, exposure: f32)
+                         -> Result<Arc<CpuAccessibleBuffer<vs::ty::Data>>, Error> {
+    CpuAccessibleBuffer::from_data(device, &BufferUsage::all(), Some(queue.family()),
+                                   simulation::uniforms(config, dimensions, refresh_hz, time,
+                                                        exposure, 0, [0.0, 0.0]))
+        .map_err(|err| Error::OutOfMemory("the uniform buffer", err))
+}
+
+/// Makes the geometry covering the window, to be drawn with `coverage.topology()`.
+fn create_vertex_buffer(device: &Arc<Device>, queue: &Arc<Queue>, coverage: Coverage)
+                        -> Result<Arc<CpuAccessibleBuffer<[Vertex]>>, Error> {
+    let positions: &[[f32; 2]] = match coverage {
+        // A rectangle with points in each corner of the window.
+        Coverage::Fan => &[[-1.0, -1.0], [1.0, -1.0], [1.0, 1.0], [-1.0, 1.0]],
+        Coverage::List => {
+            &[[-1.0, -1.0], [1.0, -1.0], [1.0, 1.0], [-1.0, -1.0], [1.0, 1.0], [-1.0, 1.0]]
+        }
+        // The vertex shader of the scene generates these itself and is drawn without a vertex
+        // input, the buffer is only read by the passes blitting with the same geometry.
+        Coverage::Triangle => &[[-1.0, -1.0], [3.0, -1.0], [-1.0, 3.0]],
+    };
+
+    CpuAccessibleBuffer::from_iter(device, &BufferUsage::all(), Some(queue.family()),
+                                   positions.iter().map(|&position| Vertex { position: position }))
+        .map_err(|err| Error::OutOfMemory("the vertex buffer", err))
+}
+
+/// Checks that `physical` supports the rasterizer state given on the command line.
+fn check_rasterization(config: &Config, physical: &PhysicalDevice) -> Result<(), String> {
+    if config.depth_clamp && !physical.supported_features().depth_clamp {
+        return Err("the device doesn't support depth clamping".to_string());
+    }
+
+    Ok(())
+}
+
+/// The depth test of pipelines that draw with a depth buffer, letting through the fragments
+/// closer than those drawn before.
+///
+/// Their depth is only written if `write`: an overlay that is hidden behind what was drawn before
+/// it, without hiding parts of itself, doesn't write it.
+fn depth_test(write: bool) -> DepthStencil {
+    DepthStencil {
+        depth_write: write,
+        .. DepthStencil::simple_depth_test()
+    }
+}
+
+/// The rasterizer state given on the command line.
+fn rasterization(config: &Config) -> Rasterization {
+    Rasterization {
+        depth_clamp: config.depth_clamp,
+        depth_bias: match config.depth_bias {
+            Some((constant, slope)) => DepthBiasControl::Static(DepthBias {
+                constant_factor: constant,
+                clamp: 0.0,
+                slope_factor: slope,
+            }),
+            None => DepthBiasControl::Disabled,
+        },
+        cull_mode: config.cull_mode,
+        front_face: config.front_face,
+        .. Default::default()
+    }
+}
+
+/// Creates the pipeline drawing the scene into images of the given dimensions.
+fn create_pipeline<Rp>(device: &Arc<Device>, config: &Config, vs: &VertexShader,
+                       fs: &FragmentShader, pipeline_layout: &Arc<pipeline_layout::CustomPipeline>,
+                       render_pass: &Arc<Rp>, dimensions: [u32; 2])
+                       -> Result<Arc<GraphicsPipeline<SceneInput,
+                                                      pipeline_layout::CustomPipeline, Rp>>,
+                                 Error>
+    where Rp: RenderPass + RenderPassDesc + Send + Sync + 'static
+{
+    // Before we draw we have to create what is called a pipeline. This is similar to an OpenGL
+    // program, but much more specific.
+    GraphicsPipeline::new(device, GraphicsPipelineParams {
+        // We need to indicate the layout of the vertices, which is none when the vertex shader
+        // generates them.
+        vertex_input: SceneInput::new(vs),
+        // A Vulkan shader can in theory contain multiple entry points, so we have to specify
+        // which one. The built-in shaders use `main`, loaded modules the one given on the command
+        // line.
+        vertex_shader: vs.entry_point(),
+        // This defines the way vertices are used to render shapes
+        input_assembly: InputAssembly {
+            topology: config.coverage.topology(),
+            // Only allowed for strips and fans, where `geometry::RESTART_INDEX` starts a new one.
+            primitive_restart_enable: config.primitive_restart,
+        },
+        tessellation: None,
+        geometry_shader: None,
+        viewport: ViewportsState::Fixed {
+            data: vec![(
+                Viewport {
+                    origin: [0.0, 0.0],
+                    depth_range: 0.0 .. 1.0,
+                    dimensions: [dimensions[0] as f32, dimensions[1] as f32],
+                },
+                Scissor::irrelevant()
+            )],
+        },
+        // Culling, depth bias and depth clamping only matter to geometry that isn't a
+        // rectangle covering the screen, but they are configurable for debugging anyway.
+        raster: rasterization(config),
+        multisample: Multisample::disabled(),
+        // See `vertex_shader`.
+        fragment_shader: fs.entry_point(),
+        depth_stencil: DepthStencil::disabled(),
+        // `Blend::pass_through()` is a shortcut to build a `Blend` struct that describes the fact
+        // that colors must be directly transferred from the fragment shader output to the
+        // attachments without any change. Over a gradient background, the scene is blended by
+        // its alpha instead.
+        blend: if config.background == BackgroundKind::Solid {
+            Blend::pass_through()
+        } else {
+            Blend::alpha_blending()
+        },
+        // Provide external resources, such as `uniform` fields.
+        layout: pipeline_layout,
+        // We have to indicate which subpass of which render pass this pipeline is going to be used
+        // in. The pipeline will only be usable from this particular subpass.
+        render_pass: Subpass::from(render_pass, 0).ok_or_else(Error::no_subpass)?,
+    }).map_err(|err| Error::GraphicsPipeline("the pipeline of the scene", err))
+}
+
+/// The title of the window, showing whether time doesn't pass at the normal speed, whether the
+/// camera is frozen, how many samples have been accumulated, if any, and how many bounces are
+/// traced.
+fn title(simulation: &Simulation, samples: Option<u32>, bounces: u32) -> String {
+    let mut details = Vec::new();
+
+    if simulation.paused() {
+        details.push("paused".to_string());
+    } else if simulation.scale() != 1.0 {
+        details.push(format!("time {}x", simulation.scale()));
+    }
+
+    if simulation.camera_frozen() {
+        details.push("camera frozen".to_string());
+    }
+
+    if let Some(samples) = samples {
+        details.push(format!("{} sample{}", samples, if samples == 1 { "" } else { "s" }));
+    }
+
+    details.push(format!("{} bounce{}", bounces, if bounces == 1 { "" } else { "s" }));
+
+    format!("submanifold ({})", details.join(", "))
+}
 
 fn main() {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+
+    // The flags take precedence over `RUST_LOG`, so that `--quiet` is quiet anyway.
+    match config::log_filter(&args) {
+        Some(filter) => env_logger::Builder::new().parse_filters(filter).init(),
+        None => {
+            env_logger::Builder::from_env(env_logger::Env::default()
+                                              .default_filter_or("warn,submanifold=info"))
+                .init()
+        }
+    }
+
+    if let Err(err) = run() {
+        // Invalid options are shown along with the usage, to see what is expected instead.
+        match err {
+            Error::Config(_) => eprintln!("error: {}\n\n{}", err, config::USAGE),
+            _ => error!("{}", err),
+        }
+
+        process::exit(err.exit_code());
+    }
+}
+
+/// Watches the file of `--scene` and the fragment shader it uses, if there is one.
+fn scene_watchers(config: &Config) -> Vec<FileWatcher> {
+    match config.scene {
+        Some(ref scene) => {
+            Some(scene).into_iter().chain(config.frag.as_ref())
+                .map(|path| FileWatcher::new(path.clone()))
+                .collect()
+        }
+        None => Vec::new(),
+    }
+}
+
+/// Parses the command line again to take over a changed `--scene`, recompiling the fragment
+/// shader, and returns the new configuration, its resolution clamped to the device as at
+/// startup. Nothing changes if either fails, or if the pipelines can't be created with the
+/// shader.
+fn reload_scene(device: &Arc<Device>, renderer: &mut Renderer, mesh: Option<&Mesh>)
+                -> Result<Config, String> {
+    let mut config = Config::parse(env::args().skip(1))?;
+    config = device::clamp_resolution(&device.physical_device(), &config);
+
+    if let Some(mesh) = mesh {
+        mesh.fit_camera(&mut config);
+    }
+
+    let fs = FragmentShader::load(device, &config)?;
+
+    renderer.set_fragment_shader(fs).map_err(|err| err.to_string())?;
+    renderer.apply_scene(&config);
+
+    Ok(config)
+}
+
+/// Sleeps until `frame_interval` has passed since `last_frame` started, to stay below the frame
+/// rate limit.
+fn wait_for_next_frame(last_frame: Instant, frame_interval: Duration) {
+    let elapsed = last_frame.elapsed();
+
+    if elapsed < frame_interval {
+        thread::sleep(frame_interval - elapsed);
+    }
+}
+
+/// Sets everything up as the command line says and renders until the window is closed.
+fn run() -> Result<(), Error> {
+    let mut config = Config::parse(env::args().skip(1)).map_err(Error::Config)?;
+
+    if config.help {
+        println!("{}", config::USAGE);
+        return Ok(());
+    }
+
+    // The uniform buffer is created with the layout of `vs.glsl`, which the other shaders would
+    // otherwise silently read past or short of.
+    shader::check_builtin_uniform_blocks().map_err(Error::Shader)?;
+
+    if let Some(ref dir) = config.dump_spirv {
+        return shader::dump_spirv(&config, dir).map_err(Error::Shader);
+    }
+
+    if !config.check_shaders.is_empty() {
+        return shader::check_shaders(&config).map_err(Error::Shader);
+    }
+
+    // RenderDoc has to be loaded before the instance is created to hook into it.
+    let mut gpu_capture = GpuCapture::new(config.renderdoc);
+
     // The first step of any vulkan program is to create an instance.
     let instance = {
         // When we create an instance, we have to pass a list of extensions that we want to enable.
@@ -56,9 +468,45 @@ fn main() {
         let extensions = vulkano_win::required_extensions();
 
         // Now creating the instance.
-        Instance::new(None, &extensions, None).expect("failed to create Vulkan instance")
+        instance::create(&extensions).map_err(Error::Instance)?
+    };
+    let _validation = instance::log_validation(&instance);
+
+    // Rendering without a window takes an entirely separate, much shorter path.
+    if let Some(ref path) = config.headless {
+        return headless::render(&instance, &config, path);
+    }
+
+    if let Some(ref path) = config.montage {
+        return montage::render(&instance, &config, path);
+    }
+
+    if let Some(ref path) = config.aa_compare {
+        return montage::compare_aa(&instance, &config, path);
+    }
+
+    if let Some(ref dir) = config.sweep {
+        return headless::sweep(&instance, &config, dir);
+    }
+
+    if let Some(ref dir) = config.cubemap {
+        return headless::cubemap(&instance, &config, dir);
+    }
+
+    if let Some(ref path) = config.turntable {
+        return headless::turntable(&instance, &config, path);
+    }
+
+    // The camera is fitted to the mesh before anything starts from where it is.
+    let mesh = match config.mesh {
+        Some(ref path) => Some(Mesh::load(path).map_err(Error::Mesh)?),
+        None => None,
     };
 
+    if let Some(ref mesh) = mesh {
+        mesh.fit_camera(&mut config);
+    }
+
     // We then choose which physical device to use.
     //
     // In a real application, there are three things to take into consideration:
@@ -71,12 +519,19 @@ fn main() {
     //
     // - You probably want to leave the choice between the remaining devices to the user.
     //
-    // For the sake of the example we are just going to use the first device, which should work
-    // most of the time.
-    let physical = vulkano::instance::PhysicalDevice::enumerate(&instance)
-                            .next().expect("no device available");
+    // We use the first device, unless a software implementation is requested through the
+    // environment.
+    let physical = device::select_physical_device(&instance, &config).map_err(Error::Device)?;
     // Some little debug infos.
-    println!("Using device: {} (type: {:?})", physical.name(), physical.ty());
+    info!("using device: {} (type: {:?})", physical.name(), physical.ty());
+
+    // The time and everything else the user changes while the program runs, its state is also
+    // shown in the title of the window.
+    let mut simulation = Simulation::new(&config);
+
+    if let Some(ref state) = config.restore_state {
+        simulation.restore(state).map_err(Error::Config)?;
+    }
 
     // The objective of this example is to draw a triangle on a window. To do so, we first need to
     // create the window.
@@ -88,7 +543,26 @@ fn main() {
     //
     // This returns a `vulkano_win::Window` object that contains both a cross-platform winit
     // window and a cross-platform Vulkan surface that represents the surface of the window.
-    let window = winit::WindowBuilder::new().build_vk_surface(&instance).unwrap();
+    let mut builder = winit::WindowBuilder::new()
+        .with_title(title(&simulation, None, config.bounces))
+        .with_dimensions(config.width, config.height);
+
+    // Very small windows lead to degenerate extents, and very large ones to unbounded costs.
+    if let Some(size) = config.min_size {
+        builder = builder.with_min_dimensions(size[0], size[1]);
+    }
+
+    if let Some(size) = config.max_size {
+        builder = builder.with_max_dimensions(size[0], size[1]);
+    }
+
+    let window = builder.build_vk_surface(&instance)
+        .map_err(|err| Error::Window(err.to_string()))?;
+
+    if config.device_info {
+        info::print_device_info(&physical, window.surface());
+        return Ok(());
+    }
 
     // The next step is to choose which GPU queue will execute our draw commands.
     //
@@ -100,10 +574,21 @@ fn main() {
     // queue to handle data transfers in parallel. In this example we only use one queue.
     //
     // We have to choose which queues to use early on, because we will need this info very soon.
-    let queue = physical.queue_families().find(|q| {
-        // We take the first queue that supports drawing to our window.
-        q.supports_graphics() && window.surface().is_supported(q).unwrap_or(false)
-    }).expect("couldn't find a graphical queue family");
+    //
+    // We take the first queue that supports drawing to our window, or a queue to draw with and
+    // another one to present with if no family supports both.
+    //
+    // If the device chosen above can't, another one that can is used instead.
+    let mut queue_selector = QueueSelector::new(window.surface());
+    let (physical, families) = device::select_presenting_device(&instance, physical,
+                                                                &mut queue_selector)
+        .map_err(Error::Device)?;
+
+    // A resolution too large for the device would otherwise fail deep in the creation of the
+    // images.
+    config = device::clamp_resolution(&physical, &config);
+
+    check_rasterization(&config, &physical).map_err(Error::Device)?;
 
     // Now initializing the device. This is probably the most important object of Vulkan.
     //
@@ -130,251 +615,433 @@ fn main() {
             .. vulkano::device::DeviceExtensions::none()
         };
 
+        let mut requested = vec![(families.graphics, config.queue_priority)];
+
+        if families.separate() {
+            requested.push((families.present, config.queue_priority));
+        }
+
         Device::new(&physical, physical.supported_features(), &device_ext,
-                    [(queue, 0.5)].iter().cloned()).expect("failed to create device")
+                    requested.into_iter())
+            .map_err(|err| Error::Device(format!("failed to create the device: {}", err)))?
     };
 
-    // Since we can request multiple queues, the `queues` variable is in fact an iterator. In this
-    // example we use only one queue, so we just retreive the first and only element of the
-    // iterator and throw it away.
+    // Since we can request multiple queues, the `queues` variable is in fact an iterator, with
+    // the queues in the order they were requested in. The queue that draws presents too unless
+    // a second one was requested for that.
     let queue = queues.next().unwrap();
+    let present_queue = queues.next().unwrap_or_else(|| queue.clone());
 
-    // Before we can draw on the surface, we have to create what is called a swapchain. Creating
-    // a swapchain allocates the color buffers that will contain the image that will ultimately
-    // be visible on the screen. These images are returned alongside with the swapchain.
-    let (swapchain, images) = {
-        // Querying the capabilities of the surface. When we create the swapchain we can only
-        // pass values that are allowed by the capabilities.
-        let caps = window.surface().get_capabilities(&physical)
-                         .expect("failed to get surface capabilities");
-
-        // We choose the dimensions of the swapchain to match the current dimensions of the window.
-        // If `caps.current_extent` is `None`, this means that the window size will be determined
-        // by the dimensions of the swapchain, in which case we just use a default value.
-        let dimensions = caps.current_extent.unwrap_or(RESOLUTION);
-
-        // The present mode determines the way the images will be presented on the screen. This
-        // includes things such as vsync and will affect the framerate of your application. We just
-        // use the first supported value, but you probably want to leave that choice to the user.
-        let present = caps.present_modes.iter().next().unwrap();
-
-        // The alpha mode indicates how the alpha value of the final image will behave. For example
-        // you can choose whether the window will be opaque or transparent.
-        let alpha = caps.supported_composite_alpha.iter().next().unwrap();
-
-        // Choosing the internal format that the images will have.
-        let format = caps.supported_formats[0].0;
-
-        // Please take a look at the docs for the meaning of the parameters we didn't mention.
-        Swapchain::new(&device, &window.surface(), 2, format, dimensions, 1,
-                       &caps.supported_usage_flags, &queue, SurfaceTransform::Identity, alpha,
-                       present, true, None).expect("failed to create swapchain")
-    };
+    let refresh_hz = display::refresh_rate(window.window());
+
+    if let Some(frames) = config.bench_paths {
+        let report = bench::compare_paths(&device, &queue, &present_queue, window.surface(),
+                                          &config, refresh_hz, frames)?;
 
-    let uniform_buffer = vulkano::buffer::cpu_access::CpuAccessibleBuffer::<vs::ty::Data>
-           ::from_data(&device, &vulkano::buffer::BufferUsage::all(), Some(queue.family()), 
-            vs::ty::Data {
-                resolution: [RESOLUTION[0] as f32, RESOLUTION[1] as f32],
-            })
-            .expect("failed to create buffer");
-
-    // Make a rectangle with points in each corner of the window
-    let vertex_buffer = {
-        #[derive(Debug, Clone)]
-        struct Vertex {
-            position: [f32; 2],
+        if let Some(ref path) = config.json_report {
+            report.write(path).map_err(Error::Report)?;
         }
-        impl_vertex!(Vertex, position);
-
-        CpuAccessibleBuffer::from_iter(&device, &BufferUsage::all(), Some(queue.family()), [
-            Vertex { position: [-1.0, -1.0] },
-            Vertex { position: [1.0, -1.0] },
-            Vertex { position: [1.0, 1.0] },
-            Vertex { position: [-1.0, 1.0] }
-        ].iter().cloned()).expect("failed to create buffer")
-    };
+
+        return Ok(());
+    }
 
     // Load the transpiled SPIR-V shaders
-    let vs = vs::Shader::load(&device).expect("failed to create the vertex shader module");
-    let fs = fs::Shader::load(&device).expect("failed to create the fragment shader module");
+    let vs = VertexShader::load(&device, &config).map_err(Error::Shader)?;
+    let fs = FragmentShader::load(&device, &config).map_err(Error::Shader)?;
 
-    // The next step is to create a *render pass*, which is an object that describes where the
-    // output of the graphics pipeline will go. It describes the layout of the images
-    // where the colors, depth and/or stencil information will be written.
-    mod render_pass {
-        use vulkano::format::Format;
+    let mut renderer = Renderer::new(&device, &queue, &present_queue, window.surface(), &config,
+                                     refresh_hz, vs, fs)?;
 
-        // Calling this macro creates multiple structs based on the macro's parameters:
-        //
-        // - `CustomRenderPass` is the main struct that represents the render pass.
-        // - `Formats` can be used to indicate the list of the formats of the attachments.
-        // - `AList` can be used to indicate the actual list of images that are attached.
-        //
-        // Render passes can also have multiple subpasses, the only restriction being that all
-        // the passes will use the same framebuffer dimensions. Here we only have one pass, so
-        // we use the appropriate macro.
-        single_pass_renderpass!{
-            attachments: {
-                // `color` is a custom name we give to the first and only attachment.
-                color: {
-                    // `load: Clear` means that we ask the GPU to clear the content of this
-                    // attachment at the start of the drawing.
-                    load: Clear,
-                    // `store: Store` means that we ask the GPU to store the output of the draw
-                    // in the actual image. We could also ask it to discard the result.
-                    store: Store,
-                    // `format: <ty>` indicates the type of the format of the image. This has to
-                    // be one of the types of the `vulkano::format` module (or alternatively one
-                    // of your structs that implements the `FormatDesc` trait). Here we use the
-                    // generic `vulkano::format::Format` enum because we don't know the format in
-                    // advance.
-                    format: Format,
+    if let Some(ref mesh) = mesh {
+        renderer.set_mesh(mesh).map_err(Error::Mesh)?;
+    }
+
+    // The preview window shows the frames rendered for the main one, which handles the input.
+    let preview = match config.preview_window {
+        Some(size) => {
+            let preview = winit::WindowBuilder::new()
+                .with_title(format!("{} (preview)", title(&simulation, None, config.bounces)))
+                .with_dimensions(size[0], size[1])
+                .build_vk_surface(&instance)
+                .map_err(|err| Error::Window(err.to_string()))?;
+
+            match renderer.add_mirror(preview.surface(), size) {
+                Ok(()) => Some(preview),
+                Err(message) => {
+                    warn!("{}, not opening the preview window", message);
+                    None
                 }
-            },
-            pass: {
-                // We use the attachment named `color` as the one and only color attachment.
-                color: [color],
-                // No depth-stencil attachment is indicated with empty brackets.
-                depth_stencil: {}
             }
         }
-    }
+        None => None,
+    };
 
-    // The macro above only created the custom struct that represents our render pass. We also have
-    // to actually instanciate that struct.
-    //
-    // To do so, we have to pass the actual values of the formats of the attachments.
-    let render_pass = render_pass::CustomRenderPass::new(&device, &render_pass::Formats {
-        // Use the format of the images and one sample.
-        color: (images[0].format(), 1)
-    }).unwrap();
-
-    let pipeline_layout = pipeline_layout::CustomPipeline::new(&device)
-        .expect("Could not create a custom pipeline.");
-
-    let descriptor_pool = vulkano::descriptor::descriptor_set::DescriptorPool::new(&device);
-
-    let set = pipeline_layout::set0::Set::new(
-        &descriptor_pool,
-        &pipeline_layout,
-        &pipeline_layout::set0::Descriptors {
-            uniforms: &uniform_buffer
+    // The frames are read back for the stream without ever waiting for the GPU or the clients.
+    if let Some(port) = config.stream_port {
+        let stream = Stream::serve(port).map_err(Error::Capture)?;
+
+        if let Err(message) = renderer.set_stream(stream) {
+            warn!("{}, not streaming the frames", message);
         }
-    );
+    }
 
-    // Before we draw we have to create what is called a pipeline. This is similar to an OpenGL
-    // program, but much more specific.
-    let pipeline = GraphicsPipeline::new(&device, GraphicsPipelineParams {
-        // We need to indicate the layout of the vertices.
-        // The type `SingleBufferDefinition` actually contains a template parameter corresponding
-        // to the type of each vertex. But in this code it is automatically inferred.
-        vertex_input: SingleBufferDefinition::new(),
-        // A Vulkan shader can in theory contain multiple entry points, so we have to specify
-        // which one. The `main` word of `main_entry_point` actually corresponds to the name of
-        // the entry point.
-        vertex_shader: vs.main_entry_point(),
-        // This defines the way vertices are used to render shapes
-        input_assembly: InputAssembly {
-            topology: PrimitiveTopology::TriangleFan,
-            primitive_restart_enable: false,
-        },
-        tessellation: None,
-        geometry_shader: None,
-        viewport: ViewportsState::Fixed {
-            data: vec![(
-                Viewport {
-                    origin: [0.0, 0.0],
-                    depth_range: 0.0 .. 1.0,
-                    dimensions: [images[0].dimensions()[0] as f32,
-                                 images[0].dimensions()[1] as f32],
-                },
-                Scissor::irrelevant()
-            )],
-        },
-        raster: Default::default(),
-        multisample: Multisample::disabled(),
-        // See `vertex_shader`.
-        fragment_shader: fs.main_entry_point(),
-        depth_stencil: DepthStencil::disabled(),
-        // `Blend::pass_through()` is a shortcut to build a `Blend` struct that describes the fact
-        // that colors must be directly transferred from the fragment shader output to the
-        // attachments without any change.
-        blend: Blend::pass_through(),
-        // Provide external resources, such as `uniform` fields.
-        layout: &pipeline_layout,
-        // We have to indicate which subpass of which render pass this pipeline is going to be used
-        // in. The pipeline will only be usable from this particular subpass.
-        render_pass: Subpass::from(&render_pass, 0).unwrap(),
-    }).unwrap();
+    // Hot-reload recompiles the fragment shader whenever its source changes.
+    let mut shader_watcher = if !config.hot_reload {
+        None
+    } else if config.spirv_frag.is_some() {
+        warn!("hot-reload is not available for precompiled shaders");
+        None
+    } else {
+        FileWatcher::shader(&config.shader_dir)
+    };
 
-    // The render pass we created above only describes the layout of our framebuffers. Before we
-    // can draw we also need to create the actual framebuffers.
-    //
-    // Since we need to draw to multiple images, we are going to create a different framebuffer for
-    // each image.
-    let framebuffers = images.iter().map(|image| {
-        let dimensions = [image.dimensions()[0], image.dimensions()[1], 1];
-        Framebuffer::new(&render_pass, dimensions, render_pass::AList {
-            // The `AList` struct was generated by the render pass macro above, and contains one
-            // member for each attachment.
-            color: image
-        }).unwrap()
-    }).collect::<Vec<_>>();
+    let mut scene_watchers = scene_watchers(&config);
+
+    // Without an explicit limit, there is no point in rendering faster than the monitor shows.
+    let max_fps = config.max_fps.unwrap_or(refresh_hz.round().max(1.0) as u32);
+    let frame_interval = Duration::new(0, 1_000_000_000 / max_fps);
+    let mut clock = simulation::clock(&config, max_fps as f32);
+    let mut frame_skip = FrameSkip::new(&config);
 
     // Initialization is finally finished!
 
-    // In the loop below we are going to submit commands to the GPU. Submitting a command produces
-    // a `Submission` object which holds the resources for as long as they are in use by the GPU.
-    //
-    // Destroying a `Submission` blocks until the GPU is finished executing it. In order to avoid
-    // that, we store them in a `Vec` and clean them from time to time.
-    let mut submissions: Vec<Arc<Submission>> = Vec::new();
-
-    loop {
-        // Clearing the old submissions by keeping alive only the ones whose destructor would block.
-        submissions.retain(|s| s.destroying_would_block());
-
-        // Before we can draw on the output, we have to *acquire* an image from the swapchain. If
-        // no image is available (which happens if you submit draw commands too quickly), then the
-        // function will block.
-        // This operation returns the index of the image that we are allowed to draw upon.
-        //
-        // This function can block if no image is available. The parameter is a timeout after
-        // which the function call will return an error.
-        let image_num = swapchain.acquire_next_image(Duration::new(1, 0)).unwrap();
+    let keybindings = Keybindings::default();
+    let mut modifiers = Modifiers::default();
+    let mut mouse = Mouse::default();
+    let mut pivot = Pivot::default();
+    let mut presets = Presets::new(&config);
+    let mut light = Light::default();
+    let mut frame_times = FrameTimes::new();
+    let mut last_frame = Instant::now();
+    let mut letterbox = Letterbox::new(&config);
+    let mut scale_factor = ScaleFactor::new(window.window());
+    let mut window_title = title(&simulation, None, config.bounces);
 
-        // Building a command buffer is an expensive operation (usually a few hundred
-        // microseconds), but it is known to be a hot path in the driver and is expected to be
-        // optimized.
-        //
-        // Note that we have to pass a queue family when we create the command buffer. The command
-        // buffer will only be executable on that given queue family.
-        let command_buffer = PrimaryCommandBufferBuilder::new(&device, queue.family())
-            // Before we can draw, we have to *enter a render pass*. There are two methods to do
-            // this: `draw_inline` and `draw_secondary`.
-            .draw_inline(&render_pass, &framebuffers[image_num], render_pass::ClearValues {
-                color: [0.0, 0.0, 1.0, 1.0]
-            })
-            // Execute a subpass. The next one would be executed with `next_inline` or
-            // `next_secondary`.
-            .draw(&pipeline, &vertex_buffer, &DynamicState::none(), &set, &())
-            .draw_end()
-            .build();
-
-        // Now all we need to do is submit the command buffer to the queue.
-        submissions.push(command_buffer::submit(&command_buffer, &queue).unwrap());
-
-        // Submits a command to display the color output on screen.
-        // May take a while, consider spawning a separate thread for this call.
-        swapchain.present(&queue, image_num).unwrap();
-
-        // Handling the window events in order to close the program when the user wants to close
-        // it.
+    let mut watchdog = if config.watchdog > 0.0 {
+        let timeout = Duration::new(config.watchdog as u64,
+                                    (config.watchdog.fract() * 1_000_000_000.0) as u32);
+
+        Some(Watchdog::new(timeout, config.watchdog_abort))
+    } else {
+        None
+    };
+
+    // The screenshot is taken from the very first frame, without entering the loop.
+    if let Some(ref path) = config.screenshot_on_start {
+        let result = renderer.screenshot(simulation.advance(0.0), path);
+
+        renderer.wait();
+        result.map_err(Error::Capture)?;
+
+        info!("saved the screenshot to `{}`", path.display());
+        return Ok(());
+    }
+    let mut show_frame_graph = false;
+    let mut idle = Idle::new(&config);
+    // Whether the letterbox bars were shown before they were eased in for being idle.
+    let mut letterbox_before_idle = letterbox.shown();
+
+    'main: loop {
+        // With `--low-latency`, the wait is before reading the input instead, so that the input
+        // is as recent as possible when the frame is rendered.
+        if !config.low_latency {
+            wait_for_next_frame(last_frame, frame_interval);
+        }
+
+        let now = Instant::now();
+        let elapsed = now - last_frame;
+        last_frame = now;
+
+        // A shader that fails to compile, or that the pipelines can't be created with, keeps the
+        // previous one in use.
+        if let Some(ref mut watcher) = shader_watcher {
+            if let Some(source) = watcher.poll() {
+                let name = watcher.path().display().to_string();
+                let reloaded = source
+                    .and_then(|source| {
+                        shader::compile_glsl(&source, &name, ShaderKind::Fragment, &config.defines,
+                                             config.shader_cache.as_ref())
+                    })
+                    .and_then(|spirv| FragmentShader::from_spirv(&device, &spirv, "main"));
+
+                match reloaded {
+                    Ok(reloaded) => {
+                        match renderer.set_fragment_shader(reloaded) {
+                            Ok(()) => info!("reloaded `{}`", name),
+                            Err(err) => error!("{}, keeping the previous shader", err),
+                        }
+                    }
+                    Err(message) => error!("{}", message),
+                }
+            }
+        }
+
+        // Every watcher is polled, so that one change doesn't reload the scene twice.
+        if scene_watchers.iter_mut().filter_map(|watcher| watcher.poll()).count() > 0 {
+            match reload_scene(&device, &mut renderer, mesh.as_ref()) {
+                Ok(reloaded) => {
+                    config = reloaded;
+                    scene_watchers = scene_watchers(&config);
+                    info!("reloaded the scene");
+                }
+                Err(message) => error!("{}, keeping the previous scene", message),
+            }
+        }
+
+        if let Some(ref mut watchdog) = watchdog {
+            watchdog.frame_started();
+        }
+
+        // The real time of the shaders goes on with every frame, skipped or not.
+        simulation.advance_real(simulation::seconds(elapsed));
+
+        // Being idle is also measured in real time, and only affects the camera or the bars.
+        let was_idle = idle.is_idle();
+
+        idle.advance(simulation::seconds(elapsed));
+
+        if idle.is_idle() && !was_idle {
+            info!("idle, {}", match idle.behavior() {
+                IdleKind::Orbit => "orbiting the camera",
+                IdleKind::Letterbox => "easing in the letterbox bars",
+            });
+
+            if idle.behavior() == IdleKind::Letterbox {
+                letterbox_before_idle = letterbox.shown();
+                letterbox.show(true);
+            }
+        }
+
+        pivot.advance(&config, simulation::seconds(elapsed));
+
+        presets.advance(simulation::seconds(elapsed));
+
+        // A frozen camera stays where it was, the input only moves it once it is released.
+        if !simulation.camera_frozen() {
+            let (camera_position, camera_target) = pivot.camera(&config);
+            let (camera_position, camera_up) = presets.camera(&config, camera_position,
+                                                              camera_target);
+
+            renderer.set_camera(idle.camera_position(camera_position, camera_target),
+                                camera_target, camera_up);
+        }
+        renderer.set_light_direction(light.direction(&config));
+        renderer.set_mouse(mouse);
+
+        // The bars move in real time, so that they don't depend on the speed of the simulation.
+        letterbox.advance(simulation::seconds(elapsed));
+        renderer.set_letterbox(letterbox.height());
+
+        // The skipped frames only handle the input, the next rendered one takes over their time.
+        let step = clock.step(simulation::seconds(elapsed));
+
+        if let Some((elapsed, step)) = frame_skip.advance(elapsed, step) {
+            frame_times.push(elapsed);
+
+            let time = simulation.advance_skipped(step);
+            renderer.render(time, if show_frame_graph { Some(&frame_times) } else { None })?;
+        }
+
+        // A lost surface is rebuilt once, if that doesn't work either there is no recovering.
+        if renderer.surface_lost() {
+            warn!("the surface of the window was lost, recreating it and its swapchain");
+
+            display::create_surface(&instance, window.window())
+                .and_then(|surface| renderer.replace_surface(&surface))
+                .map_err(|err| {
+                    Error::Swapchain(format!("the surface of the window was lost and couldn't \
+                                              be recreated: {}", err))
+                })?;
+
+            info!("recreated the surface of the window");
+        }
+
+        // The title is only set when it changes, as it is updated with every accumulated sample.
+        let new_title = title(&simulation, renderer.accumulated_samples(), renderer.bounces());
+
+        if new_title != window_title {
+            window.window().set_title(&new_title);
+            window_title = new_title;
+        }
+
+        if let Some(ref mut watchdog) = watchdog {
+            watchdog.frame_finished();
+        }
+
+        if config.low_latency {
+            wait_for_next_frame(last_frame, frame_interval);
+        }
+
+        // Only closing and resizing the preview window do anything.
+        if let Some(ref preview) = preview {
+            for ev in preview.window().poll_events() {
+                match ev {
+                    winit::Event::Closed => break 'main,
+                    winit::Event::Resized(width, height) => {
+                        renderer.resize_mirror([width, height])
+                    }
+                    _ => (),
+                }
+            }
+        }
+
+        // Handling the window events. Everything the user can trigger is turned into an `Action`
+        // first, so that all of them are handled in one place.
         for ev in window.window().poll_events() {
+            // Any input ends being idle, putting back the camera or the bars.
             match ev {
-                winit::Event::Closed => return,
-                _ => ()
+                winit::Event::KeyboardInput(..) | winit::Event::MouseMoved(..) |
+                winit::Event::MouseInput(..) | winit::Event::MouseWheel(..) |
+                winit::Event::Touch(..) => {
+                    if idle.is_idle() && idle.behavior() == IdleKind::Letterbox {
+                        letterbox.show(letterbox_before_idle);
+                    }
+
+                    idle.input();
+                    frame_skip.input();
+                }
+                _ => (),
+            }
+
+            let action = match ev {
+                winit::Event::Closed => Some(Action::Quit),
+                winit::Event::Resized(width, height) => {
+                    renderer.resize([width, height]);
+                    None
+                }
+                // The size in pixels changes along with the scale factor, the swapchain is
+                // recreated for it right away instead of staying blurry until the next resize.
+                winit::Event::Moved(..) => {
+                    if scale_factor.changed(window.window()) {
+                        if let Some((width, height)) = window.window().get_inner_size_pixels() {
+                            renderer.resize([width, height]);
+                        }
+                    }
+
+                    None
+                }
+                winit::Event::MouseMoved(x, y) => {
+                    mouse.moved(x, y);
+                    // Along the screen as the camera is turned, by a preset too.
+                    pivot.moved(renderer.config(), renderer.dimensions(), x, y);
+                    light.moved(&config, x, y, modifiers.alt);
+                    None
+                }
+                winit::Event::MouseInput(state, button) => {
+                    mouse.update(state, button);
+                    pivot.update(state, button,
+                                 modifiers.command() && !simulation.camera_frozen());
+
+                    if state == winit::ElementState::Pressed &&
+                       button == winit::MouseButton::Right {
+                        Some(Action::Pick)
+                    } else {
+                        None
+                    }
+                }
+                winit::Event::KeyboardInput(state, _, Some(key)) => {
+                    modifiers.update(state, key);
+
+                    // Any input may change what is shown.
+                    if state == winit::ElementState::Pressed {
+                        renderer.reset_accumulation();
+                        keybindings.action(key, &modifiers)
+                    } else {
+                        None
+                    }
+                }
+                _ => None
+            };
+
+            match action {
+                Some(Action::Quit) => break 'main,
+                // The compute path draws nothing over the scene, so they are printed instead.
+                Some(Action::ShowBindings) if renderer.compute_path() => {
+                    println!("{}", keybindings)
+                }
+                Some(Action::ShowBindings) => renderer.toggle_text(keybindings.to_string())?,
+                Some(Action::ToggleFrameGraph) => show_frame_graph = !show_frame_graph,
+                Some(Action::ResetState) => renderer.reset_state(),
+                Some(Action::TogglePause) => simulation.toggle_pause(),
+                Some(Action::SpeedUp) => simulation.speed_up(),
+                Some(Action::SlowDown) => simulation.slow_down(),
+                Some(Action::ToggleLetterbox) => letterbox.toggle(),
+                Some(Action::CopyState) => {
+                    match share::copy(simulation.snapshot()) {
+                        Ok(()) => info!("copied the state to the clipboard"),
+                        Err(message) => warn!("failed to copy the state: {}", message),
+                    }
+                }
+                Some(Action::PasteState) => {
+                    match share::paste().and_then(|text| simulation.restore(&text)) {
+                        Ok(()) => info!("restored the state from the clipboard"),
+                        Err(message) => warn!("failed to paste the state: {}", message),
+                    }
+                }
+                Some(Action::CycleAttachment) => renderer.cycle_attachment(),
+                Some(Action::ToggleCrosshair) => renderer.toggle_crosshair(),
+                Some(Action::ToggleHeatmap) => renderer.toggle_heatmap(),
+                Some(Action::RecreateAll) => renderer.recreate_all()?,
+                Some(Action::ResetPivot) => {
+                    pivot.reset();
+                    presets.reset();
+                }
+                Some(Action::FreezeCamera) => simulation.toggle_camera_freeze(),
+                Some(Action::ResetLight) => light.reset(),
+                Some(Action::SaveDistances) => {
+                    // Named after the time saved at, so that saving again keeps the previous ones.
+                    let seconds = SystemTime::now().duration_since(UNIX_EPOCH)
+                        .map(|duration| duration.as_secs())
+                        .unwrap_or(0);
+                    let path = PathBuf::from(format!("distances-{}.png", seconds));
+
+                    match renderer.save_distances(simulation.advance(0.0), &path) {
+                        Ok(()) => info!("saved the distances to `{}`", path.display()),
+                        Err(message) => warn!("failed to save the distances: {}", message),
+                    }
+                }
+                Some(Action::ToggleReference) => renderer.toggle_reference(),
+                Some(Action::CaptureFrame) => gpu_capture.trigger(),
+                Some(Action::MoreBounces) => renderer.more_bounces(),
+                Some(Action::FewerBounces) => renderer.fewer_bounces(),
+                Some(Action::CopyView) => {
+                    // Logged too, for when the clipboard is unavailable.
+                    let view = View::capture(renderer.config(), &simulation).encode();
+
+                    info!("view: {}", view);
+
+                    match share::copy(view) {
+                        Ok(()) => info!("copied the view to the clipboard, pass it to `--state`"),
+                        Err(message) => warn!("failed to copy the view: {}", message),
+                    }
+                }
+                Some(Action::ToggleOrtho) => renderer.toggle_ortho(),
+                Some(Action::Preset(preset)) => {
+                    presets.select(&config, preset);
+                    info!("turning the camera to the {} view", preset.name());
+                }
+                Some(Action::Pick) => {
+                    let custom = config.frag.is_some() || config.spirv_frag.is_some();
+
+                    match renderer.pick(mouse.cursor()) {
+                        Ok(Some(id)) => info!("picked {}", mrt::describe_object(id, custom)),
+                        Ok(None) => info!("picked nothing, the cursor is outside of the scene"),
+                        Err(message) => warn!("{}", message),
+                    }
+                }
+                None => ()
             }
         }
     }
+
+    renderer.wait();
+
+    if let Some(ref path) = config.json_report {
+        Report::new(device.physical_device(), renderer.dimensions(), renderer.present_mode(),
+                    Vec::new())
+            .write(path)
+            .map_err(Error::Report)?;
+    }
+
+    Ok(())
 }
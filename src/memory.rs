@@ -0,0 +1,87 @@
+use vulkano::format::Format;
+
+/// The kind of memory an allocation is made from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Memory {
+    /// Images, which the GPU accesses directly.
+    DeviceLocal,
+    /// Buffers the CPU writes to or reads back from.
+    HostVisible,
+}
+
+struct Allocation {
+    name: &'static str,
+    count: usize,
+    /// The size of each of the `count` allocations.
+    bytes: usize,
+    memory: Memory,
+}
+
+/// The major allocations of the renderer, estimated from the dimensions and formats of the images
+/// and the sizes of the buffers, without the padding the driver may add.
+pub struct MemoryUsage {
+    allocations: Vec<Allocation>,
+}
+
+impl MemoryUsage {
+    pub fn new() -> MemoryUsage {
+        MemoryUsage {
+            allocations: Vec::new(),
+        }
+    }
+
+    /// Adds `count` 2D images of `dimensions` in `format`.
+    pub fn image(&mut self, name: &'static str, count: usize, dimensions: [u32; 2],
+                 format: Format) {
+        let texel = format.size().unwrap_or(0);
+
+        self.allocations.push(Allocation {
+            name: name,
+            count: count,
+            bytes: dimensions[0] as usize * dimensions[1] as usize * texel,
+            memory: Memory::DeviceLocal,
+        });
+    }
+
+    /// Adds `count` buffers of `bytes` each.
+    pub fn buffer(&mut self, name: &'static str, count: usize, bytes: usize) {
+        self.allocations.push(Allocation {
+            name: name,
+            count: count,
+            bytes: bytes,
+            memory: Memory::HostVisible,
+        });
+    }
+
+    /// The total size of the allocations made from `memory`.
+    pub fn total(&self, memory: Memory) -> usize {
+        self.allocations.iter()
+            .filter(|allocation| allocation.memory == memory)
+            .map(|allocation| allocation.count * allocation.bytes)
+            .sum()
+    }
+
+    pub fn print(&self) {
+        println!("Memory usage:");
+
+        for allocation in &self.allocations {
+            println!("    {}: {} x {} ({:?})", allocation.name, allocation.count,
+                     format_bytes(allocation.bytes), allocation.memory);
+        }
+
+        for &memory in &[Memory::DeviceLocal, Memory::HostVisible] {
+            println!("    Total {:?}: {}", memory, format_bytes(self.total(memory)));
+        }
+    }
+}
+
+/// Formats a size in the largest unit it is at least one of.
+fn format_bytes(bytes: usize) -> String {
+    if bytes >= 1024 * 1024 {
+        format!("{:.1} MiB", bytes as f64 / (1024.0 * 1024.0))
+    } else if bytes >= 1024 {
+        format!("{:.1} KiB", bytes as f64 / 1024.0)
+    } else {
+        format!("{} B", bytes)
+    }
+}
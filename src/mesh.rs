@@ -0,0 +1,337 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use tobj;
+
+use vulkano::buffer::CpuAccessibleBuffer;
+use vulkano::command_buffer::DynamicState;
+use vulkano::command_buffer::PrimaryCommandBufferBuilder;
+use vulkano::command_buffer::PrimaryCommandBufferBuilderInlineDraw;
+use vulkano::descriptor::descriptor_set::DescriptorPool;
+use vulkano::device::Device;
+use vulkano::device::Queue;
+use vulkano::format::Format;
+use vulkano::framebuffer::Framebuffer;
+use vulkano::framebuffer::Subpass;
+use vulkano::image::AttachmentImage;
+use vulkano::image::Dimensions;
+use vulkano::image::StorageImage;
+use vulkano::pipeline::GraphicsPipeline;
+use vulkano::pipeline::GraphicsPipelineParams;
+use vulkano::pipeline::blend::Blend;
+use vulkano::pipeline::input_assembly::InputAssembly;
+use vulkano::pipeline::input_assembly::PrimitiveTopology;
+use vulkano::pipeline::multisample::Multisample;
+use vulkano::pipeline::vertex::SingleBufferDefinition;
+use vulkano::pipeline::viewport::ViewportsState;
+use vulkano::pipeline::viewport::Viewport;
+use vulkano::pipeline::viewport::Scissor;
+
+use config::Config;
+use error::Error;
+use geometry::Geometry;
+use memory::MemoryUsage;
+use render_pass::CustomRenderPass;
+use upscale;
+use upscale::BlitPipeline;
+use Vertex;
+
+/// The format of the offscreen image the mesh is rendered into.
+const FORMAT: Format = Format::R8G8B8A8Unorm;
+
+/// The format of the depth buffer, which every device supports for depth attachments.
+const DEPTH_FORMAT: Format = Format::D16Unorm;
+
+#[derive(Debug, Clone)]
+pub struct MeshVertex {
+    position: [f32; 3],
+    normal: [f32; 3],
+}
+impl_vertex!(MeshVertex, position, normal);
+
+mod mesh_render_pass {
+    use vulkano::format::Format;
+
+    single_pass_renderpass!{
+        attachments: {
+            color: {
+                load: Clear,
+                store: Store,
+                format: Format,
+            },
+            // Only needed while the mesh is drawn.
+            depth: {
+                load: Clear,
+                store: DontCare,
+                format: Format,
+            }
+        },
+        pass: {
+            color: [color],
+            depth_stencil: {depth}
+        }
+    }
+}
+
+type MeshPipeline = GraphicsPipeline<SingleBufferDefinition<MeshVertex>,
+                                     ::pipeline_layout::CustomPipeline,
+                                     mesh_render_pass::CustomRenderPass>;
+
+/// A triangle mesh loaded from an OBJ file with `--mesh`.
+#[derive(Debug, Clone)]
+pub struct Mesh {
+    vertices: Vec<MeshVertex>,
+    indices: Vec<u32>,
+    /// The corners of the box around all vertices with the lowest and highest coordinates.
+    bounds: [[f32; 3]; 2],
+}
+
+impl Mesh {
+    /// Loads all models of the OBJ file at `path` into a single mesh, computing the normals of
+    /// the models that have none.
+    pub fn load(path: &Path) -> Result<Mesh, String> {
+        let (models, _) = tobj::load_obj(path)
+            .map_err(|err| format!("failed to load the mesh `{}`: {}", path.display(), err))?;
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+
+        for model in models {
+            let mesh = model.mesh;
+            let offset = vertices.len() as u32;
+            let count = mesh.positions.len() / 3;
+
+            if let Some(&index) = mesh.indices.iter().find(|&&index| index as usize >= count) {
+                return Err(format!("invalid mesh `{}`: the model `{}` refers to the vertex {} \
+                                    of {}", path.display(), model.name, index, count));
+            }
+
+            let normals = if mesh.normals.len() == mesh.positions.len() {
+                mesh.normals
+            } else {
+                debug!("the model `{}` has no normals, computing them", model.name);
+                compute_normals(&mesh.positions, &mesh.indices)
+            };
+
+            vertices.extend((0 .. count).map(|i| {
+                MeshVertex {
+                    position: [mesh.positions[i * 3], mesh.positions[i * 3 + 1],
+                               mesh.positions[i * 3 + 2]],
+                    normal: [normals[i * 3], normals[i * 3 + 1], normals[i * 3 + 2]],
+                }
+            }));
+            indices.extend(mesh.indices.iter().map(|&index| index + offset));
+        }
+
+        if indices.is_empty() {
+            return Err(format!("invalid mesh `{}`: it has no triangles", path.display()));
+        }
+
+        let mut bounds = [[::std::f32::INFINITY; 3], [::std::f32::NEG_INFINITY; 3]];
+
+        for vertex in &vertices {
+            for axis in 0 .. 3 {
+                bounds[0][axis] = bounds[0][axis].min(vertex.position[axis]);
+                bounds[1][axis] = bounds[1][axis].max(vertex.position[axis]);
+            }
+        }
+
+        info!("loaded `{}`: {} vertices, {} triangles", path.display(), vertices.len(),
+              indices.len() / 3);
+
+        Ok(Mesh { vertices: vertices, indices: indices, bounds: bounds })
+    }
+
+    /// Moves the camera of `config` along the direction it looks in until the whole mesh is in
+    /// view, looking at its center.
+    pub fn fit_camera(&self, config: &mut Config) {
+        let direction = config.camera_direction().unwrap_or([1.0, 0.0, 0.0]);
+        let center = [(self.bounds[0][0] + self.bounds[1][0]) / 2.0,
+                      (self.bounds[0][1] + self.bounds[1][1]) / 2.0,
+                      (self.bounds[0][2] + self.bounds[1][2]) / 2.0];
+        let radius = (0 .. 3).map(|axis| (self.bounds[1][axis] - center[axis]).powi(2))
+            .sum::<f32>().sqrt();
+
+        // The field of view is across the diagonal, so the half of the shorter side is seen at
+        // this angle from the center.
+        let (width, height) = (config.width as f32, config.height as f32);
+        let half_angle = (width.min(height) / (width * width + height * height).sqrt() *
+                          (config.fov.to_radians() / 2.0).tan()).atan();
+        let distance = (radius / half_angle.sin()).max(1.0);
+
+        config.camera_target = center;
+        config.camera_position = [center[0] - direction[0] * distance,
+                                  center[1] - direction[1] * distance,
+                                  center[2] - direction[2] * distance];
+    }
+
+    /// Uploads the mesh to be drawn with `MeshPass`.
+    pub fn upload(&self, device: &Arc<Device>, queue: &Arc<Queue>)
+                  -> Result<Geometry<MeshVertex>, String> {
+        Geometry::new(device, queue, self.vertices.clone(), self.indices.clone())
+    }
+}
+
+/// The normals of the vertices at `positions`, averaged from those of the triangles of `indices`
+/// around them, weighted by their area.
+fn compute_normals(positions: &[f32], indices: &[u32]) -> Vec<f32> {
+    let position = |index: u32| {
+        let i = index as usize * 3;
+
+        [positions[i], positions[i + 1], positions[i + 2]]
+    };
+    let mut normals = vec![0.0; positions.len()];
+
+    for triangle in indices.chunks(3).filter(|triangle| triangle.len() == 3) {
+        let (a, b, c) = (position(triangle[0]), position(triangle[1]), position(triangle[2]));
+        let ab = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+        let ac = [c[0] - a[0], c[1] - a[1], c[2] - a[2]];
+        // Twice the area long.
+        let normal = [ab[1] * ac[2] - ab[2] * ac[1], ab[2] * ac[0] - ab[0] * ac[2],
+                      ab[0] * ac[1] - ab[1] * ac[0]];
+
+        for &index in triangle {
+            for axis in 0 .. 3 {
+                normals[index as usize * 3 + axis] += normal[axis];
+            }
+        }
+    }
+
+    for normal in normals.chunks_mut(3) {
+        let length = (normal[0] * normal[0] + normal[1] * normal[1] + normal[2] * normal[2]).sqrt();
+
+        // Vertices of no or only degenerate triangles face up.
+        if length > 0.0 {
+            for component in normal.iter_mut() {
+                *component /= length;
+            }
+        } else {
+            normal.copy_from_slice(&[0.0, 0.0, 1.0]);
+        }
+    }
+
+    normals
+}
+
+/// Renders the mesh with depth testing into an offscreen image of the dimensions of the scene,
+/// which is then blended over the scene in the swapchain image.
+pub struct MeshPass {
+    dimensions: [u32; 2],
+    render_pass: Arc<mesh_render_pass::CustomRenderPass>,
+    framebuffer: Arc<Framebuffer<mesh_render_pass::CustomRenderPass>>,
+    pipeline: Arc<MeshPipeline>,
+    overlay_pipeline: Arc<BlitPipeline>,
+    overlay_set: Arc<upscale::blit_layout::set0::Set>,
+}
+
+impl MeshPass {
+    /// Creates the offscreen images for a scene of `dimensions` in a swapchain with images of
+    /// `target_dimensions`, to be blended into with `target_render_pass`.
+    pub fn new(device: &Arc<Device>, queue: &Arc<Queue>, config: &Config,
+               pipeline_layout: &Arc<::pipeline_layout::CustomPipeline>,
+               descriptor_pool: &Arc<DescriptorPool>,
+               target_render_pass: &Arc<CustomRenderPass>, target_dimensions: [u32; 2],
+               dimensions: [u32; 2]) -> Result<MeshPass, Error> {
+        let image = StorageImage::new(device,
+                                      Dimensions::Dim2d {
+                                          width: dimensions[0],
+                                          height: dimensions[1],
+                                      },
+                                      FORMAT, Some(queue.family()))
+            .map_err(|err| Error::OutOfMemory("the mesh image", err))?;
+        let depth = AttachmentImage::transient(device, dimensions, DEPTH_FORMAT)
+            .map_err(|err| Error::OutOfMemory("the depth buffer", err))?;
+
+        let render_pass = mesh_render_pass::CustomRenderPass::new(device,
+                                                                  &mesh_render_pass::Formats {
+            color: (FORMAT, 1),
+            depth: (DEPTH_FORMAT, 1),
+        }).map_err(|err| Error::RenderPass("the mesh render pass", err))?;
+
+        let framebuffer = Framebuffer::new(&render_pass, [dimensions[0], dimensions[1], 1],
+                                           mesh_render_pass::AList {
+                                               color: &image,
+                                               depth: &depth,
+                                           })
+            .map_err(|err| Error::Framebuffer("the mesh framebuffer", err))?;
+
+        let vs = ::mesh_vs::Shader::load(device)
+            .map_err(|err| Error::OutOfMemory("the vertex shader module", err))?;
+        let fs = ::mesh_fs::Shader::load(device)
+            .map_err(|err| Error::OutOfMemory("the fragment shader module", err))?;
+
+        let pipeline = GraphicsPipeline::new(device, GraphicsPipelineParams {
+            vertex_input: SingleBufferDefinition::new(),
+            vertex_shader: vs.main_entry_point(),
+            input_assembly: InputAssembly {
+                topology: PrimitiveTopology::TriangleList,
+                primitive_restart_enable: false,
+            },
+            tessellation: None,
+            geometry_shader: None,
+            viewport: ViewportsState::Fixed {
+                data: vec![(
+                    Viewport {
+                        origin: [0.0, 0.0],
+                        depth_range: 0.0 .. 1.0,
+                        dimensions: [dimensions[0] as f32, dimensions[1] as f32],
+                    },
+                    Scissor::irrelevant()
+                )],
+            },
+            // The winding of the triangles in OBJ files isn't reliable, so both sides are drawn.
+            raster: Default::default(),
+            multisample: Multisample::disabled(),
+            fragment_shader: fs.main_entry_point(),
+            depth_stencil: ::depth_test(config.depth_write),
+            blend: Blend::pass_through(),
+            layout: pipeline_layout,
+            render_pass: Subpass::from(&render_pass, 0).ok_or_else(Error::no_subpass)?,
+        }).map_err(|err| Error::GraphicsPipeline("the mesh pipeline", err))?;
+
+        let sampler = upscale::create_sampler(device, config.scale_filter)?;
+        let (overlay_layout, overlay_pipeline) =
+            upscale::create_overlay_pipeline(device, config, target_render_pass,
+                                             target_dimensions, dimensions)?;
+        let overlay_set = upscale::blit_layout::set0::Set::new(descriptor_pool, &overlay_layout,
+                                                      &upscale::blit_layout::set0::Descriptors {
+                                                          source: (&sampler, &image)
+                                                      });
+
+        Ok(MeshPass {
+            dimensions: dimensions,
+            render_pass: render_pass,
+            framebuffer: framebuffer,
+            pipeline: pipeline,
+            overlay_pipeline: overlay_pipeline,
+            overlay_set: overlay_set,
+        })
+    }
+
+    /// Adds the offscreen images to `usage`.
+    pub fn memory_usage(&self, usage: &mut MemoryUsage) {
+        usage.image("mesh image", 1, self.dimensions, FORMAT);
+        usage.image("mesh depth buffer", 1, self.dimensions, DEPTH_FORMAT);
+    }
+
+    /// Records drawing `geometry` into the offscreen image, with the descriptor set `set` of the
+    /// scene for the uniforms.
+    pub fn record(&self, builder: PrimaryCommandBufferBuilder, geometry: &Geometry<MeshVertex>,
+                  set: &Arc<::pipeline_layout::set0::Set>) -> PrimaryCommandBufferBuilder {
+        // Where there is no mesh, the scene shows through.
+        builder.draw_inline(&self.render_pass, &self.framebuffer, mesh_render_pass::ClearValues {
+                   color: [0.0, 0.0, 0.0, 0.0],
+                   depth: 1.0,
+               })
+               .draw_indexed(&self.pipeline, geometry.vertices(), geometry.indices(),
+                             &DynamicState::none(), set, &())
+               .draw_end()
+    }
+
+    /// Blends the mesh over the scene, within a render pass on the swapchain image.
+    pub fn draw(&self, builder: PrimaryCommandBufferBuilderInlineDraw,
+                vertex_buffer: &Arc<CpuAccessibleBuffer<[Vertex]>>)
+                -> PrimaryCommandBufferBuilderInlineDraw {
+        builder.draw(&self.overlay_pipeline, vertex_buffer, &DynamicState::none(),
+                     &self.overlay_set, &())
+    }
+}
@@ -0,0 +1,64 @@
+#version 450
+
+#extension GL_ARB_separate_shader_objects: enable
+#extension GL_ARB_shading_language_420pack: enable
+
+layout(set = 0, binding = 0) uniform Data {
+    vec2 resolution;
+    // The offset of the sample position within the pixel, in pixels.
+    vec2 jitter;
+    // Shadertoy's `iMouse`, in the pixels of `frag_coord()`: where the cursor was last while the
+    // left button was held, and where the button was pressed, negated while it is released.
+    vec4 mouse;
+    // Where the camera is and the unit vector it looks along. Each is followed by a scalar, which
+    // keeps the block free of padding.
+    vec3 camera_position;
+    float time;
+    vec3 camera_direction;
+    float dt;
+    // The unit vector towards a directional light, its intensity and its color, for shaders that
+    // shade. The built-in scene doesn't.
+    vec3 light_direction;
+    float light_intensity;
+    vec3 light_color;
+    uint debug_tint;
+    float refresh_hz;
+    float exposure;
+    // The number of frames rendered before.
+    uint frame;
+    // Whether the cost of each pixel is shown as a heatmap instead of its color.
+    uint heatmap;
+    // Whether `frag_coord()` and `mouse` have their origin at the bottom-left, with `--flip-y`.
+    uint flip_y;
+    // The objects of `--reference` shown, 1 for the sphere and 2 for the ground plane, the height
+    // of the plane and the radius and center of the sphere.
+    uint reference;
+    float ground_height;
+    float reference_radius;
+    vec3 reference_center;
+    float real_time;
+    vec3 camera_up;
+    float fov;
+    uint bounces;
+    float time_of_day;
+    uint orthographic;
+    float ortho_size;
+} uniforms;
+
+layout(location = 0) in vec3 v_normal;
+
+layout(location = 0) out vec4 f_color;
+
+// The share of the light that reaches the sides facing away from it.
+#define AMBIENT 0.2
+
+// Lambertian shading by the light of the uniforms, with the normal as the color so that the
+// orientation of the surfaces can be told apart.
+void main() {
+    vec3 normal = normalize(v_normal);
+    vec3 albedo = normal * 0.5 + 0.5;
+    float diffuse = max(dot(normal, uniforms.light_direction), 0.0);
+    vec3 light = uniforms.light_color * uniforms.light_intensity * diffuse + AMBIENT;
+
+    f_color = vec4(albedo * light * uniforms.exposure, 1.0);
+}
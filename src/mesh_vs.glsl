@@ -0,0 +1,91 @@
+#version 450
+
+#extension GL_ARB_separate_shader_objects: enable
+#extension GL_ARB_shading_language_420pack: enable
+
+layout(set = 0, binding = 0) uniform Data {
+    vec2 resolution;
+    // The offset of the sample position within the pixel, in pixels.
+    vec2 jitter;
+    // Shadertoy's `iMouse`, in the pixels of `frag_coord()`: where the cursor was last while the
+    // left button was held, and where the button was pressed, negated while it is released.
+    vec4 mouse;
+    // Where the camera is and the unit vector it looks along. Each is followed by a scalar, which
+    // keeps the block free of padding.
+    vec3 camera_position;
+    float time;
+    vec3 camera_direction;
+    float dt;
+    // The unit vector towards a directional light, its intensity and its color, for shaders that
+    // shade. The built-in scene doesn't.
+    vec3 light_direction;
+    float light_intensity;
+    vec3 light_color;
+    uint debug_tint;
+    float refresh_hz;
+    float exposure;
+    // The number of frames rendered before.
+    uint frame;
+    // Whether the cost of each pixel is shown as a heatmap instead of its color.
+    uint heatmap;
+    // Whether `frag_coord()` and `mouse` have their origin at the bottom-left, with `--flip-y`.
+    uint flip_y;
+    // The objects of `--reference` shown, 1 for the sphere and 2 for the ground plane, the height
+    // of the plane and the radius and center of the sphere.
+    uint reference;
+    float ground_height;
+    float reference_radius;
+    vec3 reference_center;
+    float real_time;
+    vec3 camera_up;
+    float fov;
+    uint bounces;
+    float time_of_day;
+    uint orthographic;
+    float ortho_size;
+} uniforms;
+
+// The nearest distance along the view direction that is drawn, there is no farthest one.
+#define NEAR 0.01
+
+// The farthest distance drawn with `--ortho`, whose depth can't approach 1 without one the way
+// the perspective one does.
+#define ORTHO_FAR 10000.0
+
+layout(location = 0) in vec3 position;
+layout(location = 1) in vec3 normal;
+
+layout(location = 0) out vec3 v_normal;
+
+// Projects with the same camera as `fs.glsl`: the columns of its basis are the forward, left and
+// up directions, and the field of view is `fov` across the diagonal, or `ortho_size` with
+// `--ortho`.
+void main() {
+    vec3 forward = uniforms.camera_direction;
+    vec3 left = normalize(cross(uniforms.camera_up, forward));
+    vec3 up = cross(forward, left);
+    vec3 rel = position - uniforms.camera_position;
+    float distance_from_screen_center =
+        length(uniforms.resolution) / (2.0 * tan(uniforms.fov / 2.0));
+    float depth = dot(rel, forward);
+
+    if(uniforms.orthographic != 0u) {
+        vec2 offset = vec2(-dot(rel, left), -dot(rel, up)) *
+                      (length(uniforms.resolution) / uniforms.ortho_size);
+
+        // The depth is linear from 0 at `NEAR` to 1 at `ORTHO_FAR`, as is everything without
+        // a division by `w`.
+        gl_Position = vec4(offset / (uniforms.resolution / 2.0),
+                           (depth - NEAR) / (ORTHO_FAR - NEAR), 1.0);
+        v_normal = normal;
+        return;
+    }
+
+    // The offset from the center of the screen in pixels, times the depth that the division by
+    // `w` takes out again.
+    vec2 offset = vec2(-dot(rel, left), -dot(rel, up)) * distance_from_screen_center;
+
+    // Dividing by `w` makes the depth `1 - NEAR / depth`, from 0 at `NEAR` towards 1 far away.
+    gl_Position = vec4(offset / (uniforms.resolution / 2.0), depth - NEAR, depth);
+    v_normal = normal;
+}
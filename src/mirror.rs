@@ -0,0 +1,247 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use vulkano::buffer::CpuAccessibleBuffer;
+use vulkano::command_buffer;
+use vulkano::command_buffer::DynamicState;
+use vulkano::command_buffer::PrimaryCommandBufferBuilder;
+use vulkano::command_buffer::Submission;
+use vulkano::descriptor::descriptor_set::DescriptorPool;
+use vulkano::device::Device;
+use vulkano::device::Queue;
+use vulkano::format::Format;
+use vulkano::framebuffer::Framebuffer;
+use vulkano::image::ImageUsage;
+use vulkano::image::StorageImage;
+use vulkano::image::SwapchainImage;
+use vulkano::swapchain::AcquireError;
+use vulkano::swapchain::PresentError;
+use vulkano::swapchain::Surface;
+use vulkano::swapchain::SurfaceTransform;
+use vulkano::swapchain::Swapchain;
+
+use config::Config;
+use queue;
+use render_pass::CustomRenderPass;
+use renderer;
+use upscale;
+use upscale::BlitPipeline;
+use upscale::blit_layout;
+use Vertex;
+
+/// Shows the scene rendered offscreen in the swapchain of another window, such as the small
+/// preview of `--preview-window` next to a large output window.
+///
+/// The scene isn't rendered again, the offscreen image it was rendered into for the main window
+/// is scaled into this one, keeping its aspect ratio.
+pub struct Mirror {
+    device: Arc<Device>,
+    queue: Arc<Queue>,
+    present_queue: Arc<Queue>,
+    config: Config,
+    descriptor_pool: Arc<DescriptorPool>,
+    surface: Arc<Surface>,
+    /// The dimensions of the window, used if the surface doesn't determine the dimensions of the
+    /// swapchain.
+    window_dimensions: [u32; 2],
+    /// Set when the swapchain should be recreated before the next frame.
+    recreate_swapchain: bool,
+    swapchain: Arc<Swapchain>,
+    images: Vec<Arc<SwapchainImage>>,
+    render_pass: Arc<CustomRenderPass>,
+    framebuffers: Vec<Arc<Framebuffer<CustomRenderPass>>>,
+    source: Arc<StorageImage<Format>>,
+    source_dimensions: [u32; 2],
+    pipeline: Arc<BlitPipeline>,
+    set: Arc<blit_layout::set0::Set>,
+}
+
+impl Mirror {
+    /// Creates a swapchain for `surface` of a window of `window_dimensions`, to show `source` of
+    /// `source_dimensions` in, drawn into with `queue` and presented with `present_queue`.
+    pub fn new(device: &Arc<Device>, queue: &Arc<Queue>, present_queue: &Arc<Queue>,
+               descriptor_pool: &Arc<DescriptorPool>, surface: &Arc<Surface>,
+               window_dimensions: [u32; 2], config: &Config, source: &Arc<StorageImage<Format>>,
+               source_dimensions: [u32; 2])
+               -> Result<Mirror, String> {
+        if !surface.is_supported(&present_queue.family()).unwrap_or(false) {
+            return Err("the queue can't present to the surface of the window".to_string());
+        }
+
+        let caps = surface.get_capabilities(&device.physical_device())
+            .map_err(|err| format!("failed to get the capabilities of the surface: {}", err))?;
+        let present = caps.present_modes.iter().next().ok_or("the surface has no present mode")?;
+        let alpha = caps.supported_composite_alpha.iter().next()
+            .ok_or("the surface supports no composite alpha mode")?;
+        let format = caps.supported_formats[0].0;
+        let usage = ImageUsage {
+            color_attachment: true,
+            .. ImageUsage::none()
+        };
+
+        let (swapchain, images) = Swapchain::new(device, surface, 2, format,
+                                                 renderer::swapchain_dimensions(&caps,
+                                                                                window_dimensions),
+                                                 1, &usage,
+                                                 queue::sharing_mode(&[queue, present_queue]),
+                                                 SurfaceTransform::Identity, alpha, present,
+                                                 true, None)
+            .map_err(|err| format!("failed to create the swapchain: {}", err))?;
+
+        let render_pass = CustomRenderPass::new(device, &::render_pass::Formats {
+            color: (format, 1)
+        }).map_err(|err| format!("failed to create the render pass: {}", err))?;
+
+        let (pipeline, set) = create_blit(device, descriptor_pool, config, &render_pass,
+                                          images[0].dimensions(), source, source_dimensions)?;
+
+        let mut mirror = Mirror {
+            device: device.clone(),
+            queue: queue.clone(),
+            present_queue: present_queue.clone(),
+            config: config.clone(),
+            descriptor_pool: descriptor_pool.clone(),
+            surface: surface.clone(),
+            window_dimensions: window_dimensions,
+            recreate_swapchain: false,
+            swapchain: swapchain,
+            images: images,
+            render_pass: render_pass,
+            framebuffers: Vec::new(),
+            source: source.clone(),
+            source_dimensions: source_dimensions,
+            pipeline: pipeline,
+            set: set,
+        };
+
+        mirror.create_framebuffers()?;
+        Ok(mirror)
+    }
+
+    fn create_framebuffers(&mut self) -> Result<(), String> {
+        self.framebuffers = self.images.iter().map(|image| {
+            let dimensions = [image.dimensions()[0], image.dimensions()[1], 1];
+
+            Framebuffer::new(&self.render_pass, dimensions, ::render_pass::AList {
+                color: image
+            }).map_err(|err| format!("failed to create the framebuffer: {}", err))
+        }).collect::<Result<_, _>>()?;
+
+        Ok(())
+    }
+
+    /// Shows `source` of `source_dimensions` from now on, after the offscreen image of the scene
+    /// has been recreated.
+    pub fn set_source(&mut self, source: &Arc<StorageImage<Format>>,
+                      source_dimensions: [u32; 2]) -> Result<(), String> {
+        self.source = source.clone();
+        self.source_dimensions = source_dimensions;
+        self.recreate_blit()
+    }
+
+    fn recreate_blit(&mut self) -> Result<(), String> {
+        let (pipeline, set) = create_blit(&self.device, &self.descriptor_pool, &self.config,
+                                          &self.render_pass, self.images[0].dimensions(),
+                                          &self.source, self.source_dimensions)?;
+
+        self.pipeline = pipeline;
+        self.set = set;
+        Ok(())
+    }
+
+    /// Recreates the swapchain for the window resized to `dimensions` before the next frame.
+    pub fn resize(&mut self, dimensions: [u32; 2]) {
+        self.window_dimensions = dimensions;
+        self.recreate_swapchain = true;
+    }
+
+    /// Whether the swapchain is recreated before the next frame, which waits for the frames
+    /// still using its images.
+    pub fn outdated(&self) -> bool {
+        self.recreate_swapchain
+    }
+
+    fn recreate_swapchain(&mut self) -> Result<(), String> {
+        let caps = self.surface.get_capabilities(&self.device.physical_device())
+            .map_err(|err| format!("failed to get the capabilities of the surface: {}", err))?;
+        let dimensions = renderer::swapchain_dimensions(&caps, self.window_dimensions);
+        let (swapchain, images) = self.swapchain.recreate_with_dimension(dimensions)
+            .map_err(|err| format!("failed to recreate the swapchain: {}", err))?;
+
+        self.swapchain = swapchain;
+        self.images = images;
+        self.recreate_swapchain = false;
+        self.create_framebuffers()?;
+        self.recreate_blit()
+    }
+
+    /// Draws the source into the next swapchain image and presents it, returning the submission
+    /// unless the swapchain had to be recreated instead.
+    ///
+    /// An error means that the window can't be shown anymore, typically as its surface is lost.
+    pub fn present(&mut self, vertex_buffer: &Arc<CpuAccessibleBuffer<[Vertex]>>)
+                   -> Result<Option<Arc<Submission>>, String> {
+        if self.recreate_swapchain {
+            self.recreate_swapchain()?;
+        }
+
+        let image_num = match self.swapchain.acquire_next_image(Duration::new(1, 0)) {
+            Ok((image_num, suboptimal)) => {
+                self.recreate_swapchain = suboptimal;
+                image_num
+            }
+            Err(AcquireError::OutOfDate) => {
+                self.recreate_swapchain = true;
+                return Ok(None);
+            }
+            Err(err) => return Err(format!("failed to acquire a swapchain image: {}", err)),
+        };
+
+        // The bars around a scene of another aspect ratio are left black.
+        let command_buffer = PrimaryCommandBufferBuilder::new(&self.device, self.queue.family())
+            .draw_inline(&self.render_pass, &self.framebuffers[image_num],
+                         ::render_pass::ClearValues {
+                             color: [0.0, 0.0, 0.0, 1.0]
+                         })
+            .draw(&self.pipeline, vertex_buffer, &DynamicState::none(), &self.set, &())
+            .draw_end()
+            .build();
+
+        let submission = command_buffer::submit(&command_buffer, &self.queue)
+            .map_err(|err| format!("failed to submit the command buffer: {}", err))?;
+
+        match self.swapchain.present(&self.present_queue, image_num) {
+            Ok(()) => (),
+            Err(PresentError::OutOfDate) => self.recreate_swapchain = true,
+            Err(err) => return Err(format!("failed to present a swapchain image: {}", err)),
+        }
+
+        Ok(Some(submission))
+    }
+}
+
+/// Creates the pipeline scaling `source` of `source_dimensions` into the images of
+/// `target_dimensions`, and its descriptor set.
+fn create_blit(device: &Arc<Device>, descriptor_pool: &Arc<DescriptorPool>, config: &Config,
+               render_pass: &Arc<CustomRenderPass>, target_dimensions: [u32; 2],
+               source: &Arc<StorageImage<Format>>, source_dimensions: [u32; 2])
+               -> Result<(Arc<BlitPipeline>, Arc<blit_layout::set0::Set>), String> {
+    // The scene keeps its aspect ratio in a window of another one, as an internal resolution
+    // does.
+    let config = Config {
+        internal_res: Some(source_dimensions),
+        .. config.clone()
+    };
+    let sampler = upscale::create_sampler(device, config.scale_filter)
+        .map_err(|err| err.to_string())?;
+    let (pipeline_layout, pipeline) = upscale::create_blit_pipeline(device, &config, render_pass,
+                                                                    target_dimensions,
+                                                                    source_dimensions)
+        .map_err(|err| err.to_string())?;
+    let set = blit_layout::set0::Set::new(descriptor_pool, &pipeline_layout,
+                                          &blit_layout::set0::Descriptors {
+                                              source: (&sampler, source)
+                                          });
+
+    Ok((pipeline, set))
+}
@@ -0,0 +1,100 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use image;
+
+use vulkano::instance::Instance;
+use vulkano::instance::PhysicalDevice;
+
+use config::Config;
+use device;
+use error::Error;
+use headless;
+use text::Canvas;
+use text::GLYPH_SIZE;
+
+/// The space left around the label and between the glyphs, in pixels.
+const MARGIN: u32 = 2;
+
+/// The height of the band below each frame that its label is written in.
+const LABEL_HEIGHT: u32 = GLYPH_SIZE[1] + 2 * MARGIN;
+
+/// Renders the same frame on every device that can render offscreen, as with `--headless`, and
+/// saves them side by side in a grid to `path` as a PNG, each labeled with the name of its
+/// device.
+///
+/// Devices that fail are left out with a warning, only if none succeeds is it an error.
+pub fn render(instance: &Arc<Instance>, config: &Config, path: &Path) -> Result<(), Error> {
+    let mut frames = Vec::new();
+
+    for physical in PhysicalDevice::enumerate(instance) {
+        info!("rendering on device {}: {} (type: {:?})", physical.index(), physical.name(),
+              physical.ty());
+
+        match headless::render_frame(physical, config) {
+            Ok((dimensions, pixels)) => {
+                frames.push((physical.name().to_string(), dimensions, pixels))
+            }
+            Err(err) => {
+                warn!("skipping device {} ({}): {}", physical.index(), physical.name(), err)
+            }
+        }
+    }
+
+    if frames.is_empty() {
+        return Err(Error::Capture("no device could render the frame".to_string()));
+    }
+
+    save(&frames, headless::dimensions(config), path)?;
+    info!("saved the frames of {} devices to `{}`", frames.len(), path.display());
+
+    Ok(())
+}
+
+/// Renders the same frame as with `--headless` once without anti-aliasing and once with the
+/// samples of `--accumulate`, and saves them side by side to `path` as a PNG, each labeled with
+/// how it was sampled.
+pub fn compare_aa(instance: &Arc<Instance>, config: &Config, path: &Path) -> Result<(), Error> {
+    let physical = device::select_physical_device(instance, config).map_err(Error::Device)?;
+    let samples = config.accumulate.unwrap_or(1);
+
+    info!("using device: {} (type: {:?})", physical.name(), physical.ty());
+
+    let aliased = Config { accumulate: None, .. config.clone() };
+    let (aliased_dimensions, aliased_pixels) = headless::render_frame(physical, &aliased)?;
+    let (dimensions, pixels) = headless::render_frame(physical, config)?;
+
+    let frames = [
+        ("no anti-aliasing".to_string(), aliased_dimensions, aliased_pixels),
+        (format!("{} sample{}", samples, if samples == 1 { "" } else { "s" }), dimensions, pixels),
+    ];
+
+    save(&frames, headless::dimensions(config), path)?;
+    info!("saved the comparison to `{}`", path.display());
+
+    Ok(())
+}
+
+/// Saves `frames` in a grid to `path` as a PNG, each with its name written below it, in tiles
+/// large enough for frames of `dimensions`.
+fn save(frames: &[(String, [u32; 2], Vec<u8>)], dimensions: [u32; 2], path: &Path)
+        -> Result<(), Error> {
+    // As close to a square as the number of frames allows.
+    let columns = (frames.len() as f32).sqrt().ceil() as u32;
+    let rows = (frames.len() as u32 + columns - 1) / columns;
+    // Frames that had to be rendered at a lower resolution leave the rest of their tile empty.
+    let tile = [dimensions[0], dimensions[1] + LABEL_HEIGHT];
+    let mut canvas = Canvas::new(tile[0] * columns, tile[1] * rows);
+
+    for (index, &(ref name, frame, ref pixels)) in frames.iter().enumerate() {
+        let x = (index as u32 % columns) * tile[0];
+        let y = (index as u32 / columns) * tile[1];
+
+        canvas.blit(x, y, frame, pixels);
+        canvas.write(x + MARGIN, y + dimensions[1] + MARGIN, dimensions[0].saturating_sub(MARGIN),
+                     name);
+    }
+
+    image::save_buffer(path, canvas.pixels(), canvas.width(), canvas.height(), image::RGBA(8))
+        .map_err(|err| Error::Capture(format!("failed to save `{}`: {}", path.display(), err)))
+}
@@ -0,0 +1,284 @@
+use std::sync::Arc;
+
+use vulkano::buffer::CpuAccessibleBuffer;
+use vulkano::command_buffer::DynamicState;
+use vulkano::command_buffer::PrimaryCommandBufferBuilder;
+use vulkano::command_buffer::PrimaryCommandBufferBuilderInlineDraw;
+use vulkano::descriptor::descriptor_set::DescriptorPool;
+use vulkano::device::Device;
+use vulkano::device::Queue;
+use vulkano::format::ClearValue;
+use vulkano::format::Format;
+use vulkano::framebuffer::Framebuffer;
+use vulkano::image::Dimensions;
+use vulkano::image::StorageImage;
+use vulkano::sampler::Filter;
+use vulkano::pipeline::GraphicsPipeline;
+
+use config::Config;
+use error::Error;
+use memory::MemoryUsage;
+use render_pass::CustomRenderPass;
+use shader::FragmentShader;
+use shader::VertexShader;
+use upscale;
+use upscale::BlitPipeline;
+use Vertex;
+
+/// The format of the offscreen images the scene is rendered into.
+const FORMAT: Format = Format::R8G8B8A8Unorm;
+
+/// The format of the image of the distances to what each pixel hit, which would be cut off by
+/// `FORMAT`.
+const DISTANCE_FORMAT: Format = Format::R32Sfloat;
+
+/// The format of the image of the ids of what each pixel hit, an integer one so that no id is
+/// blended or filtered into another.
+const ID_FORMAT: Format = Format::R32Uint;
+
+/// The names of the attachments, in the order of the locations of the outputs of `fs.glsl`.
+pub const ATTACHMENTS: [&'static str; 4] = ["color", "normal", "id", "distance"];
+
+/// The names of the objects of the built-in scene, in the order of the ids `fs.glsl` writes for
+/// them from 1. The id 0 is nothing at all.
+const OBJECTS: [&'static str; 3] = ["sphere", "reference sphere", "ground"];
+
+mod mrt_render_pass {
+    use vulkano::format::Format;
+
+    single_pass_renderpass!{
+        attachments: {
+            color: {
+                load: Clear,
+                store: Store,
+                format: Format,
+            },
+            normal: {
+                load: Clear,
+                store: Store,
+                format: Format,
+            },
+            id: {
+                load: Clear,
+                store: Store,
+                format: Format,
+            },
+            distance: {
+                load: Clear,
+                store: Store,
+                format: Format,
+            }
+        },
+        pass: {
+            color: [color, normal, id, distance],
+            depth_stencil: {}
+        }
+    }
+}
+
+type ScenePipeline = GraphicsPipeline<::vertex_input::SceneInput,
+                                      ::pipeline_layout::CustomPipeline,
+                                      mrt_render_pass::CustomRenderPass>;
+
+/// Offscreen images the scene writes all the outputs of the fragment shader into in a single
+/// pass, one of which is then copied into the swapchain image to be looked at.
+pub struct RenderTargets {
+    dimensions: [u32; 2],
+    render_pass: Arc<mrt_render_pass::CustomRenderPass>,
+    framebuffer: Arc<Framebuffer<mrt_render_pass::CustomRenderPass>>,
+    pipeline: Arc<ScenePipeline>,
+    blit_pipeline: Arc<BlitPipeline>,
+    /// Draws the ids instead, which can't be sampled as colors.
+    id_pipeline: Arc<BlitPipeline>,
+    /// One for each attachment, sampling its image.
+    blit_sets: Vec<Arc<upscale::blit_layout::set0::Set>>,
+    id: Arc<StorageImage<Format>>,
+    distance: Arc<StorageImage<Format>>,
+    /// What the color target is cleared to, `--clear-color`.
+    clear_color: [f32; 4],
+}
+
+impl RenderTargets {
+    /// Creates the offscreen images for a swapchain with images of `target_dimensions`, to be
+    /// copied into with `target_render_pass`.
+    pub fn new(device: &Arc<Device>, queue: &Arc<Queue>, config: &Config, vs: &VertexShader,
+               fs: &FragmentShader, pipeline_layout: &Arc<::pipeline_layout::CustomPipeline>,
+               descriptor_pool: &Arc<DescriptorPool>,
+               target_render_pass: &Arc<CustomRenderPass>, target_dimensions: [u32; 2])
+               -> Result<RenderTargets, Error> {
+        let dimensions = upscale::scene_dimensions(target_dimensions, config);
+        let image = |format| {
+            StorageImage::new(device,
+                              Dimensions::Dim2d { width: dimensions[0], height: dimensions[1] },
+                              format, Some(queue.family()))
+                .map_err(|err| Error::OutOfMemory("the render target", err))
+        };
+        let images = [image(FORMAT)?, image(FORMAT)?, image(ID_FORMAT)?, image(DISTANCE_FORMAT)?];
+
+        let render_pass = mrt_render_pass::CustomRenderPass::new(device, &mrt_render_pass::Formats {
+            color: (FORMAT, 1),
+            normal: (FORMAT, 1),
+            id: (ID_FORMAT, 1),
+            distance: (DISTANCE_FORMAT, 1),
+        }).map_err(|err| Error::RenderPass("the render pass of the render targets", err))?;
+
+        let framebuffer = Framebuffer::new(&render_pass, [dimensions[0], dimensions[1], 1],
+                                           mrt_render_pass::AList {
+                                               color: &images[0],
+                                               normal: &images[1],
+                                               id: &images[2],
+                                               distance: &images[3],
+                                           })
+            .map_err(|err| Error::Framebuffer("the framebuffer of the render targets", err))?;
+
+        let pipeline = ::create_pipeline(device, config, vs, fs, pipeline_layout, &render_pass,
+                                         dimensions)?;
+
+        let sampler = upscale::create_sampler(device, config.scale_filter)?;
+        // Devices don't have to support linear filtering of 32-bit floats, and integers can't be
+        // filtered at all.
+        let nearest_sampler = upscale::create_sampler(device, Filter::Nearest)?;
+        let (blit_layout, blit_pipeline) = upscale::create_blit_pipeline(device, config,
+                                                                         target_render_pass,
+                                                                         target_dimensions,
+                                                                         dimensions)?;
+        let (id_layout, id_pipeline) = upscale::create_id_pipeline(device, config,
+                                                                   target_render_pass,
+                                                                   target_dimensions, dimensions)?;
+
+        let blit_sets = images.iter().zip(ATTACHMENTS.iter()).map(|(image, &name)| {
+            let (sampler, layout) = match name {
+                "id" => (&nearest_sampler, &id_layout),
+                "distance" => (&nearest_sampler, &blit_layout),
+                _ => (&sampler, &blit_layout),
+            };
+
+            upscale::blit_layout::set0::Set::new(descriptor_pool, layout,
+                                                 &upscale::blit_layout::set0::Descriptors {
+                                                     source: (sampler, image)
+                                                 })
+        }).collect();
+
+        Ok(RenderTargets {
+            dimensions: dimensions,
+            render_pass: render_pass,
+            framebuffer: framebuffer,
+            pipeline: pipeline,
+            blit_pipeline: blit_pipeline,
+            id_pipeline: id_pipeline,
+            blit_sets: blit_sets,
+            id: images[2].clone(),
+            distance: images[3].clone(),
+            clear_color: config.clear_color,
+        })
+    }
+
+    /// The dimensions the scene is rendered at.
+    pub fn dimensions(&self) -> [u32; 2] {
+        self.dimensions
+    }
+
+    /// Recreates the pipeline, after the shaders of the scene have changed.
+    pub fn set_shaders(&mut self, device: &Arc<Device>, config: &Config, vs: &VertexShader,
+                       fs: &FragmentShader,
+                       pipeline_layout: &Arc<::pipeline_layout::CustomPipeline>)
+                       -> Result<(), Error> {
+        self.pipeline = ::create_pipeline(device, config, vs, fs, pipeline_layout,
+                                          &self.render_pass, self.dimensions)?;
+
+        Ok(())
+    }
+
+    /// Adds the offscreen images to `usage`.
+    pub fn memory_usage(&self, usage: &mut MemoryUsage) {
+        usage.image("render targets", ATTACHMENTS.len() - 2, self.dimensions, FORMAT);
+        usage.image("id render target", 1, self.dimensions, ID_FORMAT);
+        usage.image("distance render target", 1, self.dimensions, DISTANCE_FORMAT);
+    }
+
+    /// Clears the color target to `color` from the next frame on.
+    pub fn set_clear_color(&mut self, color: [f32; 4]) {
+        self.clear_color = color;
+    }
+
+    /// Records drawing the scene into the offscreen images, with the descriptor set `set`.
+    pub fn record(&self, builder: PrimaryCommandBufferBuilder,
+                  vertex_buffer: &Arc<CpuAccessibleBuffer<[Vertex]>>,
+                  set: &Arc<::pipeline_layout::set0::Set>) -> PrimaryCommandBufferBuilder {
+        builder.draw_inline(&self.render_pass, &self.framebuffer, mrt_render_pass::ClearValues {
+                   color: self.clear_color,
+                   normal: [0.0, 0.0, 0.0, 1.0],
+                   id: ClearValue::Uint([0, 0, 0, 0]),
+                   distance: [0.0, 0.0, 0.0, 1.0],
+               })
+               .draw(&self.pipeline, vertex_buffer, &DynamicState::none(), set, &())
+               .draw_end()
+    }
+
+    /// Records copying the distances of the last frame into `buffer`, a row after another from
+    /// the top.
+    pub fn copy_distances(&self, builder: PrimaryCommandBufferBuilder,
+                          buffer: &Arc<CpuAccessibleBuffer<[f32]>>)
+                          -> PrimaryCommandBufferBuilder {
+        builder.copy_color_image_to_buffer(buffer, &self.distance, 0, 0 .. 1, [0, 0, 0],
+                                           [self.dimensions[0], self.dimensions[1], 1])
+    }
+
+    /// Records copying the id of the last frame at `position`, in pixels of the scene from the
+    /// top-left, into `buffer`.
+    pub fn copy_id(&self, builder: PrimaryCommandBufferBuilder,
+                   buffer: &Arc<CpuAccessibleBuffer<[u32]>>, position: [u32; 2])
+                   -> PrimaryCommandBufferBuilder {
+        builder.copy_color_image_to_buffer(buffer, &self.id, 0, 0 .. 1,
+                                           [position[0], position[1], 0], [1, 1, 1])
+    }
+
+    /// Draws the attachment with the index `attachment` in `ATTACHMENTS`, within a render pass
+    /// on the swapchain image.
+    pub fn draw(&self, builder: PrimaryCommandBufferBuilderInlineDraw,
+                vertex_buffer: &Arc<CpuAccessibleBuffer<[Vertex]>>, attachment: usize)
+                -> PrimaryCommandBufferBuilderInlineDraw {
+        let pipeline = if ATTACHMENTS[attachment] == "id" {
+            &self.id_pipeline
+        } else {
+            &self.blit_pipeline
+        };
+
+        builder.draw(pipeline, vertex_buffer, &DynamicState::none(), &self.blit_sets[attachment],
+                     &())
+    }
+}
+
+/// Encodes `distances` as 8-bit gray from white at `range.0` to black at `range.1`, linearly in
+/// between and clamped outside, and where nothing was hit, which `fs.glsl` writes as 0.
+///
+/// Without a range, it is the one of the distances that were hit.
+pub fn encode_distances(distances: &[f32], range: Option<(f32, f32)>) -> Vec<u8> {
+    let hit = || distances.iter().cloned().filter(|&distance| distance > 0.0);
+    let (near, far) = range.unwrap_or_else(|| {
+        (hit().fold(::std::f32::INFINITY, f32::min), hit().fold(0.0, f32::max))
+    });
+    // A single distance, or none at all, is shown white.
+    let span = if far > near { far - near } else { 1.0 };
+
+    distances.iter().map(|&distance| {
+        if distance > 0.0 {
+            (255.0 * (1.0 - ((distance - near) / span).max(0.0).min(1.0))).round() as u8
+        } else {
+            0
+        }
+    }).collect()
+}
+
+/// Describes the object of `id` picked from the `id` render target, by its name in the built-in
+/// scene. A custom fragment shader may write ids of its own, which are only given as numbers.
+pub fn describe_object(id: u32, custom: bool) -> String {
+    if id == 0 {
+        return "nothing".to_string();
+    }
+
+    match OBJECTS.get(id as usize - 1) {
+        Some(name) if !custom => format!("the {} (id {})", name, id),
+        _ => format!("the object with the id {}", id),
+    }
+}
@@ -0,0 +1,177 @@
+use std::sync::Arc;
+
+use vulkano::buffer::BufferUsage;
+use vulkano::buffer::CpuAccessibleBuffer;
+use vulkano::command_buffer::DynamicState;
+use vulkano::command_buffer::PrimaryCommandBufferBuilderInlineDraw;
+use vulkano::descriptor::pipeline_layout::EmptyPipeline;
+use vulkano::device::Device;
+use vulkano::device::Queue;
+use vulkano::framebuffer::Subpass;
+use vulkano::pipeline::GraphicsPipeline;
+use vulkano::pipeline::GraphicsPipelineParams;
+use vulkano::pipeline::blend::Blend;
+use vulkano::pipeline::depth_stencil::DepthStencil;
+use vulkano::pipeline::input_assembly::InputAssembly;
+use vulkano::pipeline::input_assembly::PrimitiveTopology;
+use vulkano::pipeline::multisample::Multisample;
+use vulkano::pipeline::vertex::SingleBufferDefinition;
+use vulkano::pipeline::viewport::ViewportsState;
+use vulkano::pipeline::viewport::Viewport;
+use vulkano::pipeline::viewport::Scissor;
+
+use error::Error;
+use render_pass::CustomRenderPass;
+use text;
+use text::GLYPH_SIZE;
+
+/// The space between the text and the edges of its panel, and between its columns, in pixels.
+const MARGIN: u32 = 6;
+/// The space between two lines, in pixels.
+const LINE_SPACING: u32 = 2;
+
+const TEXT_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+const PANEL_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 0.75];
+
+#[derive(Debug, Clone)]
+struct OverlayVertex {
+    position: [f32; 2],
+    color: [f32; 4],
+}
+impl_vertex!(OverlayVertex, position, color);
+
+type OverlayPipeline = GraphicsPipeline<SingleBufferDefinition<OverlayVertex>, EmptyPipeline,
+                                        CustomRenderPass>;
+
+/// Text in the font of `text` drawn over the scene on a translucent panel at the top-left, such
+/// as the key bindings. The lines that don't fit the height of the image continue in another
+/// column, and the columns that don't fit its width are cut off.
+///
+/// The glyphs are laid out in pixels, so it is created again along with the swapchain to keep
+/// them crisp.
+pub struct TextOverlay {
+    pipeline: Arc<OverlayPipeline>,
+    vertex_buffer: Arc<CpuAccessibleBuffer<[OverlayVertex]>>,
+}
+
+impl TextOverlay {
+    pub fn new(device: &Arc<Device>, queue: &Arc<Queue>, render_pass: &Arc<CustomRenderPass>,
+               dimensions: [u32; 2], text: &str) -> Result<TextOverlay, Error> {
+        let vs = ::graph_vs::Shader::load(device)
+            .map_err(|err| Error::OutOfMemory("the vertex shader module", err))?;
+        let fs = ::graph_fs::Shader::load(device)
+            .map_err(|err| Error::OutOfMemory("the fragment shader module", err))?;
+
+        let pipeline_layout = EmptyPipeline::new(device)
+            .map_err(|err| Error::OutOfMemory("the pipeline layout", err))?;
+
+        let pipeline = GraphicsPipeline::new(device, GraphicsPipelineParams {
+            vertex_input: SingleBufferDefinition::new(),
+            vertex_shader: vs.main_entry_point(),
+            input_assembly: InputAssembly {
+                topology: PrimitiveTopology::TriangleList,
+                primitive_restart_enable: false,
+            },
+            tessellation: None,
+            geometry_shader: None,
+            viewport: ViewportsState::Fixed {
+                data: vec![(
+                    Viewport {
+                        origin: [0.0, 0.0],
+                        depth_range: 0.0 .. 1.0,
+                        dimensions: [dimensions[0] as f32, dimensions[1] as f32],
+                    },
+                    Scissor::irrelevant()
+                )],
+            },
+            raster: Default::default(),
+            multisample: Multisample::disabled(),
+            fragment_shader: fs.main_entry_point(),
+            depth_stencil: DepthStencil::disabled(),
+            // The panel lets the scene show through.
+            blend: Blend::alpha_blending(),
+            layout: &pipeline_layout,
+            render_pass: Subpass::from(render_pass, 0).ok_or_else(Error::no_subpass)?,
+        }).map_err(|err| Error::GraphicsPipeline("the text overlay pipeline", err))?;
+
+        let lines = text.lines().collect::<Vec<_>>();
+        let line_height = GLYPH_SIZE[1] + LINE_SPACING;
+        let rows = ((dimensions[1].saturating_sub(2 * MARGIN) + LINE_SPACING) / line_height)
+            .max(1) as usize;
+        let characters = lines.iter().map(|line| line.chars().count()).max().unwrap_or(0) as u32;
+        let column_width = characters * (GLYPH_SIZE[0] + 1);
+        let columns = (lines.len() + rows - 1) / rows;
+        let panel = [
+            (column_width + MARGIN) * columns as u32 + MARGIN,
+            (line_height * lines.len().min(rows) as u32 + 2 * MARGIN).saturating_sub(LINE_SPACING),
+        ];
+
+        let mut vertices = Vec::new();
+
+        {
+            // Two triangles covering `size` pixels from `corner`, converted to normalized device
+            // coordinates.
+            let mut rectangle = |corner: [u32; 2], size: [u32; 2], color: [f32; 4]| {
+                let to_ndc = |x: u32, y: u32| {
+                    [x as f32 / dimensions[0] as f32 * 2.0 - 1.0,
+                     y as f32 / dimensions[1] as f32 * 2.0 - 1.0]
+                };
+                let (left, top) = (corner[0], corner[1]);
+                let (right, bottom) = (corner[0] + size[0], corner[1] + size[1]);
+
+                for &(x, y) in &[(left, top), (right, top), (left, bottom),
+                                 (left, bottom), (right, top), (right, bottom)] {
+                    vertices.push(OverlayVertex { position: to_ndc(x, y), color: color });
+                }
+            };
+
+            if !lines.is_empty() {
+                rectangle([0, 0], panel, PANEL_COLOR);
+            }
+
+            for (index, line) in lines.iter().enumerate() {
+                let x = MARGIN + (index / rows) as u32 * (column_width + MARGIN);
+                let y = MARGIN + (index % rows) as u32 * line_height;
+
+                // The pixels come column by column from the top, so each run of them down a
+                // column is a single rectangle.
+                let mut run: Option<([u32; 2], u32)> = None;
+
+                text::rasterize(x, y, dimensions[0].saturating_sub(x), line, |x, y| {
+                    match run {
+                        Some((start, length)) if start[0] == x && start[1] + length == y => {
+                            run = Some((start, length + 1));
+                        }
+                        _ => {
+                            if let Some((start, length)) = run {
+                                rectangle(start, [1, length], TEXT_COLOR);
+                            }
+
+                            run = Some(([x, y], 1));
+                        }
+                    }
+                });
+
+                if let Some((start, length)) = run {
+                    rectangle(start, [1, length], TEXT_COLOR);
+                }
+            }
+        }
+
+        let vertex_buffer = CpuAccessibleBuffer::from_iter(device, &BufferUsage::all(),
+                                                           Some(queue.family()),
+                                                           vertices.into_iter())
+            .map_err(|err| Error::OutOfMemory("the vertex buffer", err))?;
+
+        Ok(TextOverlay {
+            pipeline: pipeline,
+            vertex_buffer: vertex_buffer,
+        })
+    }
+
+    /// Draws the text, within a render pass on the swapchain image.
+    pub fn draw(&self, builder: PrimaryCommandBufferBuilderInlineDraw)
+                -> PrimaryCommandBufferBuilderInlineDraw {
+        builder.draw(&self.pipeline, &self.vertex_buffer, &DynamicState::none(), &(), &())
+    }
+}
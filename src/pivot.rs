@@ -0,0 +1,150 @@
+use winit::ElementState;
+use winit::MouseButton;
+
+use config::Config;
+use vector::cross;
+use vector::normalize;
+
+/// The point the camera looks at and orbits around, which can be dragged with the command
+/// modifier and the left button held to focus on something off-center.
+///
+/// The camera moves along with it, so that dragging pans the view without turning it. With
+/// `--camera-damping`, dragging gives it a velocity instead, which carries it as far in total
+/// but eases it in and out.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Pivot {
+    /// How far the pivot and the camera have been moved from where `--cam-look` and `--cam-pos`
+    /// put them.
+    offset: [f32; 3],
+    /// How fast the pivot moves with `--camera-damping`, in units per second.
+    velocity: [f32; 3],
+    cursor: [f32; 2],
+    dragging: bool,
+}
+
+impl Pivot {
+    /// Moves the cursor to `x`, `y` in pixels of the window of `dimensions`, dragging the pivot
+    /// with it if the button is held.
+    ///
+    /// The point under the cursor as far away as the pivot stays under it, unless the
+    /// sensitivities of the mouse say otherwise.
+    pub fn moved(&mut self, config: &Config, dimensions: [u32; 2], x: i32, y: i32) {
+        let cursor = [x as f32, y as f32];
+        let delta = config.mouse_delta([cursor[0] - self.cursor[0], cursor[1] - self.cursor[1]]);
+
+        self.cursor = cursor;
+
+        let forward = match config.camera_direction() {
+            Some(forward) if self.dragging => forward,
+            _ => return,
+        };
+
+        let (position, target) = self.camera(config);
+        let distance = (0..3).map(|i| (target[i] - position[i]).powi(2)).sum::<f32>().sqrt();
+
+        // The field of view of `fs.glsl` is across the diagonal, as is the orthographic size.
+        let diagonal = ((dimensions[0] as f32).powi(2) + (dimensions[1] as f32).powi(2)).sqrt();
+        let scale = if config.ortho {
+            config.ortho_size() / diagonal
+        } else {
+            2.0 * distance * (config.fov.to_radians() / 2.0).tan() / diagonal
+        };
+
+        // The left and up directions of the camera, as in `fs.glsl`.
+        let left = normalize(cross(config.camera_up, forward));
+        let up = cross(forward, left);
+
+        for i in 0..3 {
+            let moved = (left[i] * delta[0] + up[i] * delta[1]) * scale;
+
+            // The integral of the velocity decaying at `rate` is the velocity over `rate`.
+            match config.camera_damping {
+                Some(rate) => self.velocity[i] += moved * rate,
+                None => self.offset[i] += moved,
+            }
+        }
+    }
+
+    /// Moves the pivot along with its velocity for `dt` seconds, slowing it down as
+    /// `--camera-damping` says.
+    ///
+    /// The decay is integrated exactly rather than in steps, so the pivot follows the same path
+    /// at any frame rate and never overshoots, however long a frame takes.
+    pub fn advance(&mut self, config: &Config, dt: f32) {
+        let rate = match config.camera_damping {
+            Some(rate) => rate,
+            None => return,
+        };
+        let decay = (-rate * dt).exp();
+
+        for i in 0..3 {
+            self.offset[i] += self.velocity[i] * (1.0 - decay) / rate;
+            self.velocity[i] *= decay;
+        }
+    }
+
+    pub fn update(&mut self, state: ElementState, button: MouseButton, command: bool) {
+        if button == MouseButton::Left {
+            self.dragging = state == ElementState::Pressed && command;
+        }
+    }
+
+    /// Puts the pivot and the camera back where the configuration has them.
+    pub fn reset(&mut self) {
+        self.offset = [0.0; 3];
+        self.velocity = [0.0; 3];
+    }
+
+    /// The position of the camera and the point it looks at.
+    pub fn camera(&self, config: &Config) -> ([f32; 3], [f32; 3]) {
+        let position = config.camera_position;
+        let target = config.camera_target;
+        let offset = self.offset;
+
+        ([position[0] + offset[0], position[1] + offset[1], position[2] + offset[2]],
+         [target[0] + offset[0], target[1] + offset[1], target[2] + offset[2]])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn damped(rate: f32) -> Config {
+        Config { camera_damping: Some(rate), ..Config::default() }
+    }
+
+    fn moving() -> Pivot {
+        Pivot { velocity: [2.0, -1.0, 0.5], ..Pivot::default() }
+    }
+
+    #[test]
+    fn advance_is_independent_of_the_frame_rate() {
+        let config = damped(3.0);
+        let mut stepped = moving();
+        let mut once = moving();
+
+        stepped.advance(&config, 0.1);
+        stepped.advance(&config, 0.1);
+        once.advance(&config, 0.2);
+
+        for i in 0..3 {
+            assert!((stepped.offset[i] - once.offset[i]).abs() < 1e-6);
+            assert!((stepped.velocity[i] - once.velocity[i]).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn advance_stops_where_the_velocity_carries_the_pivot() {
+        let config = damped(3.0);
+        let mut pivot = moving();
+
+        pivot.advance(&config, 1e30);
+
+        // The velocity over the rate, the whole distance it carries the pivot.
+        for (i, &expected) in [2.0 / 3.0, -1.0 / 3.0, 0.5 / 3.0].iter().enumerate() {
+            assert!((pivot.offset[i] - expected).abs() < 1e-6, "{:?}", pivot.offset);
+            assert_eq!(pivot.velocity[i], 0.0);
+        }
+    }
+}
@@ -0,0 +1,262 @@
+use config::Config;
+use vector::cross;
+use vector::normalize;
+use vector::scale;
+
+/// A canonical view the camera can be turned to, around the point it looks at.
+///
+/// The front view looks along +X, made perpendicular to `--cam-up`, or along +Y if up is along
+/// +X. The others are turned from it, the top and bottom ones keeping the front up on the
+/// screen or down.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Preset {
+    Front,
+    Back,
+    Right,
+    Left,
+    Top,
+    Bottom,
+    Isometric,
+}
+
+impl Preset {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Preset::Front => "front",
+            Preset::Back => "back",
+            Preset::Right => "right",
+            Preset::Left => "left",
+            Preset::Top => "top",
+            Preset::Bottom => "bottom",
+            Preset::Isometric => "isometric",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match *self {
+            Preset::Front => "Turn the camera to the front view",
+            Preset::Back => "Turn the camera to the back view",
+            Preset::Right => "Turn the camera to the right view",
+            Preset::Left => "Turn the camera to the left view",
+            Preset::Top => "Turn the camera to the top view",
+            Preset::Bottom => "Turn the camera to the bottom view",
+            Preset::Isometric => "Turn the camera to the isometric view from the front right",
+        }
+    }
+
+    /// The direction the camera looks along in this view and the one up on the screen, in a
+    /// scene where `up` is up.
+    fn orientation(&self, up: [f32; 3]) -> ([f32; 3], [f32; 3]) {
+        let up = normalize(up);
+        let front = match perpendicular([1.0, 0.0, 0.0], up) {
+            Some(front) => front,
+            None => perpendicular([0.0, 1.0, 0.0], up).expect("up is along +X"),
+        };
+        let right = cross(front, up);
+
+        match *self {
+            Preset::Front => (front, up),
+            Preset::Back => (scale(front, -1.0), up),
+            Preset::Right => (scale(right, -1.0), up),
+            Preset::Left => (right, up),
+            Preset::Top => (scale(up, -1.0), front),
+            Preset::Bottom => (up, scale(front, -1.0)),
+            // From the front, the right and above at once, at the same angle to each.
+            Preset::Isometric => {
+                (normalize([front[0] - right[0] - up[0], front[1] - right[1] - up[1],
+                            front[2] - right[2] - up[2]]), up)
+            }
+        }
+    }
+}
+
+/// Turns the camera to a `Preset` around the point it looks at, keeping its distance, eased
+/// over `--preset-transition`.
+///
+/// The orientations are eased as rotations rather than as directions, so that the camera turns
+/// the short way and up on the screen never ends up along the view, even between opposite
+/// views.
+#[derive(Debug, Clone, Copy)]
+pub struct Presets {
+    /// The time the camera takes to turn to a preset, in seconds.
+    duration: f32,
+    /// The orientation of the camera when the last preset was chosen and that of the preset, as
+    /// unit quaternions turning the camera from looking along +X with +Z up. None until a preset
+    /// is chosen.
+    transition: Option<([f32; 4], [f32; 4])>,
+    /// How far the camera has turned to the preset, from 0 to 1.
+    progress: f32,
+}
+
+impl Presets {
+    pub fn new(config: &Config) -> Presets {
+        Presets {
+            duration: config.preset_transition,
+            transition: None,
+            progress: 0.0,
+        }
+    }
+
+    /// Starts turning the camera to `preset`, from where it is turned now.
+    pub fn select(&mut self, config: &Config, preset: Preset) {
+        let (forward, up) = preset.orientation(config.camera_up);
+        let to = rotation(forward, up);
+        let from = match (self.rotation(), config.camera_direction()) {
+            (Some(rotation), _) => rotation,
+            (None, Some(forward)) => rotation(forward, config.camera_up),
+            // A camera without a defined orientation snaps to the preset.
+            (None, None) => to,
+        };
+
+        self.transition = Some((from, to));
+        self.progress = 0.0;
+    }
+
+    /// Goes back to the camera of the configuration.
+    pub fn reset(&mut self) {
+        self.transition = None;
+    }
+
+    /// Moves the transition on by `dt` seconds.
+    pub fn advance(&mut self, dt: f32) {
+        let step = if self.duration > 0.0 { dt / self.duration } else { 1.0 };
+
+        self.progress = (self.progress + step).min(1.0);
+    }
+
+    fn rotation(&self) -> Option<[f32; 4]> {
+        let t = self.progress;
+
+        // Smoothstep, so that the camera starts and stops turning gently.
+        self.transition.map(|(from, to)| slerp(from, to, t * t * (3.0 - 2.0 * t)))
+    }
+
+    /// The position of the camera as far from `target` as `position` is, turned to the preset,
+    /// and the direction up on the screen. Both are those of the configuration without a preset.
+    pub fn camera(&self, config: &Config, position: [f32; 3], target: [f32; 3])
+                  -> ([f32; 3], [f32; 3]) {
+        let rotation = match self.rotation() {
+            Some(rotation) => rotation,
+            None => return (position, config.camera_up),
+        };
+        let forward = rotate(rotation, [1.0, 0.0, 0.0]);
+        let up = rotate(rotation, [0.0, 0.0, 1.0]);
+        let distance = (0..3).map(|i| (target[i] - position[i]).powi(2)).sum::<f32>().sqrt();
+        let distance = if distance > 0.0 { distance } else { 1.0 };
+
+        ([target[0] - forward[0] * distance, target[1] - forward[1] * distance,
+          target[2] - forward[2] * distance], up)
+    }
+}
+
+/// The unit quaternion turning +X to `forward` and +Z to `up` made perpendicular to it, as
+/// `x, y, z, w`.
+fn rotation(forward: [f32; 3], up: [f32; 3]) -> [f32; 4] {
+    // The columns are the forward, left and up directions, as in `fs.glsl`.
+    let forward = normalize(forward);
+    let left = normalize(cross(up, forward));
+    let up = cross(forward, left);
+    let m = |row: usize, column: usize| [forward, left, up][column][row];
+    let trace = m(0, 0) + m(1, 1) + m(2, 2);
+
+    // Divided by the largest of the components, so that none of them loses precision.
+    if trace > 0.0 {
+        let s = (trace + 1.0).sqrt() * 2.0;
+        [(m(2, 1) - m(1, 2)) / s, (m(0, 2) - m(2, 0)) / s, (m(1, 0) - m(0, 1)) / s, s / 4.0]
+    } else if m(0, 0) > m(1, 1) && m(0, 0) > m(2, 2) {
+        let s = (1.0 + m(0, 0) - m(1, 1) - m(2, 2)).sqrt() * 2.0;
+        [s / 4.0, (m(0, 1) + m(1, 0)) / s, (m(0, 2) + m(2, 0)) / s, (m(2, 1) - m(1, 2)) / s]
+    } else if m(1, 1) > m(2, 2) {
+        let s = (1.0 + m(1, 1) - m(0, 0) - m(2, 2)).sqrt() * 2.0;
+        [(m(0, 1) + m(1, 0)) / s, s / 4.0, (m(1, 2) + m(2, 1)) / s, (m(0, 2) - m(2, 0)) / s]
+    } else {
+        let s = (1.0 + m(2, 2) - m(0, 0) - m(1, 1)).sqrt() * 2.0;
+        [(m(0, 2) + m(2, 0)) / s, (m(1, 2) + m(2, 1)) / s, s / 4.0, (m(1, 0) - m(0, 1)) / s]
+    }
+}
+
+/// `v` turned by the unit quaternion `q`.
+fn rotate(q: [f32; 4], v: [f32; 3]) -> [f32; 3] {
+    let axis = [q[0], q[1], q[2]];
+    let t = scale(cross(axis, v), 2.0);
+    let u = cross(axis, t);
+
+    [v[0] + q[3] * t[0] + u[0], v[1] + q[3] * t[1] + u[1], v[2] + q[3] * t[2] + u[2]]
+}
+
+/// The rotation `t` of the way from `a` to `b`, the short way round.
+fn slerp(a: [f32; 4], b: [f32; 4], t: f32) -> [f32; 4] {
+    let dot = (0..4).map(|i| a[i] * b[i]).sum::<f32>();
+    // `b` and `-b` are the same rotation, the one nearer to `a` turns less.
+    let (b, dot) = if dot < 0.0 { ([-b[0], -b[1], -b[2], -b[3]], -dot) } else { (b, dot) };
+
+    // Nearly the same rotations are interpolated linearly, where the angle is imprecise.
+    let (wa, wb) = if dot > 0.9995 {
+        (1.0 - t, t)
+    } else {
+        let angle = dot.acos();
+        (((1.0 - t) * angle).sin() / angle.sin(), (t * angle).sin() / angle.sin())
+    };
+    let q = [a[0] * wa + b[0] * wb, a[1] * wa + b[1] * wb, a[2] * wa + b[2] * wb,
+             a[3] * wa + b[3] * wb];
+    let length = (0..4).map(|i| q[i] * q[i]).sum::<f32>().sqrt();
+
+    [q[0] / length, q[1] / length, q[2] / length, q[3] / length]
+}
+
+/// The part of `v` perpendicular to the unit vector `axis`, normalized, unless `v` is nearly
+/// along it.
+fn perpendicular(v: [f32; 3], axis: [f32; 3]) -> Option<[f32; 3]> {
+    let along = v[0] * axis[0] + v[1] * axis[1] + v[2] * axis[2];
+    let rest = [v[0] - axis[0] * along, v[1] - axis[1] * along, v[2] - axis[2] * along];
+
+    if rest.iter().map(|c| c * c).sum::<f32>() > 1e-6 {
+        Some(normalize(rest))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PRESETS: [Preset; 7] = [Preset::Front, Preset::Back, Preset::Right, Preset::Left,
+                                  Preset::Top, Preset::Bottom, Preset::Isometric];
+
+    fn assert_close(a: [f32; 3], b: [f32; 3]) {
+        assert!((0..3).all(|i| (a[i] - b[i]).abs() < 1e-4), "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn rotations_turn_the_axes_to_the_orientation() {
+        for &scene_up in &[[0.0, 0.0, 1.0], [0.0, 1.0, 0.0], [1.0, 0.0, 0.0], [1.0, 2.0, 3.0]] {
+            for preset in &PRESETS {
+                let (forward, up) = preset.orientation(scene_up);
+                let q = rotation(forward, up);
+
+                assert_close(rotate(q, [1.0, 0.0, 0.0]), forward);
+                // Up on the screen is made perpendicular to the view, as the isometric one isn't.
+                assert_close(rotate(q, [0.0, 0.0, 1.0]), perpendicular(up, forward).unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn slerp_goes_between_the_endpoints_the_short_way() {
+        let identity = [0.0, 0.0, 0.0, 1.0];
+        let half = 0.5f32.sqrt();
+        // A quarter turn around +Z, negated, which is the same rotation.
+        let quarter = [0.0, 0.0, -half, -half];
+        let x = [1.0, 0.0, 0.0];
+
+        assert_close(rotate(slerp(identity, quarter, 0.0), x), x);
+        assert_close(rotate(slerp(identity, quarter, 1.0), x), [0.0, 1.0, 0.0]);
+        // Halfway is an eighth of a turn, not the three eighths the long way.
+        assert_close(rotate(slerp(identity, quarter, 0.5), x), [half, half, 0.0]);
+
+        let same = slerp(quarter, quarter, 0.5);
+
+        assert_close(rotate(same, x), [0.0, 1.0, 0.0]);
+    }
+}
@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use vulkano::device::Queue;
+use vulkano::instance::PhysicalDevice;
+use vulkano::instance::QueueFamily;
+use vulkano::swapchain::Surface;
+use vulkano::sync::SharingMode;
+
+/// Chooses the queue families of devices to render to a surface with.
+///
+/// Whether a queue family can present to the surface is only queried once per family, as the
+/// same devices may be considered repeatedly.
+pub struct QueueSelector {
+    surface: Arc<Surface>,
+    // Indexed by the index of the physical device and the ID of the queue family.
+    present_support: HashMap<(usize, u32), bool>,
+}
+
+impl QueueSelector {
+    pub fn new(surface: &Arc<Surface>) -> QueueSelector {
+        QueueSelector {
+            surface: surface.clone(),
+            present_support: HashMap::new(),
+        }
+    }
+
+    /// Whether `family` can present images to the surface.
+    pub fn supports_present(&mut self, family: &QueueFamily) -> bool {
+        let surface = &self.surface;
+
+        *self.present_support
+            .entry((family.physical_device().index(), family.id()))
+            .or_insert_with(|| surface.is_supported(family).unwrap_or(false))
+    }
+
+    /// Chooses the first queue family of `physical` that supports both drawing and presenting to
+    /// the surface.
+    ///
+    /// Some devices have none, only separate families for each, in which case the first of
+    /// either is used, with a queue of each.
+    pub fn select<'a>(&mut self, physical: &PhysicalDevice<'a>)
+                      -> Result<QueueFamilies<'a>, String> {
+        for family in physical.queue_families() {
+            if family.supports_graphics() && self.supports_present(&family) {
+                return Ok(QueueFamilies {
+                    graphics: family,
+                    present: family,
+                });
+            }
+        }
+
+        let graphics = physical.queue_families()
+            .filter(|family| family.supports_graphics())
+            .collect::<Vec<_>>();
+        let present = physical.queue_families()
+            .filter(|family| self.supports_present(family))
+            .collect::<Vec<_>>();
+
+        match (graphics.first(), present.first()) {
+            (Some(&graphics), Some(&present)) => {
+                info!("no queue family of `{}` supports both graphics and presenting to the \
+                       window, drawing on family {} and presenting on family {}",
+                      physical.name(), graphics.id(), present.id());
+
+                Ok(QueueFamilies {
+                    graphics: graphics,
+                    present: present,
+                })
+            }
+            _ => {
+                Err(format!("the device `{}` has no queue family that supports graphics or none \
+                             that supports presenting to the window (graphics: {:?}, \
+                             present: {:?})", physical.name(),
+                            graphics.iter().map(|family| family.id()).collect::<Vec<_>>(),
+                            present.iter().map(|family| family.id()).collect::<Vec<_>>()))
+            }
+        }
+    }
+}
+
+/// The queue families to draw with and to present with, the same one unless the device has no
+/// family that can do both.
+#[derive(Debug, Clone, Copy)]
+pub struct QueueFamilies<'a> {
+    pub graphics: QueueFamily<'a>,
+    pub present: QueueFamily<'a>,
+}
+
+impl<'a> QueueFamilies<'a> {
+    /// Whether presenting needs a queue of its own.
+    pub fn separate(&self) -> bool {
+        self.graphics.id() != self.present.id()
+    }
+}
+
+/// How images used by all of `queues` are shared between their families: exclusively when they
+/// are all from the same one, which is the fastest, and concurrently otherwise, so that no
+/// ownership transfers are needed, for example to copy a swapchain image on a transfer queue.
+///
+/// Concurrent sharing has to list distinct families, at least two of them.
+pub fn sharing_mode(queues: &[&Arc<Queue>]) -> SharingMode {
+    let mut families = queues.iter().map(|queue| queue.family().id()).collect::<Vec<_>>();
+
+    families.sort();
+    families.dedup();
+
+    if families.len() == 1 {
+        SharingMode::Exclusive(families[0])
+    } else {
+        SharingMode::Concurrent(families)
+    }
+}
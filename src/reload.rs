@@ -0,0 +1,80 @@
+use std::fs;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+use std::time::Instant;
+use std::time::SystemTime;
+
+/// How often the watched files are checked for modifications.
+const POLL_INTERVAL_MS: u64 = 250;
+
+/// Watches a file, such as the source of the fragment shader, so that what is made from it can be
+/// made again when it changes.
+pub struct FileWatcher {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    last_poll: Instant,
+}
+
+impl FileWatcher {
+    /// Watches `path`, which counts as modified once when it appears if it doesn't exist yet.
+    pub fn new(path: PathBuf) -> FileWatcher {
+        FileWatcher {
+            modified: modified(&path),
+            path: path,
+            last_poll: Instant::now(),
+        }
+    }
+
+    /// Watches `fs.glsl` in `shader_dir`, or returns `None` with a warning if it is missing.
+    pub fn shader(shader_dir: &Path) -> Option<FileWatcher> {
+        if !shader_dir.is_dir() {
+            warn!("the shader directory `{}` doesn't exist, hot-reload is disabled",
+                  shader_dir.display());
+            return None;
+        }
+
+        let path = shader_dir.join("fs.glsl");
+
+        if !path.is_file() {
+            warn!("`{}` doesn't exist, hot-reload is disabled", path.display());
+            return None;
+        }
+
+        Some(FileWatcher::new(path))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the new contents of the file if it has been modified since the last call.
+    pub fn poll(&mut self) -> Option<Result<String, String>> {
+        if self.last_poll.elapsed() < Duration::from_millis(POLL_INTERVAL_MS) {
+            return None;
+        }
+
+        self.last_poll = Instant::now();
+
+        let modified = modified(&self.path);
+
+        if modified == self.modified {
+            return None;
+        }
+
+        self.modified = modified;
+
+        let mut source = String::new();
+
+        Some(File::open(&self.path)
+            .and_then(|mut file| file.read_to_string(&mut source))
+            .map(|_| source)
+            .map_err(|err| format!("failed to read `{}`: {}", self.path.display(), err)))
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
@@ -0,0 +1,1642 @@
+use std::cmp;
+use std::collections::VecDeque;
+use std::mem;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use image;
+
+use vulkano::buffer::Buffer;
+use vulkano::buffer::BufferUsage;
+use vulkano::buffer::CpuAccessibleBuffer;
+use vulkano::command_buffer;
+use vulkano::command_buffer::DynamicState;
+use vulkano::command_buffer::PrimaryCommandBufferBuilder;
+use vulkano::command_buffer::PrimaryCommandBufferBuilderInlineDraw;
+use vulkano::command_buffer::Submission;
+use vulkano::descriptor::descriptor_set::DescriptorPool;
+use vulkano::device::Device;
+use vulkano::device::Queue;
+use vulkano::format::Format;
+use vulkano::framebuffer::Framebuffer;
+use vulkano::image::ImageUsage;
+use vulkano::image::SwapchainImage;
+use vulkano::pipeline::ComputePipeline;
+use vulkano::swapchain::AcquireError;
+use vulkano::swapchain::Capabilities;
+use vulkano::swapchain::PresentError;
+use vulkano::swapchain::PresentMode;
+use vulkano::swapchain::SupportedPresentModes;
+use vulkano::swapchain::Surface;
+use vulkano::swapchain::SurfaceTransform;
+use vulkano::swapchain::Swapchain;
+
+use accumulate::Accumulator;
+use background::Background;
+use simulation::Time;
+use config;
+use config::BackgroundKind;
+use config::Config;
+use exposure::AutoExposure;
+use exposure::Metering;
+use geometry::Geometry;
+use graph::FrameGraph;
+use graph::FrameTimes;
+use crosshair::Crosshair;
+use error::Error;
+use input::Mouse;
+use letterbox::LetterboxBars;
+use memory::MemoryUsage;
+use mesh::Mesh;
+use mesh::MeshPass;
+use mesh::MeshVertex;
+use mirror::Mirror;
+use mrt;
+use mrt::ATTACHMENTS;
+use mrt::RenderTargets;
+use overlay::TextOverlay;
+use queue;
+use render_pass::CustomRenderPass;
+use shader::FragmentShader;
+use shader::VertexShader;
+use state::State;
+use stream::Stream;
+use text::Canvas;
+use upscale;
+use upscale::Upscaler;
+use Pipeline;
+use Vertex;
+
+/// Renders the scene into the swapchain of a window.
+///
+/// Everything that depends on the swapchain is kept here, so that it can be recreated along with
+/// the swapchain when the window changes.
+pub struct Renderer {
+    device: Arc<Device>,
+    queue: Arc<Queue>,
+    /// The queue the swapchain images are presented with, `queue` itself unless its family
+    /// can't present to the surface.
+    present_queue: Arc<Queue>,
+    surface: Arc<Surface>,
+    config: Config,
+    refresh_hz: f32,
+    time: Time,
+    /// The number of frames advanced to so far.
+    frame: u32,
+    /// The mouse over the window, passed to the shaders as `iMouse`.
+    mouse: Mouse,
+    auto_exposure: AutoExposure,
+    /// Measures the scene for auto-exposure and the ambient clear, if enabled.
+    metering: Option<Metering>,
+    /// The mean color of a recent frame the scene is cleared to, with `--ambient-clear`.
+    ambient: Option<[f32; 4]>,
+    vs: VertexShader,
+    fs: FragmentShader,
+    vertex_buffer: Arc<CpuAccessibleBuffer<[Vertex]>>,
+    /// What the scene is drawn with instead of `vertex_buffer`, once there is geometry.
+    geometry: Option<Geometry>,
+    /// The mesh of `--mesh`, drawn over the scene by `mesh_pass`.
+    mesh: Option<Geometry<MeshVertex>>,
+    mesh_pass: Option<MeshPass>,
+    uniform_buffer: Arc<CpuAccessibleBuffer<::vs::ty::Data>>,
+    pipeline_layout: Arc<::pipeline_layout::CustomPipeline>,
+    descriptor_pool: Arc<DescriptorPool>,
+    state: State,
+    /// The descriptor sets for both parities of the frames, see `State::sets`.
+    sets: [Arc<::pipeline_layout::set0::Set>; 2],
+    render_pass: Arc<CustomRenderPass>,
+    compute_pipeline: Option<Arc<ComputePipeline<::compute_layout::CustomPipeline>>>,
+    compute_layout: Arc<::compute_layout::CustomPipeline>,
+    /// Whether the swapchain images can be copied from, for screenshots and the stream.
+    capturable: bool,
+
+    /// The dimensions of the window, used if the surface doesn't determine the dimensions of the
+    /// swapchain.
+    window_dimensions: [u32; 2],
+    /// Set when the swapchain should be recreated before the next frame.
+    recreate_swapchain: bool,
+    /// Set when the surface is lost, nothing is rendered until it is replaced.
+    surface_lost: bool,
+
+    swapchain: Arc<Swapchain>,
+    present_mode: PresentMode,
+    images: Vec<Arc<SwapchainImage>>,
+    framebuffers: Vec<Arc<Framebuffer<CustomRenderPass>>>,
+    upscaler: Option<Upscaler>,
+    render_targets: Option<RenderTargets>,
+    accumulator: Option<Accumulator>,
+    /// The index of the render target shown, in `mrt::ATTACHMENTS`.
+    attachment: usize,
+    background: Option<Background>,
+    pipeline: Arc<Pipeline>,
+    compute_sets: Vec<Arc<::compute_layout::set0::Set>>,
+    frame_graph: Option<FrameGraph>,
+    letterbox_bars: Option<LetterboxBars>,
+    /// The height of each letterbox bar as a fraction of the height of the image.
+    letterbox: f32,
+    crosshair: Option<Crosshair>,
+    show_crosshair: bool,
+    /// The text shown over the scene, such as the key bindings, and its overlay.
+    text: Option<String>,
+    text_overlay: Option<TextOverlay>,
+    /// The preview window of `--preview-window`, showing the offscreen image of `upscaler`.
+    mirror: Option<Mirror>,
+    /// The stream of `--stream-port` the frames are sent to, and whether the swapchain images are
+    /// in BGRA.
+    stream: Option<(Stream, bool)>,
+    /// The frames copied for the stream that weren't read back yet, oldest first with their
+    /// dimensions, so that the GPU is never waited for.
+    readbacks: VecDeque<([u32; 2], Arc<CpuAccessibleBuffer<[[u8; 4]]>>)>,
+
+    // Submitting a command produces a `Submission` object which holds the resources for as long
+    // as they are in use by the GPU.
+    //
+    // Destroying a `Submission` blocks until the GPU is finished executing it. In order to avoid
+    // that, we store them in a `Vec` and clean them from time to time.
+    submissions: Vec<Arc<Submission>>,
+    /// How many frames may be submitted without the GPU having finished them, see
+    /// `--max-frames-in-flight`.
+    max_frames_in_flight: usize,
+}
+
+impl Renderer {
+    pub fn new(device: &Arc<Device>, queue: &Arc<Queue>, present_queue: &Arc<Queue>,
+               surface: &Arc<Surface>, config: &Config, refresh_hz: f32, vs: VertexShader,
+               fs: FragmentShader)
+               -> Result<Renderer, Error> {
+        let physical = device.physical_device();
+
+        // Before we can draw on the surface, we have to create what is called a swapchain.
+        // Creating a swapchain allocates the color buffers that will contain the image that will
+        // ultimately be visible on the screen. These images are returned alongside with the
+        // swapchain.
+        let (swapchain, images, present_mode, compute_present, capturable) = {
+            // Querying the capabilities of the surface. When we create the swapchain we can only
+            // pass values that are allowed by the capabilities.
+            let caps = surface.get_capabilities(&physical).map_err(|err| {
+                Error::Swapchain(format!("failed to get the capabilities of the surface: {}", err))
+            })?;
+
+            // We choose the dimensions of the swapchain to match the current dimensions of the
+            // window. If `caps.current_extent` is `None`, this means that the window size will be
+            // determined by the dimensions of the swapchain, in which case we just use a default
+            // value.
+            let dimensions = caps.current_extent.unwrap_or([config.width, config.height]);
+
+            // The present mode determines the way the images will be presented on the screen.
+            // This includes things such as vsync and will affect the framerate of your
+            // application. We just use the first supported value, unless `--present-mode` asks
+            // for another one, or `--low-latency` for one that doesn't make a finished frame wait
+            // for the next vertical blank.
+            let first = caps.present_modes.iter().next()
+                .ok_or_else(|| Error::Swapchain("the surface has no present mode".to_string()))?;
+            let requested = config.present_mode.and_then(|mode| {
+                if caps.present_modes.supports(mode) {
+                    Some(mode)
+                } else {
+                    warn!("the surface doesn't support the {:?} present mode", mode);
+                    None
+                }
+            });
+            let present = if let Some(mode) = requested {
+                mode
+            } else if config.low_latency {
+                let mode = low_latency_present_mode(&caps.present_modes);
+
+                match mode {
+                    Some(mode) => info!("presenting with {:?} for a low latency", mode),
+                    None => warn!("the surface supports neither the mailbox nor the relaxed FIFO \
+                                   present mode, presenting with {:?}", first),
+                }
+
+                mode.unwrap_or(first)
+            } else {
+                first
+            };
+
+            // The alpha mode indicates how the alpha value of the final image will behave. For
+            // example you can choose whether the window will be opaque or transparent.
+            let alpha = caps.supported_composite_alpha.iter().next().ok_or_else(|| {
+                Error::Swapchain("the surface supports no composite alpha mode".to_string())
+            })?;
+
+            // Choosing the internal format that the images will have.
+            let format = caps.supported_formats[0].0;
+
+            // A compute shader can only write into the swapchain images if they can be used as
+            // storage images. As their format isn't known in advance, the shader declares the
+            // image without a format qualifier, which requires an additional device feature.
+            let compute_present = config.compute_present && {
+                let supported = caps.supported_usage_flags.storage &&
+                                physical.supported_features()
+                                        .shader_storage_image_write_without_format;
+
+                if !supported {
+                    warn!("storage swapchain images are not supported, \
+                           falling back to the graphics pipeline");
+                }
+
+                supported
+            };
+
+            // Screenshots and the frames of the stream are copied out of the swapchain images, so
+            // that they match exactly what is shown in the window.
+            let capturable = (config.screenshot_on_start.is_some() ||
+                              config.stream_port.is_some()) &&
+                             caps.supported_usage_flags.transfer_source;
+
+            let usage = ImageUsage {
+                color_attachment: true,
+                storage: compute_present,
+                transfer_source: capturable,
+                .. ImageUsage::none()
+            };
+
+            // Every queue that touches the swapchain images has to be listed here, the one that
+            // draws into them and the one that presents them.
+            let sharing = queue::sharing_mode(&[queue, present_queue]);
+            info!("swapchain sharing mode: {:?}", sharing);
+
+            // Please take a look at the docs for the meaning of the parameters we didn't mention.
+            // Clipping lets the driver skip the pixels covered by other windows, which leaves
+            // them undefined in screenshots, so `--no-clip` turns it off.
+            let (swapchain, images) = Swapchain::new(device, surface, 2, format, dimensions, 1,
+                                                     &usage, sharing, SurfaceTransform::Identity,
+                                                     alpha, present, config.clipped, None)
+                .map_err(|err| {
+                    Error::Swapchain(format!("failed to create the swapchain: {}", err))
+                })?;
+
+            (swapchain, images, present, compute_present, capturable)
+        };
+
+        let vertex_buffer = ::create_vertex_buffer(device, queue, config.coverage)?;
+
+        // The macro of the render pass only created the custom struct that represents our
+        // render pass. We also have to actually instanciate that struct.
+        //
+        // To do so, we have to pass the actual values of the formats of the attachments.
+        let render_pass = CustomRenderPass::new(device, &::render_pass::Formats {
+            // Use the format of the images and one sample.
+            color: (images[0].format(), 1)
+        }).map_err(|err| Error::Pipeline(format!("failed to create the render pass: {}", err)))?;
+
+        let pipeline_layout = ::pipeline_layout::CustomPipeline::new(device).map_err(|err| {
+            Error::Pipeline(format!("failed to create the pipeline layout: {}", err))
+        })?;
+
+        let descriptor_pool = DescriptorPool::new(device);
+
+        let time = Time::fixed(config.fixed_time.unwrap_or(0.0));
+        let uniform_buffer = ::create_uniform_buffer(device, queue, config,
+                                                     images[0].dimensions(), refresh_hz, time,
+                                                     config.exposure)?;
+
+        let state = State::new(device, queue, config.state_size)?;
+        let sets = state.sets(&descriptor_pool, &pipeline_layout, &uniform_buffer);
+
+        // The compute path replaces the graphics pipeline with a compute shader that writes
+        // every pixel of the swapchain image itself.
+        let compute_layout = ::compute_layout::CustomPipeline::new(device).map_err(|err| {
+            Error::Pipeline(format!("failed to create the compute pipeline layout: {}", err))
+        })?;
+        let compute_pipeline = if compute_present {
+            let cs = ::cs::Shader::load(device).map_err(|err| {
+                Error::Shader(format!("failed to create the compute shader module: {}", err))
+            })?;
+
+            Some(ComputePipeline::new(device, &compute_layout, &cs.main_entry_point(), &())
+                .map_err(|err| {
+                    Error::Pipeline(format!("failed to create the compute pipeline: {}", err))
+                })?)
+        } else {
+            None
+        };
+
+        if config.pixel_scale > 1 && compute_present {
+            warn!("the pixel scale is not supported by the compute path");
+        }
+
+        if config.internal_res.is_some() && compute_present {
+            warn!("an internal resolution is not supported by the compute path");
+        }
+
+        if config.background != BackgroundKind::Solid && compute_present {
+            warn!("gradient backgrounds are not supported by the compute path");
+        }
+
+        if config.flip_y && compute_present {
+            warn!("flipping the y axis is not supported by the compute path");
+        }
+
+        if config.motion_blur.is_some() {
+            warn!("motion blur is only applied to the frames rendered without a window");
+        }
+
+        // There is no text overlay on the GPU yet, only the CPU pixels of captures are written on.
+        if config.timecode && config.screenshot_on_start.is_none() {
+            warn!("the timecode is only written into frames saved by `--headless` and \
+                   `--screenshot-on-start`");
+        }
+
+        if config.mrt && compute_present {
+            warn!("multiple render targets are not supported by the compute path");
+        } else if config.mrt && config.background != BackgroundKind::Solid {
+            warn!("gradient backgrounds are not supported with multiple render targets");
+        }
+
+        // The compute shader is the built-in one, compiled into the executable.
+        if !config.defines.is_empty() && compute_present {
+            warn!("`--define` is not supported by the compute path");
+        }
+
+        if config.state_size > 1 && compute_present {
+            warn!("the state buffers are not supported by the compute path");
+        }
+
+        if config.accumulate.is_some() && compute_present {
+            warn!("accumulation is not supported by the compute path");
+        } else if config.accumulate.is_some() && config.mrt {
+            warn!("accumulation is not supported with multiple render targets");
+        }
+
+        let pipeline = ::create_pipeline(device, config, &vs, &fs, &pipeline_layout,
+                                         &render_pass, images[0].dimensions())?;
+
+        let metering = if !config.auto_exposure && !config.ambient_clear {
+            None
+        } else if compute_present {
+            warn!("auto-exposure and the ambient clear are not supported by the compute path");
+            None
+        } else {
+            Some(Metering::new(device, queue, config, &vs, &fs, &pipeline_layout,
+                               &descriptor_pool, &state)?)
+        };
+
+        let mut renderer = Renderer {
+            device: device.clone(),
+            queue: queue.clone(),
+            present_queue: present_queue.clone(),
+            surface: surface.clone(),
+            config: config.clone(),
+            refresh_hz: refresh_hz,
+            time: time,
+            frame: 0,
+            mouse: Mouse::default(),
+            auto_exposure: AutoExposure::new(config),
+            metering: metering,
+            ambient: None,
+            vs: vs,
+            fs: fs,
+            vertex_buffer: vertex_buffer,
+            geometry: None,
+            mesh: None,
+            mesh_pass: None,
+            uniform_buffer: uniform_buffer,
+            pipeline_layout: pipeline_layout,
+            descriptor_pool: descriptor_pool,
+            state: state,
+            sets: sets,
+            render_pass: render_pass,
+            compute_pipeline: compute_pipeline,
+            compute_layout: compute_layout,
+            capturable: capturable,
+            window_dimensions: [config.width, config.height],
+            recreate_swapchain: false,
+            surface_lost: false,
+            swapchain: swapchain,
+            present_mode: present_mode,
+            images: images,
+            framebuffers: Vec::new(),
+            upscaler: None,
+            render_targets: None,
+            accumulator: None,
+            attachment: 0,
+            background: None,
+            pipeline: pipeline,
+            compute_sets: Vec::new(),
+            frame_graph: None,
+            letterbox_bars: None,
+            letterbox: 0.0,
+            crosshair: None,
+            show_crosshair: config.crosshair,
+            text: None,
+            text_overlay: None,
+            mirror: None,
+            stream: None,
+            readbacks: VecDeque::new(),
+            submissions: Vec::new(),
+            max_frames_in_flight: config.max_frames_in_flight,
+        };
+
+        renderer.create_swapchain_resources()?;
+        Ok(renderer)
+    }
+
+    /// (Re)creates everything that depends on the swapchain images.
+    fn create_swapchain_resources(&mut self) -> Result<(), Error> {
+        let dimensions = self.images[0].dimensions();
+
+        // Each frame in flight holds on to a swapchain image, so there can't be more of them.
+        let max_frames_in_flight = self.config.max_frames_in_flight.min(self.images.len());
+
+        if max_frames_in_flight < self.config.max_frames_in_flight &&
+           max_frames_in_flight != self.max_frames_in_flight {
+            warn!("the swapchain has {} images, limiting the frames in flight to {}",
+                  self.images.len(), max_frames_in_flight);
+        }
+
+        self.max_frames_in_flight = max_frames_in_flight;
+
+        // The render pass only describes the layout of our framebuffers. Before we can draw we
+        // also need to create the actual framebuffers.
+        //
+        // Since we need to draw to multiple images, we are going to create a different
+        // framebuffer for each image.
+        self.framebuffers = self.images.iter().map(|image| {
+            let dimensions = [image.dimensions()[0], image.dimensions()[1], 1];
+            Framebuffer::new(&self.render_pass, dimensions, ::render_pass::AList {
+                // The `AList` struct was generated by the render pass macro, and contains one
+                // member for each attachment.
+                color: image
+            }).map_err(|err| Error::Framebuffer("the framebuffer of a swapchain image", err))
+        }).collect::<Result<Vec<_>, _>>()?;
+
+        // With a pixel scale or an internal resolution, the scene is rendered into an offscreen
+        // image at that resolution, which is then scaled into the swapchain image.
+        //
+        // With multiple render targets or accumulation, the scene is always rendered offscreen
+        // and the result is copied into the swapchain image the same way.
+        //
+        // With a preview window, the scene is rendered offscreen once and copied into both.
+        let graphics = self.compute_pipeline.is_none();
+        let accumulate = self.config.accumulate.is_some() && graphics && !self.config.mrt;
+
+        let scaled = self.config.pixel_scale > 1 || self.config.internal_res.is_some() ||
+                     self.config.preview_window.is_some();
+
+        self.upscaler = if scaled && graphics && !self.config.mrt && !accumulate {
+            Some(Upscaler::new(&self.device, &self.queue, &self.descriptor_pool,
+                               &self.render_pass, dimensions, &self.config)?)
+        } else {
+            None
+        };
+
+        let source = match (self.mirror.as_mut(), self.upscaler.as_ref()) {
+            (Some(mirror), Some(upscaler)) => {
+                mirror.set_source(upscaler.image(), upscaler.dimensions())
+            }
+            _ => Ok(()),
+        };
+
+        if let Err(message) = source {
+            warn!("{}, no longer showing the second window", message);
+            self.mirror = None;
+        }
+
+        self.render_targets = if self.config.mrt && graphics {
+            Some(RenderTargets::new(&self.device, &self.queue, &self.config, &self.vs, &self.fs,
+                                    &self.pipeline_layout, &self.descriptor_pool,
+                                    &self.render_pass, dimensions)?)
+        } else {
+            None
+        };
+
+        self.accumulator = if accumulate {
+            Some(Accumulator::new(&self.device, &self.queue, &self.descriptor_pool,
+                                  &self.render_pass, dimensions, &self.config)?)
+        } else {
+            None
+        };
+
+        let scene_dimensions = self.scene_dimensions();
+
+        self.background = if graphics && self.render_targets.is_none() {
+            Background::new(&self.device, &self.queue, &self.descriptor_pool,
+                            self.scene_render_pass(), scene_dimensions, &self.config)?
+        } else {
+            None
+        };
+
+        self.write_uniforms();
+
+        self.pipeline = ::create_pipeline(&self.device, &self.config, &self.vs, &self.fs,
+                                          &self.pipeline_layout, self.scene_render_pass(),
+                                          scene_dimensions)?;
+
+        // Each swapchain image needs its own descriptor set for the compute path.
+        self.compute_sets = if self.compute_pipeline.is_some() {
+            self.images.iter().map(|image| {
+                ::compute_layout::set0::Set::new(
+                    &self.descriptor_pool,
+                    &self.compute_layout,
+                    &::compute_layout::set0::Descriptors {
+                        uniforms: &self.uniform_buffer,
+                        target: image
+                    }
+                )
+            }).collect()
+        } else {
+            Vec::new()
+        };
+
+        self.mesh_pass = self.create_mesh_pass()?;
+
+        // The frame time graph, the letterbox bars and the crosshair are drawn over the scene,
+        // which the compute path doesn't do.
+        self.frame_graph = if self.compute_pipeline.is_none() {
+            Some(FrameGraph::new(&self.device, &self.queue, &self.render_pass, dimensions,
+                                 self.images.len())?)
+        } else {
+            None
+        };
+
+        self.letterbox_bars = if self.compute_pipeline.is_none() {
+            Some(LetterboxBars::new(&self.device, &self.queue, &self.render_pass, dimensions,
+                                    self.images.len())?)
+        } else {
+            None
+        };
+
+        self.crosshair = if self.compute_pipeline.is_none() {
+            Some(Crosshair::new(&self.device, &self.queue, &self.render_pass, dimensions,
+                                &self.config)?)
+        } else {
+            None
+        };
+
+        self.text_overlay = self.create_text_overlay()?;
+
+        // Printed again after every resize, to show how the footprint scales.
+        if self.config.mem_info {
+            self.memory_usage().print();
+        }
+
+        Ok(())
+    }
+
+    /// The major allocations of the renderer with the current swapchain.
+    pub fn memory_usage(&self) -> MemoryUsage {
+        let mut usage = MemoryUsage::new();
+
+        usage.image("swapchain images", self.images.len(), self.images[0].dimensions(),
+                    self.images[0].format());
+
+        if let Some(ref upscaler) = self.upscaler {
+            upscaler.memory_usage(&mut usage);
+        }
+
+        if let Some(ref render_targets) = self.render_targets {
+            render_targets.memory_usage(&mut usage);
+        }
+
+        if let Some(ref accumulator) = self.accumulator {
+            accumulator.memory_usage(&mut usage);
+        }
+
+        if let Some(ref metering) = self.metering {
+            metering.memory_usage(&mut usage);
+        }
+
+        usage.buffer("uniform buffer", 1, mem::size_of::<::vs::ty::Data>());
+        usage.buffer("vertex buffer", 1, self.vertex_buffer.size());
+
+        if let Some(ref geometry) = self.geometry {
+            geometry.memory_usage(&mut usage);
+        }
+
+        if let Some(ref mesh) = self.mesh {
+            mesh.memory_usage(&mut usage);
+        }
+
+        if let Some(ref mesh_pass) = self.mesh_pass {
+            mesh_pass.memory_usage(&mut usage);
+        }
+
+        self.state.memory_usage(&mut usage);
+
+        usage
+    }
+
+    /// Moves on to a frame at `time`.
+    fn advance(&mut self, time: Time) {
+        // Samples are only averaged while the time doesn't advance.
+        if !time.same_animation(&self.time) {
+            self.reset_accumulation();
+        }
+
+        self.time = time;
+        self.frame = self.frame.wrapping_add(1);
+        self.state.swap();
+
+        let measurement = match self.metering {
+            Some(ref mut metering) => metering.next_frame(&self.config, self.refresh_hz, time),
+            None => None,
+        };
+
+        if let Some(measurement) = measurement {
+            if self.config.auto_exposure {
+                self.auto_exposure.adapt(measurement.luminance);
+            }
+
+            // Measured at an exposure of 1, so scaled like the scene.
+            if self.config.ambient_clear {
+                let exposure = self.auto_exposure.exposure();
+                let color = measurement.color;
+
+                self.ambient = Some([color[0] * exposure, color[1] * exposure,
+                                     color[2] * exposure, 1.0]);
+            }
+        }
+
+        self.write_uniforms();
+    }
+
+    fn write_uniforms(&self) {
+        let mut data =
+            ::simulation::uniforms(&self.config, self.scene_dimensions(), self.refresh_hz,
+                                   self.time, self.auto_exposure.exposure(), self.frame,
+                                   self.accumulator.as_ref().map_or([0.0, 0.0], |a| a.jitter()));
+
+        data.mouse = self.mouse_uniform();
+        *self.uniform_buffer.write(Duration::new(1, 0)).unwrap() = data;
+    }
+
+    /// The mouse in pixels of the scene, with the origin where `frag_coord()` in `fs.glsl` has
+    /// it.
+    fn mouse_uniform(&self) -> [f32; 4] {
+        let target_dimensions = self.images[0].dimensions();
+        let scene_dimensions = self.scene_dimensions();
+        let flip_y = self.config.flip_y;
+
+        self.mouse.shadertoy(|position| {
+            let position = upscale::to_scene(&self.config, target_dimensions, scene_dimensions,
+                                             position);
+
+            if flip_y {
+                [position[0], scene_dimensions[1] as f32 - position[1]]
+            } else {
+                position
+            }
+        })
+    }
+
+    /// The dimensions of the swapchain images.
+    pub fn dimensions(&self) -> [u32; 2] {
+        self.images[0].dimensions()
+    }
+
+    /// How the swapchain images are presented, which it was created with.
+    pub fn present_mode(&self) -> PresentMode {
+        self.present_mode
+    }
+
+    /// Whether the frames are rendered by the compute shader of `--compute-present`, which is
+    /// only the case if the surface supports it.
+    pub fn compute_path(&self) -> bool {
+        self.compute_pipeline.is_some()
+    }
+
+    /// The dimensions the scene is rendered at.
+    fn scene_dimensions(&self) -> [u32; 2] {
+        if let Some(ref upscaler) = self.upscaler {
+            upscaler.dimensions()
+        } else if let Some(ref render_targets) = self.render_targets {
+            render_targets.dimensions()
+        } else if let Some(ref accumulator) = self.accumulator {
+            accumulator.dimensions()
+        } else {
+            self.images[0].dimensions()
+        }
+    }
+
+    /// The render pass the scene is drawn with, without multiple render targets.
+    fn scene_render_pass(&self) -> &Arc<CustomRenderPass> {
+        if let Some(ref upscaler) = self.upscaler {
+            upscaler.render_pass()
+        } else if let Some(ref accumulator) = self.accumulator {
+            accumulator.render_pass()
+        } else {
+            &self.render_pass
+        }
+    }
+
+    /// The number of samples averaged into the frame shown, with accumulation.
+    pub fn accumulated_samples(&self) -> Option<u32> {
+        self.accumulator.as_ref().map(|accumulator| accumulator.samples())
+    }
+
+    /// Starts the accumulation over, after something the scene depends on has changed.
+    pub fn reset_accumulation(&mut self) {
+        if let Some(ref mut accumulator) = self.accumulator {
+            accumulator.reset();
+        }
+    }
+
+    /// Draws the scene with `geometry` instead of covering the screen, or covers it again.
+    pub fn set_geometry(&mut self, geometry: Option<Geometry>) {
+        if geometry.is_some() && self.compute_pipeline.is_some() {
+            warn!("geometry is not supported by the compute path");
+        }
+
+        self.geometry = geometry;
+        self.reset_accumulation();
+    }
+
+    /// Draws `mesh` over the scene.
+    pub fn set_mesh(&mut self, mesh: &Mesh) -> Result<(), String> {
+        if self.compute_pipeline.is_some() {
+            warn!("meshes are not supported by the compute path");
+        }
+
+        self.mesh = Some(mesh.upload(&self.device, &self.queue)?);
+        self.mesh_pass = self.create_mesh_pass().map_err(|err| err.to_string())?;
+        self.reset_accumulation();
+
+        Ok(())
+    }
+
+    /// The pass drawing the mesh, if there is one, at the current dimensions of the scene.
+    fn create_mesh_pass(&self) -> Result<Option<MeshPass>, Error> {
+        if self.mesh.is_none() || self.compute_pipeline.is_some() {
+            return Ok(None);
+        }
+
+        Ok(Some(MeshPass::new(&self.device, &self.queue, &self.config, &self.pipeline_layout,
+                              &self.descriptor_pool, &self.render_pass,
+                              self.images[0].dimensions(), self.scene_dimensions())?))
+    }
+
+    /// Takes over the options of a reloaded `--scene` that are passed to the shaders, the light,
+    /// the exposure, the heatmap and the reference objects, and the clear color, which the
+    /// frames are recorded with. The camera is set with `set_camera` every frame anyway, and the
+    /// rest only applies on the next start.
+    pub fn apply_scene(&mut self, config: &Config) {
+        self.config.light_direction = config.light_direction;
+        self.config.light_color = config.light_color;
+        self.config.light_intensity = config.light_intensity;
+        self.config.coord_system = config.coord_system;
+        self.config.fov = config.fov;
+        self.config.reference = config.reference;
+        self.config.show_reference = config.show_reference;
+        self.config.reference_center = config.reference_center;
+        self.config.reference_radius = config.reference_radius;
+        self.config.ground_height = config.ground_height;
+        self.config.clear_color = config.clear_color;
+
+        if let Some(ref mut render_targets) = self.render_targets {
+            render_targets.set_clear_color(config.clear_color);
+        }
+
+        if self.compute_pipeline.is_none() {
+            self.config.heatmap = config.heatmap;
+        }
+
+        // Auto-exposure starts over from the new exposure.
+        if config.exposure != self.config.exposure {
+            self.config.exposure = config.exposure;
+            self.auto_exposure = AutoExposure::new(&self.config);
+        }
+
+        self.reset_accumulation();
+    }
+
+    /// Replaces the fragment shader of the scene, keeping the previous one if the pipelines
+    /// can't be created with it.
+    pub fn set_fragment_shader(&mut self, fs: FragmentShader) -> Result<(), Error> {
+        let previous = mem::replace(&mut self.fs, fs);
+
+        if let Err(err) = self.recreate_pipelines() {
+            self.fs = previous;
+            self.recreate_pipelines()?;
+            return Err(err);
+        }
+
+        Ok(())
+    }
+
+    /// Recreates all the pipelines drawing the scene, with the current shaders.
+    fn recreate_pipelines(&mut self) -> Result<(), Error> {
+        self.pipeline = ::create_pipeline(&self.device, &self.config, &self.vs, &self.fs,
+                                          &self.pipeline_layout, self.scene_render_pass(),
+                                          self.scene_dimensions())?;
+
+        if let Some(ref mut metering) = self.metering {
+            metering.set_shaders(&self.device, &self.config, &self.vs, &self.fs,
+                                 &self.pipeline_layout)?;
+        }
+
+        if let Some(ref mut render_targets) = self.render_targets {
+            render_targets.set_shaders(&self.device, &self.config, &self.vs, &self.fs,
+                                       &self.pipeline_layout)?;
+        }
+
+        self.reset_accumulation();
+        Ok(())
+    }
+
+    /// Shows the next of the render targets, if there are multiple ones.
+    pub fn cycle_attachment(&mut self) {
+        if self.render_targets.is_none() {
+            warn!("there is only one render target, use `--mrt` to render into multiple ones");
+            return;
+        }
+
+        self.attachment = (self.attachment + 1) % ATTACHMENTS.len();
+        info!("showing the `{}` render target", ATTACHMENTS[self.attachment]);
+    }
+
+    /// Moves the camera to `position`, looking at `target` with `up` up on the screen.
+    pub fn set_camera(&mut self, position: [f32; 3], target: [f32; 3], up: [f32; 3]) {
+        if position != self.config.camera_position || target != self.config.camera_target ||
+           up != self.config.camera_up {
+            self.config.camera_position = position;
+            self.config.camera_target = target;
+            self.config.camera_up = up;
+            self.reset_accumulation();
+        }
+    }
+
+    /// Updates the direction towards the light passed to the shaders.
+    pub fn set_light_direction(&mut self, direction: [f32; 3]) {
+        if direction != self.config.light_direction {
+            self.config.light_direction = direction;
+            self.reset_accumulation();
+        }
+    }
+
+    /// Updates the mouse passed to the shaders.
+    pub fn set_mouse(&mut self, mouse: Mouse) {
+        // Moving the cursor without the button held isn't seen by the shaders.
+        let identity = |position| position;
+
+        if mouse.shadertoy(identity) != self.mouse.shadertoy(identity) {
+            self.reset_accumulation();
+        }
+
+        self.mouse = mouse;
+    }
+
+    /// Switches between showing the colors of the scene and the heatmap of its cost.
+    pub fn toggle_heatmap(&mut self) {
+        self.config.heatmap = !self.config.heatmap;
+        self.reset_accumulation();
+    }
+
+    /// Switches between the perspective camera and the orthographic one of `--ortho`.
+    pub fn toggle_ortho(&mut self) {
+        self.config.ortho = !self.config.ortho;
+        self.reset_accumulation();
+        info!("rendering with the {} camera",
+              if self.config.ortho { "orthographic" } else { "perspective" });
+    }
+
+    /// Shows or hides the reference objects of `--reference`.
+    pub fn toggle_reference(&mut self) {
+        if self.compute_pipeline.is_some() {
+            warn!("the reference objects are not supported by the compute path");
+            return;
+        }
+
+        self.config.show_reference = !self.config.show_reference;
+        self.reset_accumulation();
+    }
+
+    /// The configuration the frames are rendered with, including the camera and the light as
+    /// they were last set.
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    pub fn bounces(&self) -> u32 {
+        self.config.bounces
+    }
+
+    /// Lets the secondary rays bounce once more, up to `config::MAX_BOUNCES`.
+    pub fn more_bounces(&mut self) {
+        self.config.bounces = cmp::min(self.config.bounces + 1, config::MAX_BOUNCES);
+        self.reset_accumulation();
+    }
+
+    /// Lets the secondary rays bounce once less, down to none.
+    pub fn fewer_bounces(&mut self) {
+        self.config.bounces = self.config.bounces.saturating_sub(1);
+        self.reset_accumulation();
+    }
+
+    /// Clears the state kept by the shader.
+    pub fn reset_state(&mut self) {
+        // The buffers may still be in use by the GPU.
+        self.submissions.clear();
+        self.state.reset();
+        self.reset_accumulation();
+    }
+
+    /// Notifies the renderer that the window has been resized.
+    pub fn resize(&mut self, dimensions: [u32; 2]) {
+        self.window_dimensions = dimensions;
+        self.recreate_swapchain = true;
+    }
+
+    /// Whether the surface has been lost, and has to be replaced with `replace_surface` for
+    /// anything to be rendered again.
+    pub fn surface_lost(&self) -> bool {
+        self.surface_lost
+    }
+
+    /// Creates a swapchain like the previous one on `surface`, to replace a surface that was
+    /// lost.
+    pub fn replace_surface(&mut self, surface: &Arc<Surface>) -> Result<(), String> {
+        let physical = self.device.physical_device();
+
+        if !surface.is_supported(&self.present_queue.family()).unwrap_or(false) {
+            return Err("the queue can't present to the new surface".to_string());
+        }
+
+        let caps = surface.get_capabilities(&physical)
+            .map_err(|err| format!("failed to get the capabilities of the surface: {}", err))?;
+
+        // The render pass and the pipelines were created for the format of the old images.
+        let format = self.images[0].format();
+
+        if !caps.supported_formats.iter().any(|&(supported, _)| supported == format) {
+            return Err(format!("the new surface doesn't support the format {:?}", format));
+        }
+
+        if !caps.present_modes.supports(self.present_mode) {
+            return Err(format!("the new surface doesn't support the present mode {:?}",
+                               self.present_mode));
+        }
+
+        let alpha = caps.supported_composite_alpha.iter().next()
+            .ok_or("the new surface supports no composite alpha mode")?;
+        let usage = ImageUsage {
+            color_attachment: true,
+            storage: self.compute_pipeline.is_some(),
+            transfer_source: self.capturable,
+            .. ImageUsage::none()
+        };
+
+        // The old swapchain images may still be in use.
+        self.submissions.clear();
+
+        // The old swapchain belongs to the lost surface, so it can't be passed on.
+        let (swapchain, images) = Swapchain::new(&self.device, surface, 2, format,
+                                                 swapchain_dimensions(&caps,
+                                                                      self.window_dimensions),
+                                                 1, &usage,
+                                                 queue::sharing_mode(&[&self.queue,
+                                                                       &self.present_queue]),
+                                                 SurfaceTransform::Identity, alpha,
+                                                 self.present_mode, self.config.clipped, None)
+            .map_err(|err| format!("failed to create the swapchain: {}", err))?;
+
+        self.surface = surface.clone();
+        self.swapchain = swapchain;
+        self.images = images;
+        self.recreate_swapchain = false;
+        self.surface_lost = false;
+        self.create_swapchain_resources().map_err(|err| err.to_string())
+    }
+
+    /// Recreates the swapchain for the current dimensions of the window, or marks the surface
+    /// as lost if that fails.
+    fn recreate_swapchain(&mut self) -> Result<(), Error> {
+        // Failing to query the surface is what losing it usually looks like first.
+        let caps = match self.surface.get_capabilities(&self.device.physical_device()) {
+            Ok(caps) => caps,
+            Err(err) => {
+                warn!("failed to get the capabilities of the surface, it may be lost: {}", err);
+                self.surface_lost = true;
+                return Ok(());
+            }
+        };
+        let dimensions = swapchain_dimensions(&caps, self.window_dimensions);
+
+        // The old swapchain images may still be in use.
+        self.submissions.clear();
+
+        let (swapchain, images) = match self.swapchain.recreate_with_dimension(dimensions) {
+            Ok(swapchain) => swapchain,
+            Err(err) => {
+                warn!("failed to recreate the swapchain, the surface may be lost: {}", err);
+                self.surface_lost = true;
+                return Ok(());
+            }
+        };
+
+        self.swapchain = swapchain;
+        self.images = images;
+        self.recreate_swapchain = false;
+        self.create_swapchain_resources()
+    }
+
+    /// Tears down and recreates everything that depends on the swapchain and the shaders right
+    /// away, the way a resize does, logging each step to help debugging the recreation.
+    pub fn recreate_all(&mut self) -> Result<(), Error> {
+        info!("recreating everything: waiting for the GPU to finish the submitted frames");
+        self.submissions.clear();
+
+        info!("recreating the swapchain, its framebuffers and the resources sized after it");
+        self.recreate_swapchain()?;
+
+        info!("recreating the pipelines of the scene");
+        self.recreate_pipelines()?;
+
+        info!("recreated everything, {} swapchain images of {:?}", self.images.len(),
+              self.images[0].dimensions());
+        Ok(())
+    }
+
+    /// Sets the height of the letterbox bars drawn over the following frames, as a fraction of
+    /// the height of the image.
+    pub fn set_letterbox(&mut self, height: f32) {
+        self.letterbox = height;
+    }
+
+    /// Shows the crosshair if it is hidden, and hides it otherwise.
+    pub fn toggle_crosshair(&mut self) {
+        if self.crosshair.is_none() {
+            warn!("the crosshair is not supported by the compute path");
+            return;
+        }
+
+        self.show_crosshair = !self.show_crosshair;
+    }
+
+    /// Shows `text` over the scene if no text is shown, and hides the text otherwise.
+    pub fn toggle_text(&mut self, text: String) -> Result<(), Error> {
+        self.text = match self.text {
+            Some(_) => None,
+            None => Some(text),
+        };
+        self.text_overlay = self.create_text_overlay()?;
+
+        Ok(())
+    }
+
+    fn create_text_overlay(&self) -> Result<Option<TextOverlay>, Error> {
+        match self.text {
+            Some(ref text) if self.compute_pipeline.is_none() => {
+                Ok(Some(TextOverlay::new(&self.device, &self.queue, &self.render_pass,
+                                         self.images[0].dimensions(), text)?))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Records the commands drawing a frame into the swapchain image `image_num`, with the graph
+    /// of `frame_times` over it if given.
+    fn record(&mut self, image_num: usize, frame_times: Option<&FrameTimes>)
+              -> PrimaryCommandBufferBuilder {
+        // Building a command buffer is an expensive operation (usually a few hundred
+        // microseconds), but it is known to be a hot path in the driver and is expected to be
+        // optimized.
+        //
+        // Note that we have to pass a queue family when we create the command buffer. The
+        // command buffer will only be executable on that given queue family.
+        match self.compute_pipeline {
+            Some(ref compute_pipeline) => {
+                // One invocation per pixel, the group size matches `local_size` of the shader.
+                let dimensions = self.images[image_num].dimensions();
+                let groups = [(dimensions[0] + 15) / 16, (dimensions[1] + 15) / 16, 1];
+
+                PrimaryCommandBufferBuilder::new(&self.device, self.queue.family())
+                    .dispatch(compute_pipeline, &self.compute_sets[image_num], groups, &())
+            }
+            None => {
+                let builder = PrimaryCommandBufferBuilder::new(&self.device, self.queue.family());
+                let set = &self.sets[self.state.current()];
+
+                // The luminance of the scene is measured separately, before it is drawn.
+                let builder = match self.metering {
+                    Some(ref mut metering) => {
+                        metering.record(builder, &self.vertex_buffer, self.state.current())
+                    }
+                    None => builder,
+                };
+
+                // The mesh is drawn offscreen at the resolution of the scene, to be blended over
+                // it below.
+                let builder = match (self.mesh_pass.as_ref(), self.mesh.as_ref()) {
+                    (Some(mesh_pass), Some(mesh)) => mesh_pass.record(builder, mesh, set),
+                    _ => builder,
+                };
+
+                let vertex_buffer = &self.vertex_buffer;
+                let geometry = self.geometry.as_ref();
+                let pipeline = &self.pipeline;
+                let background = self.background.as_ref();
+                // Shows where the scene is transparent or doesn't draw.
+                let scene_clear_color = self.ambient.unwrap_or(self.config.clear_color);
+
+                // The scene is drawn over the background, if there is one.
+                let draw_scene = |builder: PrimaryCommandBufferBuilderInlineDraw| {
+                    let builder = match background {
+                        Some(background) => background.draw(builder, vertex_buffer),
+                        None => builder,
+                    };
+
+                    match geometry {
+                        Some(geometry) => {
+                            builder.draw_indexed(pipeline, geometry.vertices(), geometry.indices(),
+                                                 &DynamicState::none(), set, &())
+                        }
+                        None => {
+                            builder.draw(pipeline, vertex_buffer, &DynamicState::none(), set, &())
+                        }
+                    }
+                };
+
+                // When upscaling, the scene is first drawn into the offscreen image.
+                let builder = match self.upscaler {
+                    Some(ref upscaler) => {
+                        draw_scene(builder.draw_inline(upscaler.render_pass(),
+                                                       upscaler.framebuffer(),
+                                                       ::render_pass::ClearValues {
+                                                           color: scene_clear_color
+                                                       }))
+                            .draw_end()
+                    }
+                    None => builder,
+                };
+
+                // With multiple render targets, all of them are drawn into offscreen.
+                let builder = match self.render_targets {
+                    Some(ref render_targets) => render_targets.record(builder, vertex_buffer, set),
+                    None => builder,
+                };
+
+                // With accumulation, a sample is drawn offscreen and averaged with the previous
+                // ones, until there are enough of them.
+                let builder = match self.accumulator {
+                    Some(ref mut accumulator) if accumulator.needs_sample() => {
+                        let builder = builder.draw_inline(accumulator.render_pass(),
+                                                          accumulator.framebuffer(),
+                                                          ::render_pass::ClearValues {
+                                                              color: scene_clear_color
+                                                          });
+                        let builder = draw_scene(builder).draw_end();
+
+                        accumulator.record(builder, vertex_buffer)
+                    }
+                    _ => builder,
+                };
+
+                // The bars around a scene of an internal resolution that doesn't fit the window
+                // are left black.
+                let clear_color = if self.config.internal_res.is_some() {
+                    [0.0, 0.0, 0.0, 1.0]
+                } else {
+                    scene_clear_color
+                };
+
+                // Before we can draw, we have to *enter a render pass*. There are two methods to
+                // do this: `draw_inline` and `draw_secondary`.
+                let builder = builder.draw_inline(&self.render_pass, &self.framebuffers[image_num],
+                                                  ::render_pass::ClearValues {
+                                                      color: clear_color
+                                                  });
+
+                // Execute a subpass. The next one would be executed with `next_inline` or
+                // `next_secondary`.
+                let builder = if let Some(ref upscaler) = self.upscaler {
+                    upscaler.draw(builder, vertex_buffer)
+                } else if let Some(ref render_targets) = self.render_targets {
+                    render_targets.draw(builder, vertex_buffer, self.attachment)
+                } else if let Some(ref accumulator) = self.accumulator {
+                    accumulator.draw(builder, vertex_buffer)
+                } else {
+                    draw_scene(builder)
+                };
+
+                let builder = match self.mesh_pass {
+                    Some(ref mesh_pass) => mesh_pass.draw(builder, vertex_buffer),
+                    None => builder,
+                };
+
+                let builder = match self.letterbox_bars {
+                    Some(ref bars) if self.letterbox > 0.0 => {
+                        bars.draw(builder, image_num, self.letterbox)
+                    }
+                    _ => builder,
+                };
+
+                let builder = match self.crosshair {
+                    Some(ref crosshair) if self.show_crosshair => crosshair.draw(builder),
+                    _ => builder,
+                };
+
+                let builder = match (self.frame_graph.as_ref(), frame_times) {
+                    (Some(frame_graph), Some(frame_times)) => {
+                        frame_graph.draw(builder, image_num, frame_times)
+                    }
+                    _ => builder,
+                };
+
+                let builder = match self.text_overlay {
+                    Some(ref text_overlay) => text_overlay.draw(builder),
+                    None => builder,
+                };
+
+                builder.draw_end()
+            }
+        }
+    }
+
+    /// Draws and presents a frame at `time`, with the graph of `frame_times` over it if given.
+    pub fn render(&mut self, time: Time, frame_times: Option<&FrameTimes>) -> Result<(), Error> {
+        // Clearing the old submissions by keeping alive only the ones whose destructor would
+        // block.
+        self.submissions.retain(|s| s.destroying_would_block());
+
+        // Waits for the oldest frames, until there is room for this one.
+        while self.submissions.len() >= self.max_frames_in_flight {
+            self.submissions.remove(0);
+        }
+
+        if self.recreate_swapchain {
+            self.recreate_swapchain()?;
+        }
+
+        if self.surface_lost {
+            return Ok(());
+        }
+
+        self.advance(time);
+
+        // Before we can draw on the output, we have to *acquire* an image from the swapchain. If
+        // no image is available (which happens if you submit draw commands too quickly), then
+        // the function will block.
+        // This operation returns the index of the image that we are allowed to draw upon.
+        //
+        // This function can block if no image is available. The parameter is a timeout after
+        // which the function call will return an error.
+        //
+        // An out of date swapchain can't be presented to anymore and has to be recreated right
+        // away. A suboptimal one still can, so it is only recreated after this frame to avoid a
+        // hitch.
+        let image_num = match self.swapchain.acquire_next_image(Duration::new(1, 0)) {
+            Ok((image_num, suboptimal)) => {
+                if suboptimal {
+                    debug!("the swapchain is suboptimal, recreating it after this frame");
+                    self.recreate_swapchain = true;
+                }
+
+                image_num
+            }
+            Err(AcquireError::OutOfDate) => {
+                debug!("the swapchain is out of date, recreating it");
+                return self.recreate_swapchain();
+            }
+            Err(AcquireError::SurfaceLost) => {
+                warn!("the surface was lost while acquiring a swapchain image");
+                self.surface_lost = true;
+                return Ok(());
+            }
+            Err(err) => {
+                return Err(Error::Swapchain(format!("failed to acquire a swapchain image: {}",
+                                                    err)));
+            }
+        };
+
+        let mut builder = self.record(image_num, frame_times);
+
+        if self.stream.is_some() {
+            let (copying, dimensions, pixels) = self.copy_swapchain_image(builder, image_num)?;
+
+            builder = copying;
+            self.readbacks.push_back((dimensions, pixels));
+        }
+
+        let command_buffer = builder.build();
+
+        // Now all we need to do is submit the command buffer to the queue.
+        let submission = command_buffer::submit(&command_buffer, &self.queue).map_err(|err| {
+            Error::Capture(format!("failed to submit the command buffer: {}", err))
+        })?;
+        self.submissions.push(submission);
+        self.publish_readbacks();
+
+        // Submits a command to display the color output on screen.
+        // May take a while, consider spawning a separate thread for this call.
+        match self.swapchain.present(&self.present_queue, image_num) {
+            Ok(()) => (),
+            Err(PresentError::OutOfDate) => {
+                debug!("the swapchain is out of date, recreating it");
+                self.recreate_swapchain()?;
+            }
+            Err(PresentError::SurfaceLost) => {
+                warn!("the surface was lost while presenting a swapchain image");
+                self.surface_lost = true;
+            }
+            Err(err) => {
+                return Err(Error::Swapchain(format!("failed to present a swapchain image: {}",
+                                                    err)));
+            }
+        }
+
+        self.present_mirror();
+        Ok(())
+    }
+
+    /// Shows the scene in the window of `surface` of `dimensions` as well, see `Mirror`.
+    ///
+    /// That requires the scene to be rendered offscreen, which isn't the case on the compute
+    /// path, with multiple render targets or with accumulation.
+    pub fn add_mirror(&mut self, surface: &Arc<Surface>, dimensions: [u32; 2])
+                      -> Result<(), String> {
+        let mirror = match self.upscaler {
+            Some(ref upscaler) => {
+                Mirror::new(&self.device, &self.queue, &self.present_queue,
+                            &self.descriptor_pool, surface, dimensions, &self.config,
+                            upscaler.image(), upscaler.dimensions())?
+            }
+            None => {
+                return Err("a second window is not supported by the compute path, with \
+                            multiple render targets or with accumulation".to_string());
+            }
+        };
+
+        self.mirror = Some(mirror);
+        Ok(())
+    }
+
+    /// Recreates the swapchain of the second window for its new `dimensions` before the next
+    /// frame.
+    pub fn resize_mirror(&mut self, dimensions: [u32; 2]) {
+        if let Some(ref mut mirror) = self.mirror {
+            mirror.resize(dimensions);
+        }
+    }
+
+    /// Shows the frame just rendered in the second window, if there is one, which is closed if
+    /// it can't be shown anymore.
+    fn present_mirror(&mut self) {
+        let result = match self.mirror {
+            Some(ref mut mirror) => {
+                // The old swapchain images may still be in use.
+                if mirror.outdated() {
+                    self.submissions.clear();
+                }
+
+                mirror.present(&self.vertex_buffer)
+            }
+            None => return,
+        };
+
+        match result {
+            Ok(submission) => self.submissions.extend(submission),
+            Err(message) => {
+                warn!("{}, no longer showing the second window", message);
+                self.mirror = None;
+            }
+        }
+    }
+
+    /// Records the copy of swapchain image `image_num` into a new buffer after the commands of
+    /// `builder`, returning it along with the dimensions of the image and the buffer.
+    ///
+    /// The image is transitioned for the copy by the builder, which tracks its layout: from
+    /// `PresentSrc`, or the layout it was last drawn in, to `TransferSrcOptimal` with a barrier
+    /// waiting for the drawing before it, then back to `PresentSrc` at the end of the command
+    /// buffer, as a swapchain image has to be when it is presented. The copy is correct wherever
+    /// it is recorded in the frame, and nothing recorded after it sees another layout.
+    ///
+    /// vulkano doesn't let these barriers be recorded by hand, `SUBMANIFOLD_VALIDATION=1` has the
+    /// validation layer check the ones it records.
+    fn copy_swapchain_image(&self, builder: PrimaryCommandBufferBuilder, image_num: usize)
+                            -> Result<(PrimaryCommandBufferBuilder, [u32; 2],
+                                       Arc<CpuAccessibleBuffer<[[u8; 4]]>>), Error> {
+        let image = &self.images[image_num];
+        let dimensions = image.dimensions();
+        let pixels = CpuAccessibleBuffer::<[[u8; 4]]>::array(&self.device,
+                                                              (dimensions[0] * dimensions[1])
+                                                                  as usize,
+                                                              &BufferUsage::all(),
+                                                              Some(self.queue.family()))
+            .map_err(|err| Error::OutOfMemory("the buffer of the copied image", err))?;
+        let builder = builder.copy_color_image_to_buffer(&pixels, image, 0, 0 .. 1, [0, 0, 0],
+                                                         [dimensions[0], dimensions[1], 1]);
+
+        Ok((builder, dimensions, pixels))
+    }
+
+    /// Sends the frames shown in the window to `stream` from now on.
+    pub fn set_stream(&mut self, stream: Stream) -> Result<(), String> {
+        if !self.capturable {
+            return Err("the swapchain images of the surface can't be copied from".to_string());
+        }
+
+        let bgra = bgra(self.images[0].format())?;
+
+        self.stream = Some((stream, bgra));
+        Ok(())
+    }
+
+    /// Sends the frames copied for the stream that the GPU has finished to it.
+    ///
+    /// They are dropped instead if too many are waiting, as the GPU has to wait for the frames
+    /// in flight anyway.
+    fn publish_readbacks(&mut self) {
+        let (stream, bgra) = match self.stream {
+            Some((ref stream, bgra)) => (stream, bgra),
+            None => return,
+        };
+
+        while self.readbacks.len() > self.max_frames_in_flight + 1 {
+            self.readbacks.pop_front();
+        }
+
+        while let Some((dimensions, pixels)) = self.readbacks.pop_front() {
+            let bytes = match pixels.read(Duration::new(0, 0)) {
+                Ok(pixels) => Some(rgba(&pixels, bgra)),
+                Err(_) => None,
+            };
+
+            match bytes {
+                Some(bytes) => stream.publish(dimensions, bytes),
+                None => {
+                    self.readbacks.push_front((dimensions, pixels));
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Draws and presents a frame at `time`, and saves it to `path` as a PNG.
+    pub fn screenshot(&mut self, time: Time, path: &Path) -> Result<(), String> {
+        if !self.capturable {
+            return Err("the swapchain images of the surface can't be copied from".to_string());
+        }
+
+        // The bytes of the image are reordered to RGBA below.
+        let bgra = bgra(self.images[0].format())?;
+
+        self.advance(time);
+
+        let image_num = self.swapchain.acquire_next_image(Duration::new(1, 0))
+            .map(|(image_num, _)| image_num)
+            .map_err(|err| format!("failed to acquire a swapchain image: {}", err))?;
+        let builder = self.record(image_num, None);
+        let (builder, dimensions, pixels) = self.copy_swapchain_image(builder, image_num)
+            .map_err(|err| err.to_string())?;
+        let command_buffer = builder.build();
+
+        // Destroying the submission blocks until the GPU is done with it.
+        command_buffer::submit(&command_buffer, &self.queue)
+            .map_err(|err| format!("failed to submit the command buffer: {}", err))?;
+
+        self.swapchain.present(&self.present_queue, image_num)
+            .map_err(|err| format!("failed to present a swapchain image: {}", err))?;
+
+        let pixels = pixels.read(Duration::new(5, 0))
+            .map_err(|_| "timed out while reading the rendered image")?;
+        let mut bytes = ::headless::with_alpha(&self.config, rgba(&pixels, bgra));
+
+        if self.config.timecode {
+            let mut canvas = Canvas::from_pixels(dimensions[0], dimensions[1], bytes);
+
+            canvas.write_timecode(self.frame, time.time);
+            bytes = canvas.into_pixels();
+        }
+
+        image::save_buffer(path, &bytes, dimensions[0], dimensions[1], image::RGBA(8))
+            .map_err(|err| format!("failed to save `{}`: {}", path.display(), err))
+    }
+
+    /// Draws and presents a frame at `time`, and saves the distances of the `--mrt` render
+    /// target to `path` as a grayscale PNG, see `mrt::encode_distances`.
+    pub fn save_distances(&mut self, time: Time, path: &Path) -> Result<(), String> {
+        let dimensions = match self.render_targets {
+            Some(ref render_targets) => render_targets.dimensions(),
+            None => return Err("there are no distances to save without `--mrt`".to_string()),
+        };
+
+        self.advance(time);
+
+        let image_num = self.swapchain.acquire_next_image(Duration::new(1, 0))
+            .map(|(image_num, _)| image_num)
+            .map_err(|err| format!("failed to acquire a swapchain image: {}", err))?;
+
+        let distances = CpuAccessibleBuffer::<[f32]>::array(&self.device,
+                                                            (dimensions[0] * dimensions[1])
+                                                                as usize,
+                                                            &BufferUsage::all(),
+                                                            Some(self.queue.family()))
+            .map_err(|err| format!("failed to create the buffer of the distances: {}", err))?;
+
+        let builder = self.record(image_num, None);
+        let command_buffer = self.render_targets.as_ref()
+            .expect("the render targets were checked for above")
+            .copy_distances(builder, &distances)
+            .build();
+
+        // Destroying the submission blocks until the GPU is done with it.
+        command_buffer::submit(&command_buffer, &self.queue)
+            .map_err(|err| format!("failed to submit the command buffer: {}", err))?;
+
+        self.swapchain.present(&self.present_queue, image_num)
+            .map_err(|err| format!("failed to present a swapchain image: {}", err))?;
+
+        let distances = distances.read(Duration::new(5, 0))
+            .map_err(|_| "timed out while reading the rendered distances")?;
+        let bytes = mrt::encode_distances(&distances, self.config.distance_range);
+
+        image::save_buffer(path, &bytes, dimensions[0], dimensions[1], image::Gray(8))
+            .map_err(|err| format!("failed to save `{}`: {}", path.display(), err))
+    }
+
+    /// The id the last frame wrote into the `--mrt` render target at `position`, in pixels of
+    /// the window, or none if that is outside of the scene, such as on the bars around an
+    /// internal resolution of another aspect ratio.
+    ///
+    /// Only the single texel is copied, so that this is quick enough to do on every click.
+    pub fn pick(&mut self, position: [f32; 2]) -> Result<Option<u32>, String> {
+        let dimensions = match self.render_targets {
+            Some(ref render_targets) => render_targets.dimensions(),
+            None => return Err("there are no ids to pick without `--mrt`".to_string()),
+        };
+        let position = upscale::to_scene(&self.config, self.images[0].dimensions(), dimensions,
+                                         position);
+
+        if !(position[0] >= 0.0 && position[0] < dimensions[0] as f32 &&
+             position[1] >= 0.0 && position[1] < dimensions[1] as f32) {
+            return Ok(None);
+        }
+
+        let id = CpuAccessibleBuffer::<[u32]>::array(&self.device, 1, &BufferUsage::all(),
+                                                     Some(self.queue.family()))
+            .map_err(|err| format!("failed to create the buffer of the id: {}", err))?;
+
+        let builder = PrimaryCommandBufferBuilder::new(&self.device, self.queue.family());
+        let command_buffer = self.render_targets.as_ref()
+            .expect("the render targets were checked for above")
+            .copy_id(builder, &id, [position[0] as u32, position[1] as u32])
+            .build();
+
+        // Destroying the submission blocks until the GPU is done with it.
+        command_buffer::submit(&command_buffer, &self.queue)
+            .map_err(|err| format!("failed to submit the command buffer: {}", err))?;
+
+        let id = id.read(Duration::new(5, 0))
+            .map_err(|_| "timed out while reading the picked id")?;
+
+        Ok(Some(id[0]))
+    }
+
+    /// Blocks until the GPU has finished executing all submitted commands, so that nothing is
+    /// freed while it is still in use.
+    pub fn wait(&mut self) {
+        self.submissions.clear();
+    }
+}
+
+/// Whether swapchain images of `format` are in BGRA rather than RGBA, the only ones that can be
+/// captured.
+fn bgra(format: Format) -> Result<bool, String> {
+    match format {
+        Format::R8G8B8A8Unorm | Format::R8G8B8A8Srgb => Ok(false),
+        Format::B8G8R8A8Unorm | Format::B8G8R8A8Srgb => Ok(true),
+        format => Err(format!("captures of the swapchain format {:?} are not supported", format)),
+    }
+}
+
+/// The bytes of `pixels` copied from a swapchain image, reordered to RGBA if they are in `bgra`.
+fn rgba(pixels: &[[u8; 4]], bgra: bool) -> Vec<u8> {
+    pixels.iter().flat_map(|pixel| {
+        let pixel = if bgra { [pixel[2], pixel[1], pixel[0], pixel[3]] } else { *pixel };
+        pixel.to_vec()
+    }).collect()
+}
+
+/// The dimensions of a swapchain on a surface with `caps` in a window of `window_dimensions`.
+///
+/// The window may report a size the surface doesn't support, such as a zero extent while it is
+/// being resized to its minimum.
+pub fn swapchain_dimensions(caps: &Capabilities, window_dimensions: [u32; 2]) -> [u32; 2] {
+    let dimensions = caps.current_extent.unwrap_or(window_dimensions);
+
+    [
+        cmp::min(cmp::max(dimensions[0], cmp::max(caps.min_image_extent[0], 1)),
+                 caps.max_image_extent[0]),
+        cmp::min(cmp::max(dimensions[1], cmp::max(caps.min_image_extent[1], 1)),
+                 caps.max_image_extent[1]),
+    ]
+}
+
+/// The present mode of `modes` showing a finished frame the soonest, for `--low-latency`.
+///
+/// The mailbox mode replaces a frame still waiting for the vertical blank with a newer one,
+/// without tearing. The relaxed FIFO mode, the fallback, shows a frame that missed the vertical
+/// blank right away instead of waiting for the next one, which tears when a frame is late.
+fn low_latency_present_mode(modes: &SupportedPresentModes) -> Option<PresentMode> {
+    if modes.mailbox {
+        Some(PresentMode::Mailbox)
+    } else if modes.relaxed {
+        Some(PresentMode::Relaxed)
+    } else {
+        None
+    }
+}
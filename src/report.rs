@@ -0,0 +1,64 @@
+use std::fs::File;
+use std::path::Path;
+
+use serde_json;
+
+use vulkano::instance::PhysicalDevice;
+use vulkano::swapchain::PresentMode;
+
+/// A summary of a run written with `--json-report`, for scripts and dashboards that track the
+/// performance across commits.
+#[derive(Debug, Serialize)]
+pub struct Report {
+    device: DeviceInfo,
+    /// The dimensions of the swapchain images, when the report was made.
+    resolution: [u32; 2],
+    present_mode: String,
+    /// How long the frames of each path took with `--bench-paths`, left out otherwise.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    timings: Vec<Timing>,
+}
+
+#[derive(Debug, Serialize)]
+struct DeviceInfo {
+    index: usize,
+    name: String,
+    #[serde(rename = "type")]
+    ty: String,
+}
+
+/// The frame times of a path rendered with `--bench-paths`, in milliseconds.
+#[derive(Debug, Clone, Serialize)]
+pub struct Timing {
+    pub path: &'static str,
+    pub frames: u32,
+    pub mean_ms: f32,
+    pub fastest_ms: f32,
+    pub std_dev_ms: f32,
+    /// Whether the frames got slower over the run, as when the GPU is throttled.
+    pub throttling: bool,
+}
+
+impl Report {
+    pub fn new(physical: PhysicalDevice, resolution: [u32; 2], present_mode: PresentMode,
+               timings: Vec<Timing>) -> Report {
+        Report {
+            device: DeviceInfo {
+                index: physical.index(),
+                name: physical.name().to_string(),
+                ty: format!("{:?}", physical.ty()),
+            },
+            resolution: resolution,
+            present_mode: format!("{:?}", present_mode),
+            timings: timings,
+        }
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), String> {
+        let written = File::create(path).map_err(|err| err.to_string()).and_then(|file| {
+            serde_json::to_writer_pretty(file, self).map_err(|err| err.to_string())
+        });
+
+        written.map_err(|err| format!("failed to write the report `{}`: {}", path.display(), err))
+    }
+}
@@ -0,0 +1,671 @@
+use std::env;
+use std::ffi::CStr;
+use std::ffi::CString;
+use std::fs;
+use std::fs::DirBuilder;
+use std::fs::File;
+use std::io;
+use std::io::Read;
+use std::io::Write;
+use std::mem;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process;
+use std::sync::Arc;
+
+use shaderc::CompileOptions;
+use shaderc::Compiler;
+use shaderc::ShaderKind;
+
+use vulkano::device::Device;
+use vulkano::pipeline::shader::GraphicsEntryPoint;
+use vulkano::pipeline::shader::GraphicsShaderType;
+use vulkano::pipeline::shader::ShaderModule;
+
+use config::Config;
+use config::Coverage;
+
+/// The first word of every SPIR-V module.
+const SPIRV_MAGIC: u32 = 0x07230203;
+
+/// The opcodes the interface of a module is reflected from.
+const OP_ENTRY_POINT: u32 = 15;
+const OP_TYPE_INT: u32 = 21;
+const OP_TYPE_FLOAT: u32 = 22;
+const OP_TYPE_VECTOR: u32 = 23;
+const OP_TYPE_MATRIX: u32 = 24;
+const OP_TYPE_ARRAY: u32 = 28;
+const OP_TYPE_STRUCT: u32 = 30;
+const OP_TYPE_POINTER: u32 = 32;
+const OP_CONSTANT: u32 = 43;
+const OP_VARIABLE: u32 = 59;
+const OP_DECORATE: u32 = 71;
+const OP_MEMBER_DECORATE: u32 = 72;
+
+/// The decorations of `OpDecorate` and `OpMemberDecorate` the layout is reflected from.
+const DECORATION_ARRAY_STRIDE: u32 = 6;
+const DECORATION_MATRIX_STRIDE: u32 = 7;
+const DECORATION_BINDING: u32 = 33;
+const DECORATION_DESCRIPTOR_SET: u32 = 34;
+const DECORATION_OFFSET: u32 = 35;
+
+/// The storage class of uniform blocks.
+const STORAGE_CLASS_UNIFORM: u32 = 2;
+
+/// The execution models of `OpEntryPoint`.
+const EXECUTION_MODEL_VERTEX: u32 = 0;
+const EXECUTION_MODEL_FRAGMENT: u32 = 4;
+
+/// The entry point of the vertex shader, with the interface of `vs.glsl`.
+pub type VertexEntryPoint<'a> = GraphicsEntryPoint<'a, (), ::vs::MainInput, ::vs::MainOutput,
+                                                   ::vs::Layout>;
+
+/// The entry point of the fragment shader, with the interface of `fs.glsl`.
+pub type FragmentEntryPoint<'a> = GraphicsEntryPoint<'a, (), ::fs::MainInput, ::fs::MainOutput,
+                                                     ::fs::Layout>;
+
+/// A SPIR-V module loaded at runtime, with the entry point to use.
+pub struct SpirvShader {
+    module: Arc<ShaderModule>,
+    entry_point: CString,
+}
+
+impl SpirvShader {
+    /// Creates the shader from a SPIR-V module that has already been validated, if it has an
+    /// entry point named `entry_point` with the execution model `model`.
+    fn new(device: &Arc<Device>, spirv: &[u8], entry_point: &str, model: u32)
+           -> Result<SpirvShader, String> {
+        check_entry_point(spirv, entry_point, model)?;
+        check_uniform_block(spirv)?;
+
+        let entry_point = CString::new(entry_point)
+            .map_err(|_| "the entry point name must not contain a nul byte")?;
+        let module = unsafe { ShaderModule::new(device, spirv) }
+            .map_err(|err| format!("failed to create the shader module: {}", err))?;
+
+        Ok(SpirvShader {
+            module: module,
+            entry_point: entry_point,
+        })
+    }
+}
+
+/// The vertex shader, either one of those compiled into the executable or a SPIR-V module loaded
+/// at runtime.
+///
+/// A loaded module is expected to have the same interface as `vs.glsl`.
+pub enum VertexShader {
+    Builtin(::vs::Shader),
+    /// Generates a triangle covering the screen, without reading the vertex buffer.
+    Fullscreen(::fullscreen_vs::Shader),
+    Spirv(SpirvShader),
+}
+
+impl VertexShader {
+    pub fn load(device: &Arc<Device>, config: &Config) -> Result<VertexShader, String> {
+        match config.spirv_vert {
+            Some(ref path) => {
+                SpirvShader::new(device, &read_spirv(path)?, &config.vert_entry,
+                                 EXECUTION_MODEL_VERTEX)
+                    .map(VertexShader::Spirv)
+                    .map_err(|err| format!("`{}`: {}", path.display(), err))
+            }
+            None if config.coverage == Coverage::Triangle => {
+                ::fullscreen_vs::Shader::load(device)
+                    .map(VertexShader::Fullscreen)
+                    .map_err(|err| format!("failed to create the vertex shader module: {}", err))
+            }
+            None => {
+                ::vs::Shader::load(device)
+                    .map(VertexShader::Builtin)
+                    .map_err(|err| format!("failed to create the vertex shader module: {}", err))
+            }
+        }
+    }
+
+    /// Whether the shader generates the positions of the vertices instead of reading them from
+    /// the vertex buffer, see `SceneInput`.
+    pub fn generates_positions(&self) -> bool {
+        match *self {
+            VertexShader::Fullscreen(_) => true,
+            VertexShader::Builtin(_) | VertexShader::Spirv(_) => false,
+        }
+    }
+
+    pub fn entry_point(&self) -> VertexEntryPoint {
+        match *self {
+            VertexShader::Builtin(ref shader) => shader.main_entry_point(),
+            // The interface is declared as the one of `vs.glsl`, but the shader has no
+            // position input, which `SceneInput` doesn't bind for it.
+            VertexShader::Fullscreen(ref shader) => unsafe {
+                shader.module().graphics_entry_point(CStr::from_bytes_with_nul_unchecked(b"main\0"),
+                                                     ::vs::MainInput, ::vs::MainOutput,
+                                                     ::vs::Layout, GraphicsShaderType::Vertex)
+            },
+            VertexShader::Spirv(ref shader) => unsafe {
+                shader.module.graphics_entry_point(&shader.entry_point, ::vs::MainInput,
+                                                   ::vs::MainOutput, ::vs::Layout,
+                                                   GraphicsShaderType::Vertex)
+            },
+        }
+    }
+}
+
+/// The fragment shader, either the one compiled into the executable or a SPIR-V module loaded
+/// at runtime.
+///
+/// A loaded module is expected to have the same interface as `fs.glsl`.
+pub enum FragmentShader {
+    Builtin(::fs::Shader),
+    Spirv(SpirvShader),
+}
+
+impl FragmentShader {
+    pub fn load(device: &Arc<Device>, config: &Config) -> Result<FragmentShader, String> {
+        match (config.spirv_frag.as_ref(), config.frag.as_ref()) {
+            (Some(path), _) => {
+                FragmentShader::from_spirv(device, &read_spirv(path)?, &config.frag_entry)
+                    .map_err(|err| format!("`{}`: {}", path.display(), err))
+            }
+            (None, Some(path)) => {
+                let mut source = String::new();
+
+                File::open(path)
+                    .and_then(|mut file| file.read_to_string(&mut source))
+                    .map_err(|err| format!("failed to read `{}`: {}", path.display(), err))?;
+
+                let spirv = compile_glsl(&source, &path.display().to_string(),
+                                         ShaderKind::Fragment, &config.defines,
+                                         config.shader_cache.as_ref())?;
+
+                FragmentShader::from_spirv(device, &spirv, "main")
+            }
+            // The built-in shader is compiled again with the definitions, if there are any.
+            (None, None) if !config.defines.is_empty() => {
+                let spirv = compile_glsl(include_str!("fs.glsl"), "fs.glsl", ShaderKind::Fragment,
+                                         &config.defines, config.shader_cache.as_ref())?;
+
+                FragmentShader::from_spirv(device, &spirv, "main")
+            }
+            (None, None) => {
+                ::fs::Shader::load(device)
+                    .map(FragmentShader::Builtin)
+                    .map_err(|err| format!("failed to create the fragment shader module: {}", err))
+            }
+        }
+    }
+
+    /// Creates the shader from a SPIR-V module that has already been validated.
+    pub fn from_spirv(device: &Arc<Device>, spirv: &[u8], entry_point: &str)
+                      -> Result<FragmentShader, String> {
+        SpirvShader::new(device, spirv, entry_point, EXECUTION_MODEL_FRAGMENT)
+            .map(FragmentShader::Spirv)
+    }
+
+    pub fn entry_point(&self) -> FragmentEntryPoint {
+        match *self {
+            FragmentShader::Builtin(ref shader) => shader.main_entry_point(),
+            FragmentShader::Spirv(ref shader) => unsafe {
+                shader.module.graphics_entry_point(&shader.entry_point, ::fs::MainInput,
+                                                   ::fs::MainOutput, ::fs::Layout,
+                                                   GraphicsShaderType::Fragment)
+            },
+        }
+    }
+}
+
+/// The words of the SPIR-V module, in the native endianness.
+fn words(spirv: &[u8]) -> Vec<u32> {
+    // `read_spirv` accepts either endianness, the magic number tells which one it is.
+    let big_endian = spirv[0] as u32 == SPIRV_MAGIC >> 24;
+
+    spirv.chunks(4).map(|bytes| {
+        let word = (bytes[0] as u32) | (bytes[1] as u32) << 8 |
+                   (bytes[2] as u32) << 16 | (bytes[3] as u32) << 24;
+
+        if big_endian { word.swap_bytes() } else { word }
+    }).collect()
+}
+
+/// The instructions of the module, each as its opcode and its operands.
+fn instructions(words: &[u32]) -> Vec<(u32, &[u32])> {
+    // The instructions follow the header of five words. Each of them starts with a word holding
+    // the number of its words in the upper half and the opcode in the lower half.
+    let mut instructions = Vec::new();
+    let mut index = 5;
+
+    while index < words.len() {
+        let count = (words[index] >> 16) as usize;
+        let opcode = words[index] & 0xffff;
+
+        if count == 0 || index + count > words.len() {
+            break;
+        }
+
+        instructions.push((opcode, &words[index + 1 .. index + count]));
+        index += count;
+    }
+
+    instructions
+}
+
+/// The names of the entry points of the SPIR-V module with the execution model `model`.
+fn entry_points(spirv: &[u8], model: u32) -> Vec<String> {
+    let words = words(spirv);
+
+    // `OpEntryPoint` has the execution model, the id of the function and then the name as a
+    // nul-terminated string packed into the words, followed by the interface.
+    instructions(&words).into_iter()
+        .filter(|&(opcode, operands)| {
+            opcode == OP_ENTRY_POINT && operands.len() > 2 && operands[0] == model
+        })
+        .map(|(_, operands)| {
+            let bytes = operands[2 ..].iter()
+                .flat_map(|word| (0 .. 4).map(move |byte| (word >> (byte * 8)) as u8))
+                .take_while(|&byte| byte != 0)
+                .collect::<Vec<_>>();
+
+            String::from_utf8_lossy(&bytes).into_owned()
+        })
+        .collect()
+}
+
+/// Checks that the SPIR-V module has an entry point named `entry_point` with the execution model
+/// `model`.
+fn check_entry_point(spirv: &[u8], entry_point: &str, model: u32) -> Result<(), String> {
+    let entry_points = entry_points(spirv, model);
+
+    if !entry_points.iter().any(|name| name == entry_point) {
+        let stage = if model == EXECUTION_MODEL_VERTEX { "vertex" } else { "fragment" };
+
+        return Err(if entry_points.is_empty() {
+            format!("the module has no {} entry point", stage)
+        } else {
+            format!("the module has no {} entry point named `{}`, it has `{}`", stage,
+                    entry_point, entry_points.join("`, `"))
+        });
+    }
+
+    Ok(())
+}
+
+/// The only operand of the decoration `decoration` of the id `target`.
+fn decoration(instructions: &[(u32, &[u32])], target: u32, decoration: u32) -> Option<u32> {
+    instructions.iter()
+        .filter(|&&(opcode, operands)| opcode == OP_DECORATE && operands.len() > 2)
+        .find(|&&(_, operands)| operands[0] == target && operands[1] == decoration)
+        .map(|&(_, operands)| operands[2])
+}
+
+/// The only operand of the decoration `decoration` of the member `member` of the struct `id`.
+fn member_decoration(instructions: &[(u32, &[u32])], id: u32, member: u32, decoration: u32)
+                     -> Option<u32> {
+    instructions.iter()
+        .filter(|&&(opcode, operands)| opcode == OP_MEMBER_DECORATE && operands.len() > 3)
+        .find(|&&(_, operands)| {
+            operands[0] == id && operands[1] == member && operands[2] == decoration
+        })
+        .map(|&(_, operands)| operands[3])
+}
+
+/// The type, constant or variable with the id `id`.
+fn definition<'a>(instructions: &[(u32, &'a [u32])], id: u32) -> Option<(u32, &'a [u32])> {
+    instructions.iter()
+        .find(|&&(opcode, operands)| {
+            // The result id comes after the result type, if there is one.
+            let result = match opcode {
+                OP_TYPE_INT | OP_TYPE_FLOAT | OP_TYPE_VECTOR | OP_TYPE_MATRIX | OP_TYPE_ARRAY |
+                OP_TYPE_STRUCT | OP_TYPE_POINTER => 0,
+                OP_CONSTANT | OP_VARIABLE => 1,
+                _ => return false,
+            };
+
+            operands.len() > result && operands[result] == id
+        })
+        .cloned()
+}
+
+/// The size in bytes of the uniform block at set 0 and binding 0 of the SPIR-V module, if it
+/// declares one with a layout that can be reflected.
+fn uniform_block_size(spirv: &[u8]) -> Option<u32> {
+    let words = words(spirv);
+    let instructions = instructions(&words);
+
+    let variable = instructions.iter()
+        .filter(|&&(opcode, operands)| {
+            opcode == OP_VARIABLE && operands.len() > 2 && operands[2] == STORAGE_CLASS_UNIFORM
+        })
+        .map(|&(_, operands)| operands)
+        .find(|operands| {
+            decoration(&instructions, operands[1], DECORATION_DESCRIPTOR_SET) == Some(0) &&
+                decoration(&instructions, operands[1], DECORATION_BINDING) == Some(0)
+        })?;
+
+    // The variable is a pointer to the block.
+    match definition(&instructions, variable[0])? {
+        (OP_TYPE_POINTER, operands) if operands.len() > 2 => {
+            type_size(&instructions, operands[2], None)
+        }
+        _ => None,
+    }
+}
+
+/// The size in bytes of the type with the id `id`, of which a matrix has the stride
+/// `matrix_stride` between its columns.
+fn type_size(instructions: &[(u32, &[u32])], id: u32, matrix_stride: Option<u32>)
+             -> Option<u32> {
+    let (opcode, operands) = definition(instructions, id)?;
+
+    match opcode {
+        OP_TYPE_INT | OP_TYPE_FLOAT if operands.len() > 1 => Some(operands[1] / 8),
+        OP_TYPE_VECTOR if operands.len() > 2 => {
+            Some(type_size(instructions, operands[1], None)? * operands[2])
+        }
+        OP_TYPE_MATRIX if operands.len() > 2 => Some(matrix_stride? * operands[2]),
+        OP_TYPE_ARRAY if operands.len() > 2 => {
+            let length = match definition(instructions, operands[2])? {
+                (OP_CONSTANT, operands) if operands.len() > 2 => operands[2],
+                _ => return None,
+            };
+
+            Some(decoration(instructions, id, DECORATION_ARRAY_STRIDE)? * length)
+        }
+        // Where the member that ends the furthest ends, the offsets are explicit.
+        OP_TYPE_STRUCT => {
+            let mut size = 0;
+
+            for (member, &member_type) in operands[1 ..].iter().enumerate() {
+                let member = member as u32;
+                let offset = member_decoration(instructions, id, member, DECORATION_OFFSET)?;
+                let matrix_stride = member_decoration(instructions, id, member,
+                                                      DECORATION_MATRIX_STRIDE);
+
+                size = size.max(offset + type_size(instructions, member_type, matrix_stride)?);
+            }
+
+            Some(size)
+        }
+        _ => None,
+    }
+}
+
+/// Checks that the uniform block of a module loaded at runtime has the size of `Data` in
+/// `vs.glsl`, which the uniform buffer is created with.
+///
+/// A block that can't be reflected is let through, the pipeline checks its interface later.
+fn check_uniform_block(spirv: &[u8]) -> Result<(), String> {
+    let expected = mem::size_of::<::vs::ty::Data>() as u32;
+
+    match uniform_block_size(spirv) {
+        Some(size) if size != expected => {
+            Err(format!("the uniform block at set 0, binding 0 is {} bytes, but `Data` in \
+                         `vs.glsl` is {} bytes; the blocks of the shaders must match",
+                        size, expected))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Checks that the uniform blocks of the shaders compiled into the executable all match `Data`
+/// in `vs.glsl`, which the uniform buffer is created with.
+pub fn check_builtin_uniform_blocks() -> Result<(), String> {
+    let expected = mem::size_of::<::vs::ty::Data>();
+    let blocks = [
+        ("fullscreen_vs.glsl", mem::size_of::<::fullscreen_vs::ty::Data>()),
+        ("fs.glsl", mem::size_of::<::fs::ty::Data>()),
+        ("cs.glsl", mem::size_of::<::cs::ty::Data>()),
+        ("mesh_vs.glsl", mem::size_of::<::mesh_vs::ty::Data>()),
+        ("mesh_fs.glsl", mem::size_of::<::mesh_fs::ty::Data>()),
+    ];
+
+    for &(name, size) in &blocks {
+        if size != expected {
+            return Err(format!("`Data` in `{}` is {} bytes, but in `vs.glsl` it is {} bytes; \
+                                the blocks of the shaders must match", name, size, expected));
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes the SPIR-V of the vertex and fragment shaders that would be used to `dir`.
+///
+/// The SPIR-V compiled into the executable isn't accessible, so the built-in shaders are compiled
+/// again from their sources at the time of the build.
+pub fn dump_spirv(config: &Config, dir: &Path) -> Result<(), String> {
+    let vertex = match config.spirv_vert {
+        Some(ref path) => read_spirv(path)?,
+        None if config.coverage == Coverage::Triangle => {
+            compile_glsl(include_str!("fullscreen_vs.glsl"), "fullscreen_vs.glsl",
+                         ShaderKind::Vertex, &[], config.shader_cache.as_ref())?
+        }
+        None => {
+            compile_glsl(include_str!("vs.glsl"), "vs.glsl", ShaderKind::Vertex, &[],
+                         config.shader_cache.as_ref())?
+        }
+    };
+    let fragment = match config.spirv_frag {
+        Some(ref path) => read_spirv(path)?,
+        None if config.hot_reload => {
+            let path = config.shader_dir.join("fs.glsl");
+            let mut source = String::new();
+
+            File::open(&path)
+                .and_then(|mut file| file.read_to_string(&mut source))
+                .map_err(|err| format!("failed to read `{}`: {}", path.display(), err))?;
+
+            compile_glsl(&source, &path.display().to_string(), ShaderKind::Fragment,
+                         &config.defines, config.shader_cache.as_ref())?
+        }
+        None => {
+            compile_glsl(include_str!("fs.glsl"), "fs.glsl", ShaderKind::Fragment,
+                         &config.defines, config.shader_cache.as_ref())?
+        }
+    };
+
+    fs::create_dir_all(dir)
+        .map_err(|err| format!("failed to create `{}`: {}", dir.display(), err))?;
+
+    for &(name, ref spirv) in &[("vs.spv", vertex), ("fs.spv", fragment)] {
+        let path = dir.join(name);
+
+        File::create(&path)
+            .and_then(|mut file| file.write_all(spirv))
+            .map_err(|err| format!("failed to write `{}`: {}", path.display(), err))?;
+
+        println!("Wrote `{}` ({} bytes)", path.display(), spirv.len());
+    }
+
+    Ok(())
+}
+
+/// Checks each shader of `--check-shader` the way it would be loaded, without creating a device,
+/// printing whether it passed.
+///
+/// All of them are checked even if some fail, which is an error at the end.
+pub fn check_shaders(config: &Config) -> Result<(), String> {
+    let mut failed = 0;
+
+    for path in &config.check_shaders {
+        match check_shader(config, path) {
+            Ok(()) => println!("`{}`: ok", path.display()),
+            Err(message) => {
+                error!("`{}`: {}", path.display(), message);
+                failed += 1;
+            }
+        }
+    }
+
+    if failed > 0 {
+        return Err(format!("{} of {} shaders failed the check", failed,
+                           config.check_shaders.len()));
+    }
+
+    Ok(())
+}
+
+/// Compiles or reads the shader at `path`, and checks that it has the entry point and the
+/// uniform block it would be loaded with.
+fn check_shader(config: &Config, path: &Path) -> Result<(), String> {
+    let name = path.file_name().map_or(String::new(), |name| name.to_string_lossy().into_owned());
+    let spirv = path.extension().map_or(false, |extension| extension == "spv");
+    // A module compiled from `scene.vert` is `scene.vert.spv`.
+    let stem = if spirv { &name[.. name.len() - ".spv".len()] } else { &name[..] };
+    let vertex = stem.ends_with(".vert") || stem.ends_with("vs.glsl");
+
+    let (kind, model, entry_point) = if vertex {
+        (ShaderKind::Vertex, EXECUTION_MODEL_VERTEX, &config.vert_entry)
+    } else {
+        (ShaderKind::Fragment, EXECUTION_MODEL_FRAGMENT, &config.frag_entry)
+    };
+
+    // Compiled GLSL always has its entry point named `main`.
+    let (module, entry_point) = if spirv {
+        (read_spirv(path)?, entry_point.as_str())
+    } else {
+        let mut source = String::new();
+
+        File::open(path)
+            .and_then(|mut file| file.read_to_string(&mut source))
+            .map_err(|err| format!("failed to read `{}`: {}", path.display(), err))?;
+
+        (compile_glsl(&source, &path.display().to_string(), kind, &config.defines,
+                      config.shader_cache.as_ref())?, "main")
+    };
+
+    check_entry_point(&module, entry_point, model)?;
+    check_uniform_block(&module)
+}
+
+/// Compiles the GLSL `source` of a shader to SPIR-V, `name` is used in error messages.
+///
+/// The macros `defines` are defined as if by `#define` lines right after `#version`.
+///
+/// With a `cache` directory, the SPIR-V is read from it instead if the same source was compiled
+/// before with the same definitions and the same compiler, and kept in it otherwise. A cache that
+/// can't be read or written is only a reason to compile.
+pub fn compile_glsl(source: &str, name: &str, kind: ShaderKind, defines: &[(String, String)],
+                    cache: Option<&PathBuf>) -> Result<Vec<u8>, String> {
+    let cached = cache.map(|dir| {
+        dir.join(format!("{:016x}.spv", cache_key(source, kind, defines)))
+    });
+
+    if let Some(spirv) = cached.as_ref().and_then(|path| read_spirv(path).ok()) {
+        debug!("`{}` is unchanged, using the SPIR-V compiled before", name);
+        return Ok(spirv);
+    }
+
+    let mut compiler = Compiler::new().ok_or("failed to initialize the shader compiler")?;
+    let mut options = CompileOptions::new().ok_or("failed to initialize the shader compiler")?;
+
+    for &(ref macro_name, ref value) in defines {
+        options.add_macro_definition(macro_name, Some(value.as_str()));
+    }
+
+    let spirv = compiler.compile_into_spirv(source, kind, name, "main", Some(&options))
+        .map(|artifact| artifact.as_binary_u8().to_vec())
+        .map_err(|err| format!("failed to compile `{}`:\n{}", name, err))?;
+
+    if let Some(path) = cached {
+        if let Err(message) = write_cached(&path, &spirv) {
+            warn!("failed to cache the SPIR-V of `{}`: {}", name, message);
+        }
+    }
+
+    Ok(spirv)
+}
+
+/// Identifies what the SPIR-V compiled from `source` depends on, the same from one run to the
+/// next as it is a 64-bit FNV-1a hash.
+///
+/// The compiler is identified by the version of the `shaderc` crate it was linked from, found by
+/// `build.rs`, along with the version of SPIR-V it produces.
+fn cache_key(source: &str, kind: ShaderKind, defines: &[(String, String)]) -> u64 {
+    let (spirv_version, spirv_revision) = ::shaderc::get_spirv_version();
+    let mut key = format!("{}\0{}\0{}\0{:?}\0", env!("SUBMANIFOLD_SHADERC_VERSION"),
+                          spirv_version, spirv_revision, kind);
+
+    for &(ref macro_name, ref value) in defines {
+        key.push_str(&format!("{}={}\0", macro_name, value));
+    }
+
+    key.push_str(source);
+
+    key.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// The cache of compiled shaders of the user, `submanifold/shaders` in `$XDG_CACHE_HOME` or
+/// `~/.cache`, or none if neither is known.
+///
+/// Unlike the temporary directory, it isn't shared with other users, who could otherwise plant
+/// modules in it for the shaders of this one.
+pub fn default_cache_dir() -> Option<PathBuf> {
+    let cache = env::var_os("XDG_CACHE_HOME").map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))?;
+
+    Some(cache.join("submanifold").join("shaders"))
+}
+
+/// Creates `dir` and its missing parents, readable only by the user where that can be said.
+fn create_cache_dir(dir: &Path) -> io::Result<()> {
+    let mut builder = DirBuilder::new();
+    builder.recursive(true);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(0o700);
+    }
+
+    builder.create(dir)
+}
+
+/// Writes `spirv` to `path` in the cache, through a temporary file so that a concurrent run
+/// never reads it half written. The temporary file is named after the process, so that two runs
+/// compiling the same shader don't write into the same one.
+fn write_cached(path: &Path, spirv: &[u8]) -> Result<(), String> {
+    let dir = path.parent().expect("cached modules are in a directory");
+    let temporary = path.with_extension(format!("spv.{}.tmp", process::id()));
+
+    create_cache_dir(dir)
+        .and_then(|_| File::create(&temporary))
+        .and_then(|mut file| file.write_all(spirv))
+        .and_then(|_| fs::rename(&temporary, path))
+        .map_err(|err| format!("failed to write `{}`: {}", path.display(), err))
+}
+
+/// Reads a precompiled SPIR-V module from `path`, in the byte order of the host whichever one
+/// the file is in.
+fn read_spirv(path: &Path) -> Result<Vec<u8>, String> {
+    let mut spirv = Vec::new();
+
+    File::open(path)
+        .and_then(|mut file| file.read_to_end(&mut spirv))
+        .map_err(|err| format!("failed to read `{}`: {}", path.display(), err))?;
+
+    // SPIR-V is a stream of 32-bit words, in either endianness.
+    if spirv.is_empty() || spirv.len() % 4 != 0 {
+        return Err(format!("`{}` is not a SPIR-V module, its size of {} bytes is not a \
+                            multiple of 4", path.display(), spirv.len()));
+    }
+
+    let magic = (spirv[0] as u32) | (spirv[1] as u32) << 8 |
+                (spirv[2] as u32) << 16 | (spirv[3] as u32) << 24;
+
+    if magic != SPIRV_MAGIC && magic.swap_bytes() != SPIRV_MAGIC {
+        return Err(format!("`{}` is not a SPIR-V module, it doesn't start with the magic number",
+                           path.display()));
+    }
+
+    // The device reads the words of the module in the byte order of the host.
+    let mut native = Vec::with_capacity(spirv.len());
+
+    for word in words(&spirv) {
+        native.extend_from_slice(&word.to_ne_bytes());
+    }
+
+    Ok(native)
+}
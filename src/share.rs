@@ -0,0 +1,20 @@
+use clipboard::ClipboardContext;
+use clipboard::ClipboardProvider;
+
+/// Puts `text` on the system clipboard.
+pub fn copy(text: String) -> Result<(), String> {
+    let mut context: ClipboardContext = ClipboardProvider::new()
+        .map_err(|err| format!("the clipboard is unavailable: {}", err))?;
+
+    context.set_contents(text)
+        .map_err(|err| format!("failed to write to the clipboard: {}", err))
+}
+
+/// Takes the text on the system clipboard.
+pub fn paste() -> Result<String, String> {
+    let mut context: ClipboardContext = ClipboardProvider::new()
+        .map_err(|err| format!("the clipboard is unavailable: {}", err))?;
+
+    context.get_contents()
+        .map_err(|err| format!("failed to read from the clipboard: {}", err))
+}
@@ -0,0 +1,725 @@
+use std::time::Duration;
+
+use chrono::Local;
+use chrono::Timelike;
+
+use config::Config;
+use vector::normalize;
+
+/// The longest step the time advances by in a single frame, in seconds, so that a frame that
+/// stalled (on a shader recompilation, or while the window was being dragged) doesn't make the
+/// animation skip ahead.
+pub const MAX_DT: f32 = 0.25;
+
+/// The bounds of the time scale, each step of it doubles or halves the speed.
+pub const MIN_SCALE: f32 = 1.0 / 64.0;
+pub const MAX_SCALE: f32 = 64.0;
+
+/// The first word of the text form of the state.
+const SNAPSHOT_PREFIX: &'static str = "submanifold";
+
+/// The time passed to the shaders for a frame, in seconds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Time {
+    /// The time since the start.
+    pub time: f32,
+    /// The time since the previous frame.
+    pub dt: f32,
+    /// The real time since the start, which isn't scaled and goes on while paused.
+    pub real_time: f32,
+}
+
+impl Time {
+    /// The time of a frame at `time` that doesn't advance.
+    pub fn fixed(time: f32) -> Time {
+        Time { time: time, dt: 0.0, real_time: time }
+    }
+
+    /// Whether the animation is at the same point as at `other`, however much real time has
+    /// passed.
+    pub fn same_animation(&self, other: &Time) -> bool {
+        self.time == other.time && self.dt == other.dt
+    }
+}
+
+/// Converts `duration` to seconds.
+pub fn seconds(duration: Duration) -> f32 {
+    duration.as_secs() as f32 + duration.subsec_nanos() as f32 / 1_000_000_000.0
+}
+
+/// Where the time comes from, as the step it advances by in each frame before it is scaled.
+///
+/// The real time is the default, but anything that can tell how far it has moved on since the
+/// previous frame can drive the animation, such as the playback position of a player or an
+/// external MIDI clock.
+pub trait Clock {
+    /// The step of a frame `elapsed` seconds of real time after the previous one, in seconds.
+    fn step(&mut self, elapsed: f32) -> f32;
+}
+
+/// The real time, as measured between the frames.
+#[derive(Debug, Clone, Copy)]
+pub struct RealClock;
+
+impl Clock for RealClock {
+    fn step(&mut self, elapsed: f32) -> f32 {
+        elapsed
+    }
+}
+
+/// Steps of the same length in every frame, however long the frames take, with `--bpm`.
+///
+/// The step is what makes each beat span a whole number of frames at the frame rate, so that the
+/// beats land on the same frames in every run. The number is a multiple of `--frame-skip`, so that
+/// they land on the rendered frames while frames are skipped too. The time falls behind the real
+/// time when frames take longer than planned.
+#[derive(Debug, Clone, Copy)]
+pub struct BeatClock {
+    step: f32,
+}
+
+impl BeatClock {
+    pub fn new(bpm: f32, frame_rate: f32, frame_skip: u32) -> BeatClock {
+        let beat = 60.0 / bpm;
+        let frame_skip = frame_skip.max(1) as f32;
+        let frames_per_beat = (beat * frame_rate / frame_skip).round().max(1.0) * frame_skip;
+
+        BeatClock { step: beat / frames_per_beat }
+    }
+}
+
+impl Clock for BeatClock {
+    fn step(&mut self, _elapsed: f32) -> f32 {
+        self.step
+    }
+}
+
+/// The steps of another clock smoothed with an exponential moving average, with `--smooth-dt`,
+/// so that jittery frame times don't make the animation stutter.
+///
+/// Over longer spans, the time still follows the other clock, only not from frame to frame. The
+/// real time between the frames is still measured without smoothing, for the frame graph.
+pub struct SmoothClock {
+    clock: Box<Clock>,
+    /// The weight of the latest step in the average.
+    weight: f32,
+    average: Option<f32>,
+}
+
+impl SmoothClock {
+    pub fn new(clock: Box<Clock>, weight: f32) -> SmoothClock {
+        SmoothClock { clock: clock, weight: weight, average: None }
+    }
+}
+
+impl Clock for SmoothClock {
+    fn step(&mut self, elapsed: f32) -> f32 {
+        let step = self.clock.step(elapsed);
+        let average = match self.average {
+            Some(average) => average + self.weight * (step - average),
+            None => step,
+        };
+
+        self.average = Some(average);
+        average
+    }
+}
+
+/// The clock configured with `--bpm`, or the real time, for frames at `frame_rate`, smoothed
+/// with `--smooth-dt`. It is stepped in every frame, including those skipped with `--frame-skip`.
+pub fn clock(config: &Config, frame_rate: f32) -> Box<Clock> {
+    let clock: Box<Clock> = match config.bpm {
+        Some(bpm) => Box::new(BeatClock::new(bpm, frame_rate, config.frame_skip)),
+        None => Box::new(RealClock),
+    };
+
+    match config.smooth_dt {
+        Some(weight) => Box::new(SmoothClock::new(clock, weight)),
+        None => clock,
+    }
+}
+
+/// How long every frame is rendered for after an input with `--frame-skip`, in seconds.
+const FULL_RATE_AFTER_INPUT: f32 = 2.0;
+
+/// Chooses the frames rendered with `--frame-skip N`: every Nth frame, or every frame for a while
+/// after an input so that it gets a quick response.
+///
+/// The real time and the steps of the clock of the skipped frames are added to the next rendered
+/// one, so that the animation keeps its speed. Each step is clamped to `MAX_DT` on its own before,
+/// so that frames that are only skipped together don't count as one that stalled.
+///
+/// The frames rendered while skipping are always every Nth since the start, also after an input,
+/// so that the beats of `BeatClock` keep landing on them.
+#[derive(Debug, Clone, Copy)]
+pub struct FrameSkip {
+    every: u32,
+    /// The number of frames since the last Nth one.
+    skipped: u32,
+    /// The real time since the last rendered frame.
+    pending: Duration,
+    /// The steps of the clock since the last rendered frame, each clamped to `MAX_DT`.
+    pending_step: f32,
+    /// The real time since the last input, in seconds.
+    since_input: f32,
+}
+
+impl FrameSkip {
+    pub fn new(config: &Config) -> FrameSkip {
+        FrameSkip {
+            every: config.frame_skip,
+            skipped: 0,
+            pending: Duration::new(0, 0),
+            pending_step: 0.0,
+            since_input: FULL_RATE_AFTER_INPUT,
+        }
+    }
+
+    pub fn input(&mut self) {
+        self.since_input = 0.0;
+    }
+
+    /// Moves on by a frame `elapsed` after the previous one, in which the clock stepped by `step`,
+    /// returning the real time since the last rendered frame and the step to advance the
+    /// simulation by with `advance_skipped`, if this one is to be rendered.
+    pub fn advance(&mut self, elapsed: Duration, step: f32) -> Option<(Duration, f32)> {
+        self.pending += elapsed;
+        self.pending_step += step.min(MAX_DT);
+        self.since_input += seconds(elapsed);
+        self.skipped = (self.skipped + 1) % self.every;
+
+        if self.skipped != 0 && self.since_input >= FULL_RATE_AFTER_INPUT {
+            return None;
+        }
+
+        Some((::std::mem::replace(&mut self.pending, Duration::new(0, 0)),
+              ::std::mem::replace(&mut self.pending_step, 0.0)))
+    }
+}
+
+/// Everything that changes the uniforms from frame to frame, advanced by the real time that has
+/// passed so that it doesn't depend on the GPU.
+///
+/// The time is accumulated from the scaled durations of the frames rather than measured from the
+/// start, so that changing the scale or pausing doesn't make it jump.
+#[derive(Debug, Clone)]
+pub struct Simulation {
+    time: f32,
+    scale: f32,
+    paused: bool,
+    /// Whether the camera ignores the input and being idle, while the time goes on.
+    camera_frozen: bool,
+    fixed: Option<f32>,
+    /// The real time since the start, see `advance_real`.
+    real_time: f32,
+}
+
+impl Simulation {
+    pub fn new(config: &Config) -> Simulation {
+        Simulation {
+            time: 0.0,
+            scale: config.time_scale,
+            paused: false,
+            camera_frozen: false,
+            fixed: config.fixed_time,
+            real_time: 0.0,
+        }
+    }
+
+    /// How many times faster than real time the time advances.
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    /// Doubles the time scale.
+    pub fn speed_up(&mut self) {
+        self.scale = (self.scale * 2.0).min(MAX_SCALE);
+    }
+
+    /// Halves the time scale.
+    pub fn slow_down(&mut self) {
+        self.scale = (self.scale / 2.0).max(MIN_SCALE);
+    }
+
+    pub fn paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    pub fn camera_frozen(&self) -> bool {
+        self.camera_frozen
+    }
+
+    pub fn toggle_camera_freeze(&mut self) {
+        self.camera_frozen = !self.camera_frozen;
+    }
+
+    /// A compact text form of the state, to be shared and restored with `restore`.
+    pub fn snapshot(&self) -> String {
+        format!("{} time={} scale={} paused={}", SNAPSHOT_PREFIX, self.time, self.scale,
+                self.paused)
+    }
+
+    /// Restores the state from the text form produced by `snapshot`.
+    ///
+    /// Unknown fields are skipped, so that snapshots of newer versions with more fields still
+    /// restore what they can. Nothing is changed if the text is invalid.
+    pub fn restore(&mut self, text: &str) -> Result<(), String> {
+        let mut words = text.split_whitespace();
+
+        if words.next() != Some(SNAPSHOT_PREFIX) {
+            return Err("the text is not a snapshot of the state".to_string());
+        }
+
+        let mut restored = self.clone();
+
+        for word in words {
+            let mut parts = word.splitn(2, '=');
+            let (key, value) = match (parts.next(), parts.next()) {
+                (Some(key), Some(value)) => (key, value),
+                _ => return Err(format!("invalid field `{}` in the snapshot", word)),
+            };
+            let invalid = || format!("invalid value `{}` of `{}` in the snapshot", value, key);
+
+            match key {
+                "time" => restored.time = value.parse().map_err(|_| invalid())?,
+                "scale" => {
+                    let scale: f32 = value.parse().map_err(|_| invalid())?;
+                    restored.scale = scale.max(MIN_SCALE).min(MAX_SCALE);
+                }
+                "paused" => restored.paused = value.parse().map_err(|_| invalid())?,
+                _ => (),
+            }
+        }
+
+        if !restored.time.is_finite() || !restored.scale.is_finite() {
+            return Err("the snapshot must have finite values".to_string());
+        }
+
+        *self = restored;
+        Ok(())
+    }
+
+    /// Advances to the next frame, `elapsed` seconds of real time after the previous one, and
+    /// returns its time.
+    pub fn advance(&mut self, elapsed: f32) -> Time {
+        self.advance_skipped(elapsed.min(MAX_DT))
+    }
+
+    /// Advances like `advance` over the frames skipped with `--frame-skip` and the next one,
+    /// `elapsed` being the sum of their times, each already clamped to `MAX_DT`.
+    pub fn advance_skipped(&mut self, elapsed: f32) -> Time {
+        let dt = if self.paused { 0.0 } else { elapsed.max(0.0) * self.scale };
+
+        self.time += dt;
+
+        match self.fixed {
+            Some(time) => Time { real_time: self.real_time, .. Time::fixed(time) },
+            None => Time { time: self.time, dt: dt, real_time: self.real_time },
+        }
+    }
+
+    /// Moves the real time on by `elapsed` seconds, whether frames are rendered or not.
+    ///
+    /// Unlike the steps of `advance`, these aren't clamped, scaled, paused or from the clock.
+    pub fn advance_real(&mut self, elapsed: f32) {
+        self.real_time += elapsed.max(0.0);
+    }
+}
+
+/// The uniforms for rendering the frame number `frame` at `time` into images of the given
+/// dimensions, shown on a monitor with the refresh rate `refresh_hz`, sampled `jitter` pixels off
+/// the centers of the pixels.
+pub fn uniforms(config: &Config, dimensions: [u32; 2], refresh_hz: f32, time: Time,
+                exposure: f32, frame: u32, jitter: [f32; 2]) -> ::vs::ty::Data {
+    let coords = config.coord_system;
+
+    ::vs::ty::Data {
+        resolution: [dimensions[0] as f32, dimensions[1] as f32],
+        jitter: jitter,
+        // Only a window has a mouse, the renderer sets it from the input.
+        mouse: [0.0; 4],
+        camera_position: coords.transform(config.camera_position),
+        time: time.time,
+        camera_direction: coords.transform(config.camera_direction().unwrap_or([1.0, 0.0, 0.0])),
+        dt: time.dt,
+        real_time: time.real_time,
+        camera_up: coords.transform(normalize(config.camera_up)),
+        fov: config.fov.to_radians(),
+        bounces: config.bounces,
+        time_of_day: time_of_day(config),
+        orthographic: config.ortho as u32,
+        ortho_size: config.ortho_size(),
+        light_direction: coords.transform(normalize(config.light_direction)),
+        light_intensity: config.light_intensity,
+        light_color: config.light_color,
+        debug_tint: config.debug_tint as u32,
+        refresh_hz: refresh_hz,
+        exposure: exposure,
+        frame: frame,
+        heatmap: config.heatmap as u32,
+        flip_y: config.flip_y as u32,
+        reference: if config.show_reference { config.reference.flags() } else { 0 },
+        ground_height: config.ground_height,
+        reference_radius: config.reference_radius,
+        reference_center: config.reference_center,
+    }
+}
+
+/// The time of day passed to the shaders, in hours: the fixed one if there is one, that of the
+/// local clock with `--time-of-day`, and -1 otherwise.
+fn time_of_day(config: &Config) -> f32 {
+    match config.fixed_time_of_day {
+        Some(hours) => hours,
+        None if config.time_of_day => {
+            let now = Local::now().time();
+
+            (now.num_seconds_from_midnight() as f32 + now.nanosecond() as f32 / 1e9) / 3600.0
+        }
+        None => -1.0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use config::CoordSystem;
+
+    fn simulation() -> Simulation {
+        Simulation::new(&Config::default())
+    }
+
+    #[test]
+    fn advances_by_the_elapsed_time() {
+        let mut simulation = simulation();
+
+        assert_eq!(simulation.advance(0.125), Time { time: 0.125, dt: 0.125, real_time: 0.0 });
+        assert_eq!(simulation.advance(0.0625), Time { time: 0.1875, dt: 0.0625, real_time: 0.0 });
+    }
+
+    #[test]
+    fn pause_and_resume_are_continuous() {
+        let mut simulation = simulation();
+
+        simulation.advance(0.125);
+        simulation.toggle_pause();
+        assert!(simulation.paused());
+        assert_eq!(simulation.advance(0.125), Time { time: 0.125, dt: 0.0, real_time: 0.0 });
+        assert_eq!(simulation.advance(0.125), Time { time: 0.125, dt: 0.0, real_time: 0.0 });
+
+        simulation.toggle_pause();
+        assert!(!simulation.paused());
+        assert_eq!(simulation.advance(0.125), Time { time: 0.25, dt: 0.125, real_time: 0.0 });
+    }
+
+    #[test]
+    fn real_time_goes_on_while_paused_and_scaled() {
+        let mut simulation = simulation();
+
+        simulation.toggle_pause();
+        simulation.speed_up();
+        simulation.advance_real(0.5);
+        simulation.advance_real(-1.0);
+
+        assert_eq!(simulation.advance(0.5), Time { time: 0.0, dt: 0.0, real_time: 0.5 });
+
+        simulation.advance_real(10.0);
+
+        assert_eq!(simulation.advance(0.0).real_time, 10.5);
+    }
+
+    #[test]
+    fn time_scale_multiplies_the_delta() {
+        let mut simulation = simulation();
+
+        simulation.speed_up();
+        assert_eq!(simulation.scale(), 2.0);
+        assert_eq!(simulation.advance(0.125), Time { time: 0.25, dt: 0.25, real_time: 0.0 });
+
+        simulation.slow_down();
+        simulation.slow_down();
+        assert_eq!(simulation.scale(), 0.5);
+        assert_eq!(simulation.advance(0.125), Time { time: 0.3125, dt: 0.0625, real_time: 0.0 });
+    }
+
+    #[test]
+    fn time_scale_is_bounded() {
+        let mut simulation = simulation();
+
+        for _ in 0 .. 10 {
+            simulation.speed_up();
+        }
+
+        assert_eq!(simulation.scale(), MAX_SCALE);
+
+        for _ in 0 .. 20 {
+            simulation.slow_down();
+        }
+
+        assert_eq!(simulation.scale(), MIN_SCALE);
+    }
+
+    #[test]
+    fn delta_is_clamped() {
+        let mut simulation = simulation();
+
+        assert_eq!(simulation.advance(10.0), Time { time: MAX_DT, dt: MAX_DT, real_time: 0.0 });
+        assert_eq!(simulation.advance(-1.0), Time { time: MAX_DT, dt: 0.0, real_time: 0.0 });
+
+        // The clamp applies to the real time, before it is scaled.
+        simulation.speed_up();
+        assert_eq!(simulation.advance(10.0),
+                   Time { time: MAX_DT * 3.0, dt: MAX_DT * 2.0, real_time: 0.0 });
+    }
+
+    #[test]
+    fn fixed_time_is_pinned() {
+        let config = Config { fixed_time: Some(4.0), .. Config::default() };
+        let mut simulation = Simulation::new(&config);
+
+        assert_eq!(simulation.advance(0.125), Time::fixed(4.0));
+        simulation.speed_up();
+        assert_eq!(simulation.advance(0.125), Time::fixed(4.0));
+    }
+
+    #[test]
+    fn beats_span_whole_frames() {
+        // A beat at 128 BPM is 28.125 frames at 60 Hz, which is rounded to 28.
+        let mut clock = BeatClock::new(128.0, 60.0, 1);
+        let beat = (0..28).map(|_| clock.step(1.0)).sum::<f32>();
+
+        assert!((beat - 60.0 / 128.0).abs() < 1e-5);
+        assert_eq!(clock.step(0.0), clock.step(0.5));
+    }
+
+    #[test]
+    fn smoothing_evens_out_jitter() {
+        let mut clock = SmoothClock::new(Box::new(RealClock), 0.25);
+
+        assert_eq!(clock.step(0.5), 0.5);
+        assert_eq!(clock.step(1.5), 0.75);
+        assert_eq!(clock.step(0.75), 0.75);
+    }
+
+    #[test]
+    fn skipped_frames_add_up() {
+        let mut frame_skip = FrameSkip::new(&Config { frame_skip: 3, .. Config::default() });
+        let frame = Duration::new(0, 100_000_000);
+
+        assert_eq!(frame_skip.advance(frame, 0.1), None);
+        assert_eq!(frame_skip.advance(frame, 0.1), None);
+        assert_eq!(frame_skip.advance(frame, 0.1).map(|(elapsed, _)| elapsed),
+                   Some(Duration::new(0, 300_000_000)));
+
+        // Right after an input, every frame is rendered.
+        frame_skip.input();
+        assert_eq!(frame_skip.advance(frame, 0.1).map(|(elapsed, _)| elapsed), Some(frame));
+        assert_eq!(frame_skip.advance(frame, 0.1).map(|(elapsed, _)| elapsed), Some(frame));
+    }
+
+    #[test]
+    fn skipped_frames_are_clamped_one_by_one() {
+        let mut frame_skip = FrameSkip::new(&Config { frame_skip: 3, .. Config::default() });
+        let frame = Duration::new(0, 125_000_000);
+
+        // Together longer than `MAX_DT`, which doesn't slow the simulation down.
+        assert_eq!(frame_skip.advance(frame, 0.125), None);
+        assert_eq!(frame_skip.advance(frame, 0.125), None);
+        assert_eq!(frame_skip.advance(frame, 0.125), Some((Duration::new(0, 375_000_000), 0.375)));
+
+        let mut simulation = simulation();
+
+        assert_eq!(simulation.advance_skipped(0.375),
+                   Time { time: 0.375, dt: 0.375, real_time: 0.0 });
+
+        // A frame that stalled is still clamped.
+        assert_eq!(frame_skip.advance(frame, 0.125), None);
+        assert_eq!(frame_skip.advance(Duration::new(1, 0), 1.0), None);
+        assert_eq!(frame_skip.advance(frame, 0.125), Some((Duration::new(1, 250_000_000), 0.5)));
+    }
+
+    #[test]
+    fn beats_land_on_the_rendered_frames() {
+        // A beat at 120 BPM is 30 frames at 60 Hz, which is rounded to 32, a multiple of 4.
+        let step = 0.5 / 32.0;
+        let mut clock = BeatClock::new(120.0, 60.0, 4);
+
+        assert_eq!(clock.step(0.0), step);
+
+        // Right after an input, the clock only advances by its step in every frame, not by those
+        // of the skipped frames too.
+        let mut frame_skip = FrameSkip::new(&Config { frame_skip: 4, .. Config::default() });
+        let frame = Duration::new(0, 16_000_000);
+
+        frame_skip.input();
+        assert_eq!(frame_skip.advance(frame, step), Some((frame, step)));
+        assert_eq!(frame_skip.advance(frame, step), Some((frame, step)));
+
+        // Skipping again, the rendered frames are still every 4th since the start.
+        assert_eq!(frame_skip.advance(Duration::new(2, 0), step), None);
+        assert_eq!(frame_skip.advance(frame, step),
+                   Some((Duration::new(2, 16_000_000), step * 2.0)));
+        assert_eq!(frame_skip.advance(frame, step), None);
+        assert_eq!(frame_skip.advance(frame, step), None);
+        assert_eq!(frame_skip.advance(frame, step), None);
+        assert_eq!(frame_skip.advance(frame, step), Some((frame * 4, step * 4.0)));
+    }
+
+    #[test]
+    fn snapshot_round_trips() {
+        let mut simulation = simulation();
+
+        simulation.advance(0.125);
+        simulation.speed_up();
+        simulation.toggle_pause();
+
+        let mut restored = simulation();
+
+        restored.restore(&simulation.snapshot()).unwrap();
+        assert_eq!(restored.snapshot(), simulation.snapshot());
+        assert_eq!(restored.scale(), 2.0);
+        assert!(restored.paused());
+        assert_eq!(restored.advance(0.125), Time { time: 0.125, dt: 0.0, real_time: 0.0 });
+    }
+
+    #[test]
+    fn invalid_snapshots_change_nothing() {
+        let mut simulation = simulation();
+
+        assert!(simulation.restore("time=1").is_err());
+        assert!(simulation.restore("submanifold time=1 scale=fast").is_err());
+        assert!(simulation.restore("submanifold time=inf").is_err());
+        assert_eq!(simulation.advance(0.125), Time { time: 0.125, dt: 0.125, real_time: 0.0 });
+
+        // Unknown fields are skipped.
+        simulation.restore("submanifold time=4 camera=0,0,0").unwrap();
+        assert_eq!(simulation.advance(0.0), Time { time: 4.0, dt: 0.0, real_time: 0.0 });
+    }
+
+    #[test]
+    fn uniforms_reflect_the_inputs() {
+        let config = Config {
+            debug_tint: true,
+            heatmap: true,
+            flip_y: true,
+            .. Config::default()
+        };
+        let time = Time { time: 2.0, dt: 0.5, real_time: 3.0 };
+        let data = uniforms(&config, [640, 480], 144.0, time, 0.25, 7, [0.25, -0.125]);
+
+        assert_eq!(data.resolution, [640.0, 480.0]);
+        assert_eq!(data.debug_tint, 1);
+        assert_eq!(data.heatmap, 1);
+        assert_eq!(data.flip_y, 1);
+        assert_eq!(data.mouse, [0.0; 4]);
+        assert_eq!(data.refresh_hz, 144.0);
+        assert_eq!(data.time, 2.0);
+        assert_eq!(data.dt, 0.5);
+        assert_eq!(data.real_time, 3.0);
+        assert_eq!(data.exposure, 0.25);
+        assert_eq!(data.frame, 7);
+        assert_eq!(data.jitter, [0.25, -0.125]);
+        assert_eq!(data.camera_position, [0.0, 0.0, 0.0]);
+        assert_eq!(data.camera_direction, [1.0, 0.0, 0.0]);
+        assert_eq!(data.camera_up, [0.0, 0.0, 1.0]);
+        assert_eq!(data.fov, 90.0f32.to_radians());
+        assert_eq!(data.reference, 0);
+    }
+
+    #[test]
+    fn light_is_normalized() {
+        let config = Config {
+            light_direction: [0.0, 3.0, 4.0],
+            light_color: [1.0, 0.5, 0.25],
+            light_intensity: 2.0,
+            .. Config::default()
+        };
+        let data = uniforms(&config, [640, 480], 60.0, Time::fixed(0.0), 1.0, 0, [0.0, 0.0]);
+
+        assert_eq!(data.light_direction, [0.0, 0.6, 0.8]);
+        assert_eq!(data.light_color, [1.0, 0.5, 0.25]);
+        assert_eq!(data.light_intensity, 2.0);
+    }
+
+    #[test]
+    fn camera_looks_at_the_target() {
+        let config = Config {
+            camera_position: [1.0, 2.0, 3.0],
+            camera_target: [1.0, 6.0, 6.0],
+            .. Config::default()
+        };
+        let data = uniforms(&config, [640, 480], 60.0, Time::fixed(0.0), 1.0, 0, [0.0, 0.0]);
+
+        assert_eq!(data.camera_position, [1.0, 2.0, 3.0]);
+        assert_eq!(data.camera_direction, [0.0, 0.8, 0.6]);
+
+        // Looking straight up leaves the orientation undefined, unless another direction is up.
+        let config = Config { camera_target: [1.0, 2.0, 4.0], .. config };
+
+        assert_eq!(config.camera_direction(), None);
+
+        let config = Config { camera_up: [-1.0, 0.0, 0.0], .. config };
+        let data = uniforms(&config, [640, 480], 60.0, Time::fixed(0.0), 1.0, 0, [0.0, 0.0]);
+
+        assert_eq!(data.camera_direction, [0.0, 0.0, 1.0]);
+        assert_eq!(data.camera_up, [-1.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn camera_is_converted_to_the_coordinate_system() {
+        let config = Config {
+            camera_position: [1.0, 2.0, 3.0],
+            camera_target: [1.0, 6.0, 6.0],
+            light_direction: [0.0, 3.0, 4.0],
+            coord_system: CoordSystem::YUp,
+            .. Config::default()
+        };
+        let data = uniforms(&config, [640, 480], 60.0, Time::fixed(0.0), 1.0, 0, [0.0, 0.0]);
+
+        assert_eq!(data.camera_position, [1.0, 3.0, -2.0]);
+        assert_eq!(data.camera_direction, [0.0, 0.6, -0.8]);
+        assert_eq!(data.light_direction, [0.0, 0.8, -0.6]);
+
+        let config = Config { coord_system: CoordSystem::YUpLeftHanded, .. config };
+        let data = uniforms(&config, [640, 480], 60.0, Time::fixed(0.0), 1.0, 0, [0.0, 0.0]);
+
+        assert_eq!(data.camera_position, [1.0, 3.0, 2.0]);
+        assert_eq!(data.camera_direction, [0.0, 0.6, 0.8]);
+    }
+
+    #[test]
+    fn time_of_day_is_fixed_or_off() {
+        let config = Config::default();
+        let data = uniforms(&config, [640, 480], 60.0, Time::fixed(0.0), 1.0, 0, [0.0, 0.0]);
+
+        assert_eq!(data.time_of_day, -1.0);
+
+        let config = Config { time_of_day: true, fixed_time_of_day: Some(18.5), .. config };
+        let data = uniforms(&config, [640, 480], 60.0, Time::fixed(0.0), 1.0, 0, [0.0, 0.0]);
+
+        assert_eq!(data.time_of_day, 18.5);
+
+        let config = Config { fixed_time_of_day: None, .. config };
+        let data = uniforms(&config, [640, 480], 60.0, Time::fixed(0.0), 1.0, 0, [0.0, 0.0]);
+
+        assert!(data.time_of_day >= 0.0 && data.time_of_day < 24.0);
+    }
+
+    #[test]
+    fn ortho_size_defaults_to_the_perspective_view_at_the_target() {
+        let config = Config { ortho: true, .. Config::default() };
+        let data = uniforms(&config, [640, 480], 60.0, Time::fixed(0.0), 1.0, 0, [0.0, 0.0]);
+
+        // 90 degrees across the diagonal, 1 unit away.
+        assert_eq!(data.orthographic, 1);
+        assert!((data.ortho_size - 2.0).abs() < 1e-5);
+
+        let config = Config { ortho_size: Some(5.0), .. config };
+        let data = uniforms(&config, [640, 480], 60.0, Time::fixed(0.0), 1.0, 0, [0.0, 0.0]);
+
+        assert_eq!(data.ortho_size, 5.0);
+    }
+}
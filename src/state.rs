@@ -0,0 +1,110 @@
+use std::iter;
+use std::mem;
+use std::sync::Arc;
+use std::time::Duration;
+
+use vulkano::buffer::Buffer;
+use vulkano::buffer::BufferUsage;
+use vulkano::buffer::CpuAccessibleBuffer;
+use vulkano::descriptor::descriptor_set::DescriptorPool;
+use vulkano::device::Device;
+use vulkano::device::Queue;
+
+use error::Error;
+use memory::MemoryUsage;
+
+/// A pair of storage buffers the fragment shader keeps its state in across frames.
+///
+/// Each frame, the shader reads the state of the previous frame from one of them and writes the
+/// new state into the other one, then they are swapped.
+pub struct State {
+    buffers: [Arc<CpuAccessibleBuffer<[f32]>>; 2],
+    /// The index of the buffer the current frame writes to.
+    current: usize,
+}
+
+impl State {
+    /// Creates both buffers with `len` floats each.
+    pub fn new(device: &Arc<Device>, queue: &Arc<Queue>, len: usize) -> Result<State, Error> {
+        Ok(State {
+            buffers: [buffer(device, queue, len)?, buffer(device, queue, len)?],
+            current: 0,
+        })
+    }
+
+    /// A buffer of the same length as the state, for a pass that reads the state of the
+    /// previous frame but mustn't write the one of the current frame, see `scratch_sets`.
+    pub fn scratch(&self, device: &Arc<Device>, queue: &Arc<Queue>)
+                   -> Result<Arc<CpuAccessibleBuffer<[f32]>>, Error> {
+        buffer(device, queue, self.buffers[0].size() / mem::size_of::<f32>())
+    }
+
+    /// The descriptor sets of both frame parities, the one of the current frame is at the index
+    /// `current()`.
+    pub fn sets(&self, descriptor_pool: &Arc<DescriptorPool>,
+                pipeline_layout: &Arc<::pipeline_layout::CustomPipeline>,
+                uniform_buffer: &Arc<CpuAccessibleBuffer<::vs::ty::Data>>)
+                -> [Arc<::pipeline_layout::set0::Set>; 2] {
+        let set = |current: usize| {
+            ::pipeline_layout::set0::Set::new(descriptor_pool, pipeline_layout,
+                                              &::pipeline_layout::set0::Descriptors {
+                                                  uniforms: uniform_buffer,
+                                                  previous_state: &self.buffers[1 - current],
+                                                  state: &self.buffers[current]
+                                              })
+        };
+
+        [set(0), set(1)]
+    }
+
+    /// The descriptor sets of both frame parities as `sets` makes them, except that the state
+    /// is written into `scratch`, so that the pass sees the same state as the frame without
+    /// advancing it a second time.
+    pub fn scratch_sets(&self, descriptor_pool: &Arc<DescriptorPool>,
+                        pipeline_layout: &Arc<::pipeline_layout::CustomPipeline>,
+                        uniform_buffer: &Arc<CpuAccessibleBuffer<::vs::ty::Data>>,
+                        scratch: &Arc<CpuAccessibleBuffer<[f32]>>)
+                        -> [Arc<::pipeline_layout::set0::Set>; 2] {
+        let set = |current: usize| {
+            ::pipeline_layout::set0::Set::new(descriptor_pool, pipeline_layout,
+                                              &::pipeline_layout::set0::Descriptors {
+                                                  uniforms: uniform_buffer,
+                                                  previous_state: &self.buffers[1 - current],
+                                                  state: scratch
+                                              })
+        };
+
+        [set(0), set(1)]
+    }
+
+    /// Adds both buffers to `usage`.
+    pub fn memory_usage(&self, usage: &mut MemoryUsage) {
+        usage.buffer("state buffers", 2, self.buffers[0].size());
+    }
+
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    /// Makes the state written in the current frame the previous one of the next frame.
+    pub fn swap(&mut self) {
+        self.current = 1 - self.current;
+    }
+
+    /// Clears both buffers, blocking until the GPU is done with them.
+    pub fn reset(&self) {
+        for buffer in &self.buffers {
+            for value in buffer.write(Duration::new(1, 0)).unwrap().iter_mut() {
+                *value = 0.0;
+            }
+        }
+    }
+}
+
+/// A buffer of `len` zeroed floats.
+fn buffer(device: &Arc<Device>, queue: &Arc<Queue>, len: usize)
+          -> Result<Arc<CpuAccessibleBuffer<[f32]>>, Error> {
+    CpuAccessibleBuffer::from_iter(device, &BufferUsage::all(), Some(queue.family()),
+                                   iter::repeat(0.0).take(len))
+        .map_err(|err| Error::OutOfMemory("the state buffer", err))
+}
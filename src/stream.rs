@@ -0,0 +1,146 @@
+use std::io::Read;
+use std::io::Write;
+use std::net::TcpListener;
+use std::net::TcpStream;
+use std::sync::Arc;
+use std::sync::Condvar;
+use std::sync::Mutex;
+use std::thread;
+
+use image;
+
+/// The quality the frames are encoded with, from 1 to 100.
+const JPEG_QUALITY: u8 = 80;
+
+/// The boundary between the frames of the multipart response.
+const BOUNDARY: &'static str = "frame";
+
+/// A frame read back from the GPU, in RGBA.
+struct Frame {
+    dimensions: [u32; 2],
+    pixels: Vec<u8>,
+}
+
+/// The most recent frame, along with how many frames were published before it.
+///
+/// Only the latest frame is kept: a client that is slower than the renderer skips the frames it
+/// had no time to receive, instead of the renderer waiting for it.
+struct Latest {
+    frame: Mutex<(u64, Option<Arc<Frame>>)>,
+    published: Condvar,
+}
+
+/// Serves the frames shown in the window over HTTP as an MJPEG stream, which browsers show like
+/// a video, for `--stream-port`.
+///
+/// Each client is served on a thread of its own, which encodes the frames as JPEG, so that
+/// neither the network nor the encoding ever blocks the rendering.
+pub struct Stream {
+    latest: Arc<Latest>,
+}
+
+impl Stream {
+    /// Starts listening on `port` of all interfaces, in the background.
+    pub fn serve(port: u16) -> Result<Stream, String> {
+        let listener = TcpListener::bind(("0.0.0.0", port))
+            .map_err(|err| format!("failed to listen on port {}: {}", port, err))?;
+        let latest = Arc::new(Latest {
+            frame: Mutex::new((0, None)),
+            published: Condvar::new(),
+        });
+
+        info!("streaming the frames at http://localhost:{}/", port);
+
+        let shared = latest.clone();
+
+        thread::spawn(move || {
+            for client in listener.incoming() {
+                let client = match client {
+                    Ok(client) => client,
+                    Err(err) => {
+                        warn!("failed to accept a client of the stream: {}", err);
+                        continue;
+                    }
+                };
+                let latest = shared.clone();
+
+                thread::spawn(move || {
+                    let peer = client.peer_addr()
+                        .map(|address| address.to_string())
+                        .unwrap_or_else(|_| "a client".to_string());
+
+                    info!("{} connected to the stream", peer);
+
+                    if let Err(err) = serve_client(client, &latest) {
+                        info!("{} disconnected from the stream: {}", peer, err);
+                    }
+                });
+            }
+        });
+
+        Ok(Stream { latest: latest })
+    }
+
+    /// Makes `pixels` in RGBA of `dimensions` the frame sent to the clients next, replacing the
+    /// previous one if they haven't all received it yet.
+    pub fn publish(&self, dimensions: [u32; 2], pixels: Vec<u8>) {
+        let mut latest = self.latest.frame.lock().unwrap();
+
+        latest.0 += 1;
+        latest.1 = Some(Arc::new(Frame {
+            dimensions: dimensions,
+            pixels: pixels,
+        }));
+        self.latest.published.notify_all();
+    }
+}
+
+/// Answers the request of `client` with the stream, then sends it every frame published once it
+/// has received the previous one, until it disconnects.
+fn serve_client(mut client: TcpStream, latest: &Latest) -> Result<(), String> {
+    // Whatever was requested, it is answered with the stream, so only the start of the request
+    // is read to let the client send it.
+    let mut request = [0; 1024];
+    client.read(&mut request).map_err(|err| err.to_string())?;
+
+    write!(client, "HTTP/1.0 200 OK\r\n\
+                    Cache-Control: no-cache\r\n\
+                    Connection: close\r\n\
+                    Content-Type: multipart/x-mixed-replace; boundary={}\r\n\r\n", BOUNDARY)
+        .map_err(|err| err.to_string())?;
+
+    let mut sent = 0;
+
+    loop {
+        let frame = {
+            let mut frame = latest.frame.lock().unwrap();
+
+            while frame.0 == sent {
+                frame = latest.published.wait(frame).unwrap();
+            }
+
+            sent = frame.0;
+            frame.1.clone().expect("a frame was published")
+        };
+
+        let jpeg = encode(&frame)?;
+
+        write!(client, "--{}\r\nContent-Type: image/jpeg\r\nContent-Length: {}\r\n\r\n",
+               BOUNDARY, jpeg.len())
+            .and_then(|_| client.write_all(&jpeg))
+            .and_then(|_| client.write_all(b"\r\n"))
+            .map_err(|err| err.to_string())?;
+    }
+}
+
+/// `frame` as a JPEG, which has no alpha.
+fn encode(frame: &Frame) -> Result<Vec<u8>, String> {
+    let rgb = frame.pixels.chunks(4).flat_map(|pixel| pixel[.. 3].to_vec()).collect::<Vec<_>>();
+    let mut jpeg = Vec::new();
+
+    image::jpeg::JPEGEncoder::new_with_quality(&mut jpeg, JPEG_QUALITY)
+        .encode(&rgb, frame.dimensions[0], frame.dimensions[1], image::RGB(8))
+        .map_err(|err| format!("failed to encode a frame: {}", err))?;
+
+    Ok(jpeg)
+}
@@ -0,0 +1,178 @@
+/// The width and height of a glyph of `FONT`, in pixels.
+pub const GLYPH_SIZE: [u32; 2] = [5, 7];
+
+/// The space left around the timecode, in pixels.
+const TIMECODE_MARGIN: u32 = 2;
+
+/// A 5x7 font, each glyph a column per byte from the left with the top row in the lowest bit.
+/// Text is written in upper case, characters missing here as `?`.
+const FONT: &'static [(char, [u8; 5])] = &[
+    (' ', [0x00, 0x00, 0x00, 0x00, 0x00]),
+    ('(', [0x00, 0x1C, 0x22, 0x41, 0x00]),
+    (')', [0x00, 0x41, 0x22, 0x1C, 0x00]),
+    ('+', [0x08, 0x08, 0x3E, 0x08, 0x08]),
+    (',', [0x00, 0x50, 0x30, 0x00, 0x00]),
+    ('-', [0x08, 0x08, 0x08, 0x08, 0x08]),
+    ('.', [0x00, 0x60, 0x60, 0x00, 0x00]),
+    ('/', [0x20, 0x10, 0x08, 0x04, 0x02]),
+    ('0', [0x3E, 0x51, 0x49, 0x45, 0x3E]),
+    ('1', [0x00, 0x42, 0x7F, 0x40, 0x00]),
+    ('2', [0x42, 0x61, 0x51, 0x49, 0x46]),
+    ('3', [0x21, 0x41, 0x45, 0x4B, 0x31]),
+    ('4', [0x18, 0x14, 0x12, 0x7F, 0x10]),
+    ('5', [0x27, 0x45, 0x45, 0x45, 0x39]),
+    ('6', [0x3C, 0x4A, 0x49, 0x49, 0x30]),
+    ('7', [0x01, 0x71, 0x09, 0x05, 0x03]),
+    ('8', [0x36, 0x49, 0x49, 0x49, 0x36]),
+    ('9', [0x06, 0x49, 0x49, 0x29, 0x1E]),
+    (':', [0x00, 0x36, 0x36, 0x00, 0x00]),
+    ('?', [0x02, 0x01, 0x51, 0x09, 0x06]),
+    ('A', [0x7C, 0x12, 0x11, 0x12, 0x7C]),
+    ('B', [0x7F, 0x49, 0x49, 0x49, 0x36]),
+    ('C', [0x3E, 0x41, 0x41, 0x41, 0x22]),
+    ('D', [0x7F, 0x41, 0x41, 0x22, 0x1C]),
+    ('E', [0x7F, 0x49, 0x49, 0x49, 0x41]),
+    ('F', [0x7F, 0x09, 0x09, 0x09, 0x01]),
+    ('G', [0x3E, 0x41, 0x49, 0x49, 0x7A]),
+    ('H', [0x7F, 0x08, 0x08, 0x08, 0x7F]),
+    ('I', [0x00, 0x41, 0x7F, 0x41, 0x00]),
+    ('J', [0x20, 0x40, 0x41, 0x3F, 0x01]),
+    ('K', [0x7F, 0x08, 0x14, 0x22, 0x41]),
+    ('L', [0x7F, 0x40, 0x40, 0x40, 0x40]),
+    ('M', [0x7F, 0x02, 0x0C, 0x02, 0x7F]),
+    ('N', [0x7F, 0x04, 0x08, 0x10, 0x7F]),
+    ('O', [0x3E, 0x41, 0x41, 0x41, 0x3E]),
+    ('P', [0x7F, 0x09, 0x09, 0x09, 0x06]),
+    ('Q', [0x3E, 0x41, 0x51, 0x21, 0x5E]),
+    ('R', [0x7F, 0x09, 0x19, 0x29, 0x46]),
+    ('S', [0x46, 0x49, 0x49, 0x49, 0x31]),
+    ('T', [0x01, 0x01, 0x7F, 0x01, 0x01]),
+    ('U', [0x3F, 0x40, 0x40, 0x40, 0x3F]),
+    ('V', [0x1F, 0x20, 0x40, 0x20, 0x1F]),
+    ('W', [0x3F, 0x40, 0x38, 0x40, 0x3F]),
+    ('X', [0x63, 0x14, 0x08, 0x14, 0x63]),
+    ('Y', [0x07, 0x08, 0x70, 0x08, 0x07]),
+    ('Z', [0x61, 0x51, 0x49, 0x45, 0x43]),
+    ('_', [0x40, 0x40, 0x40, 0x40, 0x40]),
+    ('`', [0x00, 0x01, 0x02, 0x04, 0x00]),
+];
+
+/// An RGBA image text can be written into.
+pub struct Canvas {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Canvas {
+    pub fn new(width: u32, height: u32) -> Canvas {
+        // Opaque black, which the labels are written on.
+        let pixels = [0, 0, 0, 255].iter().cloned().cycle().take((width * height * 4) as usize)
+            .collect();
+
+        Canvas { width: width, height: height, pixels: pixels }
+    }
+
+    /// Wraps the RGBA `pixels` of an image of `width` by `height`, row by row.
+    pub fn from_pixels(width: u32, height: u32, pixels: Vec<u8>) -> Canvas {
+        assert_eq!(pixels.len(), (width * height * 4) as usize);
+
+        Canvas { width: width, height: height, pixels: pixels }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    pub fn into_pixels(self) -> Vec<u8> {
+        self.pixels
+    }
+
+    fn set(&mut self, x: u32, y: u32, pixel: &[u8]) {
+        if x < self.width && y < self.height {
+            let offset = ((y * self.width + x) * 4) as usize;
+
+            self.pixels[offset .. offset + 4].copy_from_slice(pixel);
+        }
+    }
+
+    /// Copies the RGBA `pixels` of an image of `dimensions` with its top-left corner at `x`, `y`.
+    pub fn blit(&mut self, x: u32, y: u32, dimensions: [u32; 2], pixels: &[u8]) {
+        for row in 0 .. dimensions[1] {
+            for column in 0 .. dimensions[0] {
+                let offset = ((row * dimensions[0] + column) * 4) as usize;
+
+                self.set(x + column, y + row, &pixels[offset .. offset + 4]);
+            }
+        }
+    }
+
+    /// Fills the rectangle of `dimensions` with its top-left corner at `x`, `y` with `pixel`.
+    pub fn fill(&mut self, x: u32, y: u32, dimensions: [u32; 2], pixel: &[u8]) {
+        for row in 0 .. dimensions[1] {
+            for column in 0 .. dimensions[0] {
+                self.set(x + column, y + row, pixel);
+            }
+        }
+    }
+
+    /// Writes `text` in white with its top-left corner at `x`, `y`, cut off at `max_width`.
+    pub fn write(&mut self, x: u32, y: u32, max_width: u32, text: &str) {
+        rasterize(x, y, max_width, text, |x, y| self.set(x, y, &[255, 255, 255, 255]));
+    }
+
+    /// Writes the frame number `frame` and the time `time` in seconds at the bottom-left, on
+    /// black so that they are readable over any scene, to tell dropped or duplicated frames apart
+    /// in a video assembled from captures.
+    pub fn write_timecode(&mut self, frame: u32, time: f32) {
+        let text = timecode(frame, time);
+        let width = (GLYPH_SIZE[0] + 1) * text.len() as u32 - 1 + 2 * TIMECODE_MARGIN;
+        let height = GLYPH_SIZE[1] + 2 * TIMECODE_MARGIN;
+        let top = self.height.saturating_sub(height);
+
+        self.fill(0, top, [width, height], &[0, 0, 0, 255]);
+        self.write(TIMECODE_MARGIN, top + TIMECODE_MARGIN, self.width, &text);
+    }
+}
+
+/// Calls `pixel` with the position of each pixel set by `text` written with its top-left corner
+/// at `x`, `y`, cut off at `max_width`, glyph by glyph and column by column from the top.
+pub fn rasterize<F: FnMut(u32, u32)>(x: u32, y: u32, max_width: u32, text: &str, mut pixel: F) {
+    let advance = GLYPH_SIZE[0] + 1;
+    let fitting = (max_width / advance) as usize;
+
+    for (index, character) in text.to_uppercase().chars().take(fitting).enumerate() {
+        let columns = FONT.iter()
+            .find(|&&(glyph, _)| glyph == character)
+            .or_else(|| FONT.iter().find(|&&(glyph, _)| glyph == '?'))
+            .map(|&(_, columns)| columns)
+            .unwrap_or([0; 5]);
+        let left = x + index as u32 * advance;
+
+        for (column, bits) in columns.iter().enumerate() {
+            for row in 0 .. GLYPH_SIZE[1] {
+                if bits & (1 << row) != 0 {
+                    pixel(left + column as u32, y + row);
+                }
+            }
+        }
+    }
+}
+
+/// Formats the frame number `frame` and the time `time` in seconds as hours, minutes, seconds and
+/// milliseconds, negative times with a leading `-`.
+pub fn timecode(frame: u32, time: f32) -> String {
+    let sign = if time < 0.0 { "-" } else { "" };
+    let milliseconds = (time.abs() as f64 * 1000.0).round() as u64;
+
+    format!("FRAME {} {}{:02}:{:02}:{:02}.{:03}", frame, sign, milliseconds / 3_600_000,
+            milliseconds / 60_000 % 60, milliseconds / 1000 % 60, milliseconds % 1000)
+}
@@ -0,0 +1,285 @@
+use std::ffi::CStr;
+use std::sync::Arc;
+
+use vulkano::buffer::CpuAccessibleBuffer;
+use vulkano::command_buffer::DynamicState;
+use vulkano::command_buffer::PrimaryCommandBufferBuilderInlineDraw;
+use vulkano::descriptor::descriptor_set::DescriptorPool;
+use vulkano::device::Device;
+use vulkano::device::Queue;
+use vulkano::format::Format;
+use vulkano::framebuffer::Framebuffer;
+use vulkano::framebuffer::Subpass;
+use vulkano::image::Dimensions;
+use vulkano::image::StorageImage;
+use vulkano::pipeline::GraphicsPipeline;
+use vulkano::pipeline::GraphicsPipelineParams;
+use vulkano::pipeline::blend::Blend;
+use vulkano::pipeline::depth_stencil::DepthStencil;
+use vulkano::pipeline::input_assembly::InputAssembly;
+use vulkano::pipeline::multisample::Multisample;
+use vulkano::pipeline::shader::GraphicsShaderType;
+use vulkano::pipeline::vertex::SingleBufferDefinition;
+use vulkano::pipeline::viewport::ViewportsState;
+use vulkano::pipeline::viewport::Viewport;
+use vulkano::pipeline::viewport::Scissor;
+use vulkano::sampler::Filter;
+use vulkano::sampler::MipmapMode;
+use vulkano::sampler::Sampler;
+use vulkano::sampler::SamplerAddressMode;
+
+use config::Config;
+use error::Error;
+use memory::MemoryUsage;
+use render_pass::CustomRenderPass;
+use Vertex;
+
+/// The format of the offscreen image the scene is rendered into.
+const FORMAT: Format = Format::R8G8B8A8Unorm;
+
+pub mod blit_layout {
+    pipeline_layout! {
+        set0: {
+            source: CombinedImageSampler
+        }
+    }
+}
+
+pub type BlitPipeline = GraphicsPipeline<SingleBufferDefinition<Vertex>,
+                                        blit_layout::CustomPipeline, CustomRenderPass>;
+type BlitLayout = Arc<blit_layout::CustomPipeline>;
+
+/// Renders the scene into an offscreen image at a fraction of the resolution of the swapchain,
+/// then scales it up to fill the swapchain image.
+pub struct Upscaler {
+    dimensions: [u32; 2],
+    image: Arc<StorageImage<Format>>,
+    render_pass: Arc<CustomRenderPass>,
+    framebuffer: Arc<Framebuffer<CustomRenderPass>>,
+    pipeline: Arc<BlitPipeline>,
+    set: Arc<blit_layout::set0::Set>,
+}
+
+impl Upscaler {
+    /// Creates the offscreen image for a swapchain with images of `target_dimensions`, to be
+    /// drawn into with `target_render_pass`.
+    pub fn new(device: &Arc<Device>, queue: &Arc<Queue>, descriptor_pool: &Arc<DescriptorPool>,
+               target_render_pass: &Arc<CustomRenderPass>, target_dimensions: [u32; 2],
+               config: &Config) -> Result<Upscaler, Error> {
+        let dimensions = scene_dimensions(target_dimensions, config);
+
+        let image = StorageImage::new(device,
+                                      Dimensions::Dim2d {
+                                          width: dimensions[0],
+                                          height: dimensions[1],
+                                      },
+                                      FORMAT, Some(queue.family()))
+            .map_err(|err| Error::OutOfMemory("the offscreen image", err))?;
+
+        let render_pass = CustomRenderPass::new(device, &::render_pass::Formats {
+            color: (FORMAT, 1)
+        }).map_err(|err| Error::RenderPass("the offscreen render pass", err))?;
+
+        let framebuffer = Framebuffer::new(&render_pass, [dimensions[0], dimensions[1], 1],
+                                           ::render_pass::AList {
+                                               color: &image
+                                           })
+            .map_err(|err| Error::Framebuffer("the offscreen framebuffer", err))?;
+
+        let sampler = create_sampler(device, config.scale_filter)?;
+        let (pipeline_layout, pipeline) = create_blit_pipeline(device, config, target_render_pass,
+                                                               target_dimensions, dimensions)?;
+
+        let set = blit_layout::set0::Set::new(descriptor_pool, &pipeline_layout,
+                                              &blit_layout::set0::Descriptors {
+                                                  source: (&sampler, &image)
+                                              });
+
+        Ok(Upscaler {
+            dimensions: dimensions,
+            image: image,
+            render_pass: render_pass,
+            framebuffer: framebuffer,
+            pipeline: pipeline,
+            set: set,
+        })
+    }
+
+    /// The dimensions the scene is rendered at.
+    pub fn dimensions(&self) -> [u32; 2] {
+        self.dimensions
+    }
+
+    /// Adds the offscreen image to `usage`.
+    pub fn memory_usage(&self, usage: &mut MemoryUsage) {
+        usage.image("offscreen image", 1, self.dimensions, FORMAT);
+    }
+
+    /// The offscreen image the scene is rendered into.
+    pub fn image(&self) -> &Arc<StorageImage<Format>> {
+        &self.image
+    }
+
+    /// The render pass the scene has to be drawn with.
+    pub fn render_pass(&self) -> &Arc<CustomRenderPass> {
+        &self.render_pass
+    }
+
+    /// The framebuffer the scene has to be drawn into.
+    pub fn framebuffer(&self) -> &Arc<Framebuffer<CustomRenderPass>> {
+        &self.framebuffer
+    }
+
+    /// Draws the scaled up scene, within a render pass on the swapchain image.
+    pub fn draw(&self, builder: PrimaryCommandBufferBuilderInlineDraw,
+                vertex_buffer: &Arc<CpuAccessibleBuffer<[Vertex]>>)
+                -> PrimaryCommandBufferBuilderInlineDraw {
+        builder.draw(&self.pipeline, vertex_buffer, &DynamicState::none(), &self.set, &())
+    }
+}
+
+/// The dimensions the scene is rendered at offscreen, to be scaled to `target_dimensions`.
+///
+/// These are the internal resolution if there is one, and 1/`config.pixel_scale` of the target
+/// otherwise.
+pub fn scene_dimensions(target_dimensions: [u32; 2], config: &Config) -> [u32; 2] {
+    match config.internal_res {
+        Some(dimensions) => dimensions,
+        None => {
+            // Rounded up, so that the scaled image covers the whole target image.
+            let scale = config.pixel_scale;
+
+            [(target_dimensions[0] + scale - 1) / scale,
+             (target_dimensions[1] + scale - 1) / scale]
+        }
+    }
+}
+
+/// The viewport the scene of `source_dimensions` is scaled into within the target image.
+///
+/// An internal resolution is scaled to fit, keeping its aspect ratio and leaving bars at either
+/// the sides or the top and bottom. Otherwise, the scene covers the whole target.
+fn blit_viewport(config: &Config, target_dimensions: [u32; 2], source_dimensions: [u32; 2])
+                 -> Viewport {
+    let target = [target_dimensions[0] as f32, target_dimensions[1] as f32];
+
+    let dimensions = if config.internal_res.is_some() {
+        let scale = (target[0] / source_dimensions[0] as f32)
+            .min(target[1] / source_dimensions[1] as f32);
+
+        [source_dimensions[0] as f32 * scale, source_dimensions[1] as f32 * scale]
+    } else {
+        target
+    };
+
+    Viewport {
+        origin: [(target[0] - dimensions[0]) / 2.0, (target[1] - dimensions[1]) / 2.0],
+        depth_range: 0.0 .. 1.0,
+        dimensions: dimensions,
+    }
+}
+
+/// Maps `position` in pixels of the image of `target_dimensions` to the pixels of the scene of
+/// `source_dimensions` drawn into it, see `blit_viewport`.
+pub fn to_scene(config: &Config, target_dimensions: [u32; 2], source_dimensions: [u32; 2],
+                position: [f32; 2]) -> [f32; 2] {
+    let viewport = blit_viewport(config, target_dimensions, source_dimensions);
+
+    [(position[0] - viewport.origin[0]) / viewport.dimensions[0] * source_dimensions[0] as f32,
+     (position[1] - viewport.origin[1]) / viewport.dimensions[1] * source_dimensions[1] as f32]
+}
+
+/// Creates a sampler for scaling an image with `filter`, normally the one of `--scale-filter`.
+pub fn create_sampler(device: &Arc<Device>, filter: Filter) -> Result<Arc<Sampler>, Error> {
+    Sampler::new(device, filter, filter, MipmapMode::Nearest,
+                 SamplerAddressMode::ClampToEdge, SamplerAddressMode::ClampToEdge,
+                 SamplerAddressMode::ClampToEdge, 0.0, 1.0, 0.0, 0.0)
+        .map_err(Error::Sampler)
+}
+
+/// Creates the pipeline that copies a sampled image of `source_dimensions` into the image of
+/// `target_dimensions` drawn into with `target_render_pass`, see `blit_viewport`.
+pub fn create_blit_pipeline(device: &Arc<Device>, config: &Config,
+                            target_render_pass: &Arc<CustomRenderPass>,
+                            target_dimensions: [u32; 2], source_dimensions: [u32; 2])
+                            -> Result<(BlitLayout, Arc<BlitPipeline>), Error> {
+    blit_pipeline(device, config, target_render_pass, target_dimensions, source_dimensions,
+                  BlitFragment::Colors, Blend::pass_through())
+}
+
+/// Like `create_blit_pipeline`, but blends the sampled image over the target by its alpha.
+pub fn create_overlay_pipeline(device: &Arc<Device>, config: &Config,
+                               target_render_pass: &Arc<CustomRenderPass>,
+                               target_dimensions: [u32; 2], source_dimensions: [u32; 2])
+                               -> Result<(BlitLayout, Arc<BlitPipeline>), Error> {
+    blit_pipeline(device, config, target_render_pass, target_dimensions, source_dimensions,
+                  BlitFragment::Colors, Blend::alpha_blending())
+}
+
+/// Like `create_blit_pipeline`, but for an image of ids in an unsigned integer format, see
+/// `id_fs.glsl`. It has to be sampled without filtering.
+pub fn create_id_pipeline(device: &Arc<Device>, config: &Config,
+                          target_render_pass: &Arc<CustomRenderPass>,
+                          target_dimensions: [u32; 2], source_dimensions: [u32; 2])
+                          -> Result<(BlitLayout, Arc<BlitPipeline>), Error> {
+    blit_pipeline(device, config, target_render_pass, target_dimensions, source_dimensions,
+                  BlitFragment::Ids, Blend::pass_through())
+}
+
+/// The fragment shader a blit pipeline samples the source image with.
+enum BlitFragment {
+    /// `blit_fs.glsl`, copying the colors.
+    Colors,
+    /// `id_fs.glsl`, showing the ids in colors.
+    Ids,
+}
+
+fn blit_pipeline(device: &Arc<Device>, config: &Config, target_render_pass: &Arc<CustomRenderPass>,
+                 target_dimensions: [u32; 2], source_dimensions: [u32; 2],
+                 fragment: BlitFragment, blend: Blend)
+                 -> Result<(BlitLayout, Arc<BlitPipeline>), Error> {
+    let vs = ::blit_vs::Shader::load(device)
+        .map_err(|err| Error::OutOfMemory("the vertex shader module", err))?;
+    let module = match fragment {
+        BlitFragment::Colors => ::blit_fs::Shader::load(device).map(|fs| fs.module().clone()),
+        BlitFragment::Ids => ::id_fs::Shader::load(device).map(|fs| fs.module().clone()),
+    }.map_err(|err| Error::OutOfMemory("the fragment shader module", err))?;
+
+    // Both shaders have the interface of `blit_fs.glsl`, `id_fs.glsl` only samples an unsigned
+    // integer image instead of a float one, which is the same descriptor.
+    let fs = unsafe {
+        module.graphics_entry_point(CStr::from_bytes_with_nul_unchecked(b"main\0"),
+                                    ::blit_fs::MainInput, ::blit_fs::MainOutput,
+                                    ::blit_fs::Layout, GraphicsShaderType::Fragment)
+    };
+
+    let pipeline_layout = blit_layout::CustomPipeline::new(device)
+        .map_err(|err| Error::OutOfMemory("the pipeline layout", err))?;
+
+    let pipeline = GraphicsPipeline::new(device, GraphicsPipelineParams {
+        vertex_input: SingleBufferDefinition::new(),
+        vertex_shader: vs.main_entry_point(),
+        input_assembly: InputAssembly {
+            // The same geometry as the scene is drawn with.
+            topology: config.coverage.topology(),
+            primitive_restart_enable: false,
+        },
+        tessellation: None,
+        geometry_shader: None,
+        viewport: ViewportsState::Fixed {
+            data: vec![(
+                blit_viewport(config, target_dimensions, source_dimensions),
+                Scissor::irrelevant()
+            )],
+        },
+        raster: Default::default(),
+        multisample: Multisample::disabled(),
+        fragment_shader: fs,
+        depth_stencil: DepthStencil::disabled(),
+        blend: blend,
+        layout: &pipeline_layout,
+        render_pass: Subpass::from(target_render_pass, 0).ok_or_else(Error::no_subpass)?,
+    }).map_err(|err| Error::GraphicsPipeline("the blit pipeline", err))?;
+
+    Ok((pipeline_layout, pipeline))
+}
@@ -0,0 +1,69 @@
+use std::sync::Arc;
+use std::vec;
+
+use vulkano::buffer::Buffer;
+use vulkano::pipeline::vertex::AttributeInfo;
+use vulkano::pipeline::vertex::Definition;
+use vulkano::pipeline::vertex::IncompatibleVertexDefinitionError;
+use vulkano::pipeline::vertex::InputRate;
+use vulkano::pipeline::vertex::SingleBufferDefinition;
+use vulkano::pipeline::vertex::Source;
+
+use shader::VertexShader;
+use Vertex;
+
+/// The number of vertices of the triangle covering the screen that `fullscreen_vs.glsl`
+/// generates.
+const GENERATED_VERTICES: usize = 3;
+
+/// The vertex input of the pipeline of the scene: the positions in the vertex buffer, or nothing
+/// for a vertex shader that generates them from the index of the vertex.
+///
+/// The scene is drawn with the same vertex buffer either way, so that the pipeline has a single
+/// type. When the positions are generated, the buffer isn't bound and `GENERATED_VERTICES` are
+/// drawn instead of its length.
+pub struct SceneInput {
+    positions: SingleBufferDefinition<Vertex>,
+    generated: bool,
+}
+
+impl SceneInput {
+    /// The vertex input `vs` reads.
+    pub fn new(vs: &VertexShader) -> SceneInput {
+        SceneInput {
+            positions: SingleBufferDefinition::new(),
+            generated: vs.generates_positions(),
+        }
+    }
+}
+
+unsafe impl<I> Definition<I> for SceneInput
+    where SingleBufferDefinition<Vertex>: Definition<I>
+{
+    type BuffersIter = vec::IntoIter<(u32, usize, InputRate)>;
+    type AttribsIter = vec::IntoIter<(u32, u32, AttributeInfo)>;
+
+    fn definition(&self, interface: &I)
+                  -> Result<(Self::BuffersIter, Self::AttribsIter),
+                            IncompatibleVertexDefinitionError> {
+        if self.generated {
+            return Ok((Vec::new().into_iter(), Vec::new().into_iter()));
+        }
+
+        let (buffers, attributes) = self.positions.definition(interface)?;
+
+        Ok((buffers.collect::<Vec<_>>().into_iter(), attributes.collect::<Vec<_>>().into_iter()))
+    }
+}
+
+unsafe impl<L> Source<L> for SceneInput
+    where SingleBufferDefinition<Vertex>: Source<L>
+{
+    fn decode<'l>(&self, source: &'l L) -> (Vec<&'l Arc<Buffer>>, usize, usize) {
+        if self.generated {
+            (Vec::new(), GENERATED_VERTICES, 1)
+        } else {
+            self.positions.decode(source)
+        }
+    }
+}
@@ -0,0 +1,166 @@
+use std::path::PathBuf;
+
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use serde_json;
+
+use config::Config;
+use config::CoordSystem;
+use simulation::Simulation;
+
+/// What view strings start with, followed by the version of their format and a dot.
+const PREFIX: &'static str = "smv";
+
+/// The version of the format of the view strings made by this version, increased whenever the
+/// fields change so that an older string is never read as something else.
+const VERSION: u32 = 3;
+
+/// Everything that determines what the window shows, encoded into a compact string that can be
+/// pasted into an issue or a chat and passed back with `--state` to see exactly the same.
+///
+/// The shaders are referred to by their paths, which have to be the same on the other machine.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct View {
+    camera_position: [f32; 3],
+    camera_target: [f32; 3],
+    camera_up: [f32; 3],
+    coord_system: CoordSystem,
+    fov: f32,
+    ortho: bool,
+    ortho_size: Option<f32>,
+    light_direction: [f32; 3],
+    light_color: [f32; 3],
+    light_intensity: f32,
+    exposure: f32,
+    bounces: u32,
+    heatmap: bool,
+    flip_y: bool,
+    frag: Option<PathBuf>,
+    spirv_frag: Option<PathBuf>,
+    frag_entry: String,
+    defines: Vec<(String, String)>,
+    /// The time, its speed and whether it is paused, see `Simulation::snapshot`.
+    simulation: String,
+}
+
+impl View {
+    /// The view rendered with `config`, as the renderer has it, at the time of `simulation`.
+    pub fn capture(config: &Config, simulation: &Simulation) -> View {
+        View {
+            camera_position: config.camera_position,
+            camera_target: config.camera_target,
+            camera_up: config.camera_up,
+            coord_system: config.coord_system,
+            fov: config.fov,
+            ortho: config.ortho,
+            ortho_size: config.ortho_size,
+            light_direction: config.light_direction,
+            light_color: config.light_color,
+            light_intensity: config.light_intensity,
+            exposure: config.exposure,
+            bounces: config.bounces,
+            heatmap: config.heatmap,
+            flip_y: config.flip_y,
+            frag: config.frag.clone(),
+            spirv_frag: config.spirv_frag.clone(),
+            frag_entry: config.frag_entry.clone(),
+            defines: config.defines.clone(),
+            simulation: simulation.snapshot(),
+        }
+    }
+
+    /// The view string, `smv3.` followed by the view as JSON in URL-safe base64.
+    pub fn encode(&self) -> String {
+        let json = serde_json::to_string(self).expect("views can always be serialized");
+
+        format!("{}{}.{}", PREFIX, VERSION, URL_SAFE_NO_PAD.encode(json))
+    }
+
+    /// Reads a view string made by `encode`.
+    pub fn decode(text: &str) -> Result<View, String> {
+        let invalid = || "the state is not a view string".to_string();
+        let rest = text.trim();
+
+        if !rest.starts_with(PREFIX) {
+            return Err(invalid());
+        }
+
+        let mut parts = rest[PREFIX.len() ..].splitn(2, '.');
+        let (version, encoded) = match (parts.next(), parts.next()) {
+            (Some(version), Some(encoded)) => (version, encoded),
+            _ => return Err(invalid()),
+        };
+        let version: u32 = version.parse().map_err(|_| invalid())?;
+
+        if version != VERSION {
+            return Err(format!("the view string is of version {}, this version only reads \
+                                version {}", version, VERSION));
+        }
+
+        let json = URL_SAFE_NO_PAD.decode(encoded).map_err(|_| invalid())?;
+
+        serde_json::from_slice(&json)
+            .map_err(|err| format!("the view string is invalid: {}", err))
+    }
+
+    /// Sets the options of `config` to show the view. The values are validated along with the
+    /// flags, and the simulation is restored from `config.restore_state` once created.
+    pub fn apply(self, config: &mut Config) {
+        config.camera_position = self.camera_position;
+        config.camera_target = self.camera_target;
+        config.camera_up = self.camera_up;
+        config.coord_system = self.coord_system;
+        config.fov = self.fov;
+        config.ortho = self.ortho;
+        config.ortho_size = self.ortho_size;
+        config.light_direction = self.light_direction;
+        config.light_color = self.light_color;
+        config.light_intensity = self.light_intensity;
+        config.exposure = self.exposure;
+        config.bounces = self.bounces;
+        config.heatmap = self.heatmap;
+        config.flip_y = self.flip_y;
+        config.frag = self.frag;
+        config.spirv_frag = self.spirv_frag;
+        config.frag_entry = self.frag_entry;
+        config.defines = self.defines;
+        config.restore_state = Some(self.simulation);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn view() -> View {
+        View::capture(&Config::default(), &Simulation::new(&Config::default()))
+    }
+
+    #[test]
+    fn view_round_trips() {
+        let text = view().encode();
+
+        assert!(text.starts_with("smv3."));
+        // The encoding is URL-safe and unpadded.
+        assert!(!text.contains(|c| c == '+' || c == '/' || c == '='));
+        assert_eq!(View::decode(&text).map(|view| view.encode()), Ok(text.clone()));
+        assert_eq!(View::decode(&format!("  {}\n", text)).map(|view| view.encode()), Ok(text));
+    }
+
+    #[test]
+    fn decode_rejects_other_strings() {
+        let text = view().encode();
+        let encoded = &text["smv3.".len() ..];
+
+        assert!(View::decode(&format!("xyz3.{}", encoded)).is_err());
+        assert!(View::decode(&format!("smv.{}", encoded)).is_err());
+        assert!(View::decode(encoded).is_err());
+        assert!(View::decode("smv3.").is_err());
+        // Standard base64 and padding aren't read.
+        assert!(View::decode(&format!("smv3.{}+", encoded)).is_err());
+        assert!(View::decode(&format!("smv3.{}=", encoded)).is_err());
+        assert_eq!(View::decode(&format!("smv2.{}", encoded)).map(|view| view.encode()),
+                   Err("the view string is of version 2, this version only reads version 3"
+                       .to_string()));
+    }
+}
@@ -5,6 +5,54 @@
 
 layout(set = 0, binding = 0) uniform Data {
     vec2 resolution;
+    // The offset of the sample position within the pixel, in pixels.
+    vec2 jitter;
+    // Shadertoy's `iMouse`, in the pixels of `frag_coord()`: where the cursor was last while the
+    // left button was held, and where the button was pressed, negated while it is released.
+    vec4 mouse;
+    // Where the camera is and the unit vector it looks along. Each is followed by a scalar, which
+    // keeps the block free of padding.
+    vec3 camera_position;
+    float time;
+    vec3 camera_direction;
+    float dt;
+    // The unit vector towards a directional light, its intensity and its color, for shaders that
+    // shade. The built-in scene doesn't.
+    vec3 light_direction;
+    float light_intensity;
+    vec3 light_color;
+    uint debug_tint;
+    float refresh_hz;
+    float exposure;
+    // The number of frames rendered before.
+    uint frame;
+    // Whether the cost of each pixel is shown as a heatmap instead of its color.
+    uint heatmap;
+    // Whether `frag_coord()` and `mouse` have their origin at the bottom-left, with `--flip-y`.
+    uint flip_y;
+    // The objects of `--reference` shown, 1 for the sphere and 2 for the ground plane, the height
+    // of the plane and the radius and center of the sphere.
+    uint reference;
+    float ground_height;
+    float reference_radius;
+    vec3 reference_center;
+    // The real time since the start in seconds, which goes on while `time` is paused or scaled,
+    // for effects that shouldn't stop with the animation. Fixed like `time` without a window.
+    float real_time;
+    // The direction that is up on the screen, as far as it is perpendicular to `camera_direction`,
+    // and the field of view across the diagonal of the image, in radians.
+    vec3 camera_up;
+    float fov;
+    // How many times shaders that trace secondary rays, for reflections or refractions, may let
+    // a ray bounce, up to 16. The built-in scene doesn't.
+    uint bounces;
+    // The local time of day in hours, from 0 at midnight to 24, with `--time-of-day`, and -1
+    // without.
+    float time_of_day;
+    // Whether the camera is orthographic with `--ortho`, its rays all along `camera_direction`
+    // from a plane through `camera_position`, and how wide the image is across the diagonal then.
+    uint orthographic;
+    float ortho_size;
 } uniforms;
 
 layout(location = 0) in vec2 position;
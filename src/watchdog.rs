@@ -0,0 +1,80 @@
+use std::process;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
+
+/// How often the watchdog checks on the frames.
+const POLL_INTERVAL_MS: u64 = 100;
+
+struct Progress {
+    /// The number of the frame being rendered, and when it started.
+    frame: Option<(u64, Instant)>,
+    /// Whether the current frame has been reported already.
+    reported: bool,
+}
+
+/// Reports frames that take so long that the GPU is likely hung, typically on a shader stuck in
+/// a loop with far too many iterations.
+///
+/// A hung GPU shows up as a frame that blocks while acquiring a swapchain image or waiting for
+/// the previous frame, so the time is measured on the CPU around the whole frame.
+pub struct Watchdog {
+    progress: Arc<Mutex<Progress>>,
+    frame: u64,
+}
+
+impl Watchdog {
+    /// Starts the thread reporting frames that take longer than `timeout`, aborting the process
+    /// if `abort` is set.
+    pub fn new(timeout: Duration, abort: bool) -> Watchdog {
+        let progress = Arc::new(Mutex::new(Progress { frame: None, reported: false }));
+        let weak = Arc::downgrade(&progress);
+
+        // The thread stops once the watchdog is dropped.
+        thread::spawn(move || {
+            while let Some(progress) = weak.upgrade() {
+                {
+                    let mut progress = progress.lock().unwrap();
+
+                    if let Some((frame, started)) = progress.frame {
+                        if !progress.reported && started.elapsed() > timeout {
+                            progress.reported = true;
+
+                            warn!("frame {} hasn't completed after {} s, the GPU is likely hung \
+                                   on the work of this frame or the one before", frame,
+                                  timeout.as_secs() as f32 +
+                                  timeout.subsec_nanos() as f32 / 1_000_000_000.0);
+
+                            if abort {
+                                error!("aborting");
+                                process::abort();
+                            }
+                        }
+                    }
+                }
+
+                drop(progress);
+                thread::sleep(Duration::from_millis(POLL_INTERVAL_MS));
+            }
+        });
+
+        Watchdog {
+            progress: progress,
+            frame: 0,
+        }
+    }
+
+    pub fn frame_started(&mut self) {
+        let mut progress = self.progress.lock().unwrap();
+
+        progress.frame = Some((self.frame, Instant::now()));
+        progress.reported = false;
+    }
+
+    pub fn frame_finished(&mut self) {
+        self.progress.lock().unwrap().frame = None;
+        self.frame += 1;
+    }
+}
@@ -0,0 +1,119 @@
+extern crate image;
+
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Command;
+
+use image::RgbaImage;
+
+const WIDTH: u32 = 320;
+const HEIGHT: u32 = 256;
+
+/// Reads a tuning parameter of the comparison from the environment.
+fn parameter<T: std::str::FromStr>(name: &str, default: T) -> T {
+    env::var(name).ok()
+        .map(|value| value.parse().unwrap_or_else(|_| panic!("invalid value of `{}`", name)))
+        .unwrap_or(default)
+}
+
+/// Averages every pixel with its neighbours within `radius`, to soften single-pixel differences.
+fn blur(image: &RgbaImage, radius: u32) -> RgbaImage {
+    if radius == 0 {
+        return image.clone();
+    }
+
+    let (width, height) = image.dimensions();
+
+    RgbaImage::from_fn(width, height, |x, y| {
+        let mut sum = [0u32; 4];
+        let mut count = 0;
+
+        for ny in y.saturating_sub(radius) .. (y + radius + 1).min(height) {
+            for nx in x.saturating_sub(radius) .. (x + radius + 1).min(width) {
+                for (channel, value) in sum.iter_mut().zip(image.get_pixel(nx, ny).data.iter()) {
+                    *channel += *value as u32;
+                }
+
+                count += 1;
+            }
+        }
+
+        image::Rgba([(sum[0] / count) as u8, (sum[1] / count) as u8,
+                     (sum[2] / count) as u8, (sum[3] / count) as u8])
+    })
+}
+
+/// Returns the fraction of pixels with a channel differing by more than `tolerance`.
+fn difference(expected: &RgbaImage, actual: &RgbaImage, tolerance: u8) -> f64 {
+    let differing = expected.pixels().zip(actual.pixels()).filter(|&(expected, actual)| {
+        expected.data.iter().zip(actual.data.iter()).any(|(&expected, &actual)| {
+            (expected as i16 - actual as i16).abs() > tolerance as i16
+        })
+    }).count();
+
+    differing as f64 / (expected.width() * expected.height()) as f64
+}
+
+/// Renders the default scene headlessly and compares it against `tests/golden/default.png`.
+///
+/// The comparison is tuned with environment variables, to account for differences between GPUs:
+///
+/// - `SUBMANIFOLD_GOLDEN_TOLERANCE`: the largest difference of a channel that is ignored
+///   [default: 8]
+/// - `SUBMANIFOLD_GOLDEN_THRESHOLD`: the fraction of pixels allowed to exceed the tolerance
+///   [default: 0.001]
+/// - `SUBMANIFOLD_GOLDEN_BLUR`: the radius of a blur applied to both images before comparing
+///   [default: 0]
+///
+/// With `SUBMANIFOLD_GOLDEN_UPDATE=1`, the rendered image is stored as the new golden image
+/// instead. Without it, a missing golden image fails the test.
+///
+/// Ignored until the golden image is committed, render it with
+/// `SUBMANIFOLD_GOLDEN_UPDATE=1 cargo test --test golden -- --ignored`.
+#[test]
+#[ignore]
+fn golden_image() {
+    let golden_path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden/default.png");
+    let output_path = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("golden_default.png");
+
+    let status = Command::new(env!("CARGO_BIN_EXE_submanifold"))
+        .arg("--width").arg(WIDTH.to_string())
+        .arg("--height").arg(HEIGHT.to_string())
+        .arg("--headless").arg(&output_path)
+        .status()
+        .expect("failed to run submanifold");
+
+    assert!(status.success(), "headless rendering failed: {}", status);
+
+    if env::var("SUBMANIFOLD_GOLDEN_UPDATE").ok() == Some("1".into()) {
+        fs::create_dir_all(golden_path.parent().unwrap())
+            .expect("failed to create the golden image directory");
+        fs::copy(&output_path, &golden_path).expect("failed to update the golden image");
+        println!("updated the golden image `{}`", golden_path.display());
+        return;
+    }
+
+    assert!(golden_path.exists(),
+            "the golden image `{}` is missing, render it with `SUBMANIFOLD_GOLDEN_UPDATE=1`, \
+             see `{}`",
+            golden_path.display(), output_path.display());
+
+    let tolerance = parameter("SUBMANIFOLD_GOLDEN_TOLERANCE", 8u8);
+    let threshold = parameter("SUBMANIFOLD_GOLDEN_THRESHOLD", 0.001f64);
+    let radius = parameter("SUBMANIFOLD_GOLDEN_BLUR", 0u32);
+
+    let expected = image::open(&golden_path).expect("failed to open the golden image").to_rgba();
+    let actual = image::open(&output_path).expect("failed to open the rendered image").to_rgba();
+
+    assert_eq!(expected.dimensions(), actual.dimensions(),
+               "the rendered image has different dimensions than the golden image");
+
+    let difference = difference(&blur(&expected, radius), &blur(&actual, radius), tolerance);
+
+    assert!(difference <= threshold,
+            "{:.3}% of the pixels differ from the golden image (the threshold is {:.3}%), \
+             see `{}`",
+            difference * 100.0, threshold * 100.0, output_path.display());
+}